@@ -0,0 +1,163 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
+
+/// Member of the daedalus class bound to a field or tuple element
+struct MemberAttribute {
+    name: String,
+    array: Option<u32>,
+    element: Option<syn::Index>,
+}
+
+/// Key value pairs of all `#[daedalus(..)]` attributes
+fn parse_daedalus_attribute(attr: &Attribute) -> syn::Result<Vec<(String, Lit)>> {
+    let list = match attr.parse_meta()? {
+        Meta::List(list) => list,
+        meta => return Err(syn::Error::new(meta.span(), "Expected #[daedalus(..)]")),
+    };
+    let mut pairs = vec![];
+    for nested in list.nested.iter() {
+        match nested {
+            NestedMeta::Meta(Meta::NameValue(pair)) => match pair.path.get_ident() {
+                Some(ident) => pairs.push((ident.to_string(), pair.lit.clone())),
+                None => return Err(syn::Error::new(pair.path.span(), "Expected a key")),
+            },
+            _ => return Err(syn::Error::new(nested.span(), "Expected key = value")),
+        }
+    }
+    Ok(pairs)
+}
+
+fn lit_str(lit: &Lit) -> syn::Result<String> {
+    match lit {
+        Lit::Str(value) => Ok(value.value()),
+        _ => Err(syn::Error::new(lit.span(), "Expected a string")),
+    }
+}
+
+fn lit_int(lit: &Lit) -> syn::Result<u32> {
+    match lit {
+        Lit::Int(value) => value.base10_parse(),
+        _ => Err(syn::Error::new(lit.span(), "Expected an integer")),
+    }
+}
+
+fn is_daedalus(attr: &Attribute) -> bool {
+    attr.path.is_ident("daedalus")
+}
+
+fn parse_class_name(input: &DeriveInput) -> syn::Result<String> {
+    for attr in input.attrs.iter().filter(|attr| is_daedalus(attr)) {
+        if let Some((key, lit)) = parse_daedalus_attribute(attr)?.into_iter().next() {
+            return match key.as_str() {
+                "class" => lit_str(&lit),
+                _ => Err(syn::Error::new(lit.span(), format!("Unknown key {}", key))),
+            };
+        }
+    }
+    Err(syn::Error::new(
+        input.ident.span(),
+        "Missing #[daedalus(class = \"..\")]",
+    ))
+}
+
+fn parse_member(attr: &Attribute) -> syn::Result<MemberAttribute> {
+    let mut name = None;
+    let mut array = None;
+    let mut element = None;
+    for (key, lit) in parse_daedalus_attribute(attr)? {
+        match key.as_str() {
+            "name" => name = Some(lit_str(&lit)?),
+            "array" => array = Some(lit_int(&lit)?),
+            "element" => element = Some(syn::Index::from(lit_int(&lit)? as usize)),
+            _ => return Err(syn::Error::new(lit.span(), format!("Unknown key {}", key))),
+        }
+    }
+    match name {
+        Some(name) => Ok(MemberAttribute {
+            name,
+            array,
+            element,
+        }),
+        None => Err(syn::Error::new(attr.span(), "Missing name = \"..\"")),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let class_name = parse_class_name(&input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new(ident.span(), "Expected named fields")),
+        },
+        _ => return Err(syn::Error::new(ident.span(), "Expected a struct")),
+    };
+
+    let mut members = vec![];
+    let mut layouts = vec![];
+    for field in fields.iter() {
+        let field_ident = field.ident.as_ref().unwrap();
+        for attr in field.attrs.iter().filter(|attr| is_daedalus(attr)) {
+            let member = parse_member(attr)?;
+            let name = &member.name;
+            let path = match &member.element {
+                Some(element) => quote!(#field_ident.#element),
+                None => quote!(#field_ident),
+            };
+            let array = match member.array {
+                Some(array) => quote!(Some(#array)),
+                None => quote!(None),
+            };
+            members.push(quote! {
+                ::daedalus::stdlib::members::Member {
+                    name: #name,
+                    get: |object, array_index| {
+                        ::daedalus::stdlib::members::MemberField::get_value(&object.#path, array_index)
+                    },
+                    set: |object, array_index, value| {
+                        ::daedalus::stdlib::members::MemberField::set_value(&mut object.#path, array_index, value)
+                    },
+                }
+            });
+            layouts.push(quote! {
+                ::daedalus::stdlib::members::MemberLayout::of(#name, #array, |object: &Self| &object.#path)
+            });
+        }
+    }
+
+    Ok(quote! {
+        impl ::daedalus::stdlib::members::ClassMembers for #ident {
+            const CLASS_NAME: &'static str = #class_name;
+            const MEMBERS: &'static [::daedalus::stdlib::members::Member<Self>] = &[#(#members),*];
+
+            fn get_layout() -> Vec<::daedalus::stdlib::members::MemberLayout> {
+                vec![#(#layouts),*]
+            }
+        }
+    })
+}
+
+/// Binds fields to the members of a daedalus class.
+///
+/// ```ignore
+/// #[derive(Default, DaedalusClass)]
+/// #[daedalus(class = "C_ITEM")]
+/// pub struct Item {
+///     #[daedalus(name = "DAMAGE", array = 8)]
+///     damage: [i32; DAM_INDEX_MAX],
+///     #[daedalus(name = "INV_ROTX", element = 0)]
+///     #[daedalus(name = "INV_ROTY", element = 1)]
+///     inv_rot: (i32, i32),
+/// }
+/// ```
+#[proc_macro_derive(DaedalusClass, attributes(daedalus))]
+pub fn derive_daedalus_class(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
@@ -0,0 +1,430 @@
+use super::{run_func_with_participants, GameState};
+use crate::stdlib::instances::item::{Categories, Flags};
+use crate::stdlib::instances::*;
+use crate::stdlib::prelude::*;
+use crate::stdlib::GameType;
+use crate::vm::VirtualMachine;
+use zen_memory::Handle;
+
+/// Tunables of the damage calculation, defaults follow the original games
+#[derive(Clone, Debug)]
+pub struct CombatRules {
+    pub game_type: GameType,
+    /// Damage every hit deals at least, unless the victim is immune
+    pub minimal_damage: i32,
+    /// Percentage of the damage dealt when the hit chance roll fails in Gothic 2
+    pub miss_percent: i32,
+    /// Fall height in meters without damage
+    pub fall_safe_height: f32,
+    pub fall_damage_per_meter: f32,
+    /// Script function run on the victim after damage was applied, e.g. `B_ASSESSDAMAGE`
+    pub assess_damage_func: Option<String>,
+}
+
+impl CombatRules {
+    pub fn new(game_type: GameType) -> Self {
+        match game_type {
+            GameType::Gothic1 => Self {
+                game_type,
+                minimal_damage: 1,
+                miss_percent: 0,
+                fall_safe_height: 5.0,
+                fall_damage_per_meter: 10.0,
+                assess_damage_func: None,
+            },
+            GameType::Gothic2 => Self {
+                game_type,
+                minimal_damage: 5,
+                miss_percent: 10,
+                fall_safe_height: 5.0,
+                fall_damage_per_meter: 10.0,
+                assess_damage_func: None,
+            },
+        }
+    }
+    pub fn with_assess_damage_func(mut self, name: &str) -> Self {
+        self.assess_damage_func = Some(name.to_owned());
+        self
+    }
+}
+
+/// Damage after protection, per `DAM_INDEX_*`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DamageResult {
+    pub damage: [i32; DAM_INDEX_MAX],
+    pub total: i32,
+    /// Gothic 1 critical hit or successful Gothic 2 hit chance roll
+    pub critical: bool,
+    /// Ranged attacks can miss completely
+    pub missed: bool,
+    /// `DAM_FLY` throws the victim back
+    pub fly: bool,
+}
+
+/// Talent index into `C_NPC.HITCHANCE` for the weapon
+pub fn get_weapon_talent(weapon: &Item) -> usize {
+    let flags = weapon.get_flags();
+    if flags.intersects(Flags::TwoHandSword | Flags::TwoHandAxe) {
+        NPC_TALENT_2H
+    } else if flags.contains(Flags::CrossBow) {
+        NPC_TALENT_CROSSBOW
+    } else if flags.contains(Flags::Bow) {
+        NPC_TALENT_BOW
+    } else if flags.intersects(Flags::Dagger | Flags::Sword | Flags::Axe) {
+        NPC_TALENT_1H
+    } else {
+        NPC_TALENT_UNKNOWN
+    }
+}
+
+fn is_ranged(weapon: &Item) -> bool {
+    weapon.get_main_flag().contains(Categories::Ff)
+        || weapon.get_flags().intersects(Flags::Bow | Flags::CrossBow)
+}
+
+/// Damage per type of the weapon or the npc itself, `damage_total` is spread over the types if needed
+fn get_base_damage(attacker: &Npc, weapon: Option<&Item>) -> ([i32; DAM_INDEX_MAX], i32) {
+    let mut damage = [0; DAM_INDEX_MAX];
+    let damage_type = match weapon {
+        Some(weapon) => {
+            for (index, value) in damage.iter_mut().enumerate() {
+                *value = weapon.get_damage(index);
+            }
+            if damage.iter().all(|value| *value == 0) {
+                spread(
+                    &mut damage,
+                    weapon.get_damage_type(),
+                    weapon.get_damage_total(),
+                );
+            }
+            weapon.get_damage_type()
+        }
+        None => {
+            for (index, value) in damage.iter_mut().enumerate() {
+                *value = attacker.get_damage(index);
+            }
+            attacker.get_damage_type()
+        }
+    };
+    (damage, damage_type)
+}
+
+/// Adds the amount evenly onto all damage types of the mask, the remainder goes to the first one
+fn spread(damage: &mut [i32; DAM_INDEX_MAX], damage_type: i32, amount: i32) {
+    let indices: Vec<usize> = (0..DAM_INDEX_MAX)
+        .filter(|index| damage_type & (1 << index) != 0)
+        .collect();
+    if indices.is_empty() {
+        return;
+    }
+    let share = amount / indices.len() as i32;
+    for index in indices.iter() {
+        damage[*index] += share;
+    }
+    damage[indices[0]] += amount - share * indices.len() as i32;
+}
+
+/// Subtracts the protection per type, negative protection means immune
+fn apply_protection(
+    damage: &[i32; DAM_INDEX_MAX],
+    damage_type: i32,
+    victim: &Npc,
+) -> [i32; DAM_INDEX_MAX] {
+    let mut result = [0; DAM_INDEX_MAX];
+    for (index, value) in result.iter_mut().enumerate() {
+        if damage_type & (1 << index) == 0 {
+            continue;
+        }
+        let protection = victim.get_protection(index);
+        if protection >= 0 {
+            *value = (damage[index] - protection).max(0);
+        }
+    }
+    result
+}
+
+fn is_immune(damage_type: i32, victim: &Npc) -> bool {
+    (0..DAM_INDEX_MAX)
+        .filter(|index| damage_type & (1 << index) != 0)
+        .all(|index| victim.get_protection(index) < 0)
+}
+
+impl DamageResult {
+    fn finish(
+        mut self,
+        rules: &CombatRules,
+        damage_type: i32,
+        victim: &Npc,
+        minimal: bool,
+    ) -> Self {
+        self.total = self.damage.iter().sum();
+        if minimal && !self.missed && !is_immune(damage_type, victim) {
+            self.total = self.total.max(rules.minimal_damage);
+        }
+        self.fly = damage_type & DAM_FLY != 0 && !self.missed;
+        self
+    }
+}
+
+/// Damage of a hit. `roll` in `0..100` is compared against the hit chance of the weapon talent,
+/// the host provides it so results stay reproducible.
+pub fn compute_hit_damage(
+    rules: &CombatRules,
+    attacker: &Npc,
+    weapon: Option<&Item>,
+    victim: &Npc,
+    roll: i32,
+) -> DamageResult {
+    let (mut damage, damage_type) = get_base_damage(attacker, weapon);
+    let ranged = weapon.is_some_and(is_ranged);
+    let hit_chance = match weapon {
+        Some(weapon) => attacker.get_hit_chance(get_weapon_talent(weapon)),
+        // fists and monsters always hit
+        None => 100,
+    };
+    let success = roll < hit_chance;
+    let mut result = DamageResult::default();
+    match rules.game_type {
+        GameType::Gothic1 => {
+            // critical hits double the weapon damage, ranged attacks miss instead
+            if ranged {
+                result.missed = !success;
+            } else if success && weapon.is_some() {
+                result.critical = true;
+                damage.iter_mut().for_each(|value| *value *= 2);
+            }
+        }
+        GameType::Gothic2 => {
+            result.critical = success;
+            result.missed = ranged && !success;
+        }
+    }
+    if result.missed {
+        return result.finish(rules, damage_type, victim, false);
+    }
+    let attribute = match ranged {
+        true => attacker.get_attribute(ATR_DEXTERITY),
+        false => attacker.get_attribute(ATR_STRENGTH),
+    };
+    spread(&mut damage, damage_type, attribute);
+    result.damage = apply_protection(&damage, damage_type, victim);
+    if rules.game_type == GameType::Gothic2 && !ranged && !success {
+        result
+            .damage
+            .iter_mut()
+            .for_each(|value| *value = *value * rules.miss_percent / 100);
+    }
+    result.finish(rules, damage_type, victim, true)
+}
+
+/// Damage of a fall from `height` meters, reduced by `PROT_FALL`
+pub fn compute_fall_damage(rules: &CombatRules, victim: &Npc, height: f32) -> DamageResult {
+    let mut damage = [0; DAM_INDEX_MAX];
+    damage[DAM_INDEX_FALL] =
+        ((height - rules.fall_safe_height).max(0.0) * rules.fall_damage_per_meter) as i32;
+    DamageResult {
+        damage: apply_protection(&damage, DAM_FALL, victim),
+        ..Default::default()
+    }
+    .finish(rules, DAM_FALL, victim, false)
+}
+
+/// Damage of the magic barrier, reduced by `PROT_BARRIER`
+pub fn compute_barrier_damage(rules: &CombatRules, victim: &Npc, amount: i32) -> DamageResult {
+    let mut damage = [0; DAM_INDEX_MAX];
+    damage[DAM_INDEX_BARRIER] = amount;
+    DamageResult {
+        damage: apply_protection(&damage, DAM_BARRIER, victim),
+        ..Default::default()
+    }
+    .finish(rules, DAM_BARRIER, victim, false)
+}
+
+impl GameState {
+    /// Computes the damage of a hit with the equipped melee or ranged weapon
+    pub fn compute_hit_damage(
+        &self,
+        rules: &CombatRules,
+        attacker: &Handle,
+        victim: &Handle,
+        ranged: bool,
+        roll: i32,
+    ) -> Option<DamageResult> {
+        let slot = match ranged {
+            true => super::EquipSlot::RangedWeapon,
+            false => super::EquipSlot::MeleeWeapon,
+        };
+        let weapon = self
+            .get_inv_of(attacker)
+            .and_then(|inventory| inventory.get_equipped(slot))
+            .and_then(|weapon| self.items.get(&weapon));
+        Some(compute_hit_damage(
+            rules,
+            self.npcs.get(attacker)?,
+            weapon,
+            self.npcs.get(victim)?,
+            roll,
+        ))
+    }
+    /// Subtracts the damage from the hit points of the victim and runs the assess damage function
+    /// of the rules with `self` as the victim and `other` as the attacker
+    pub fn apply_damage(
+        virtual_machine: &mut VirtualMachine,
+        rules: &CombatRules,
+        attacker: Option<Handle>,
+        victim: Handle,
+        result: &DamageResult,
+    ) -> Result<(), String> {
+        let npc = match virtual_machine.get_mut_game_state().npcs.get_mut(&victim) {
+            Some(npc) => npc,
+            None => return Err("Victim does not exist".to_owned()),
+        };
+        let hit_points = (npc.get_attribute(ATR_HITPOINTS) - result.total).max(0);
+        npc.set_attribute(ATR_HITPOINTS, hit_points);
+
+        let func = match &rules.assess_damage_func {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let func = match virtual_machine
+            .get_file()
+            .get_sym_table()
+            .get_symbol_index_by_name(func)
+        {
+            Some(func) => func,
+            None => return Err(format!("Function {} not found", func)),
+        };
+        run_func_with_participants(virtual_machine, func, victim, attacker)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sword() -> Item {
+        let mut weapon = Item::new();
+        weapon.set_main_flag(Categories::Nf.into());
+        weapon.set_flags(Flags::Sword.into());
+        weapon.set_damage_type(DAM_EDGE);
+        weapon.set_damage(DAM_INDEX_EDGE, 20);
+        weapon
+    }
+
+    fn bow() -> Item {
+        let mut weapon = Item::new();
+        weapon.set_main_flag(Categories::Ff.into());
+        weapon.set_flags(Flags::Bow.into());
+        weapon.set_damage_type(DAM_POINT);
+        weapon.set_damage(DAM_INDEX_POINT, 20);
+        weapon
+    }
+
+    fn attacker() -> Npc {
+        let mut npc = Npc::new();
+        npc.set_attribute(ATR_STRENGTH, 30);
+        npc.set_attribute(ATR_DEXTERITY, 30);
+        npc.set_hit_chance(NPC_TALENT_1H, 50);
+        npc.set_hit_chance(NPC_TALENT_BOW, 50);
+        npc
+    }
+
+    fn victim() -> Npc {
+        let mut npc = Npc::new();
+        npc.set_protection(PROT_EDGE, 10);
+        npc.set_protection(PROT_POINT, 10);
+        npc.set_protection(PROT_FALL, 10);
+        npc
+    }
+
+    #[test]
+    fn gothic2_hit() {
+        let rules = CombatRules::new(GameType::Gothic2);
+        let result = compute_hit_damage(&rules, &attacker(), Some(&sword()), &victim(), 10);
+        assert!(result.critical);
+        assert_eq!(result.damage[DAM_INDEX_EDGE], 40);
+        assert_eq!(result.total, 40);
+    }
+
+    #[test]
+    fn gothic2_failed_roll_deals_minimal_damage() {
+        let rules = CombatRules::new(GameType::Gothic2);
+        let result = compute_hit_damage(&rules, &attacker(), Some(&sword()), &victim(), 90);
+        assert!(!result.critical);
+        assert_eq!(result.damage[DAM_INDEX_EDGE], 4);
+        assert_eq!(result.total, rules.minimal_damage);
+    }
+
+    #[test]
+    fn gothic1_critical_hit_doubles_weapon_damage() {
+        let rules = CombatRules::new(GameType::Gothic1);
+        let critical = compute_hit_damage(&rules, &attacker(), Some(&sword()), &victim(), 10);
+        assert!(critical.critical);
+        assert_eq!(critical.total, 60);
+        let normal = compute_hit_damage(&rules, &attacker(), Some(&sword()), &victim(), 90);
+        assert!(!normal.critical);
+        assert_eq!(normal.total, 40);
+    }
+
+    #[test]
+    fn failed_ranged_roll_misses() {
+        for game_type in [GameType::Gothic1, GameType::Gothic2].iter() {
+            let rules = CombatRules::new(*game_type);
+            let result = compute_hit_damage(&rules, &attacker(), Some(&bow()), &victim(), 90);
+            assert!(result.missed);
+            assert_eq!(result.total, 0);
+        }
+    }
+
+    #[test]
+    fn immune_victim_takes_no_damage() {
+        let mut victim = victim();
+        victim.set_protection(PROT_EDGE, IMMUNE);
+        for game_type in [GameType::Gothic1, GameType::Gothic2].iter() {
+            let rules = CombatRules::new(*game_type);
+            let result = compute_hit_damage(&rules, &attacker(), Some(&sword()), &victim, 10);
+            assert_eq!(result.total, 0);
+        }
+    }
+
+    #[test]
+    fn fist_uses_npc_damage() {
+        let mut attacker = attacker();
+        attacker.set_damage_type(DAM_BLUNT | DAM_FLY);
+        attacker.set_damage(DAM_INDEX_BLUNT, 5);
+        let rules = CombatRules::new(GameType::Gothic2);
+        let result = compute_hit_damage(&rules, &attacker, None, &victim(), 99);
+        assert!(result.fly);
+        // strength is spread over blunt and fly
+        assert_eq!(result.damage[DAM_INDEX_BLUNT], 20);
+        assert_eq!(result.damage[DAM_INDEX_FLY], 15);
+        assert_eq!(result.total, 35);
+    }
+
+    #[test]
+    fn fall_damage_above_safe_height() {
+        let rules = CombatRules::new(GameType::Gothic2);
+        assert_eq!(compute_fall_damage(&rules, &victim(), 4.0).total, 0);
+        assert_eq!(compute_fall_damage(&rules, &victim(), 8.0).total, 20);
+    }
+
+    #[test]
+    fn barrier_damage_ignores_minimal_damage() {
+        let mut victim = victim();
+        victim.set_protection(PROT_BARRIER, 10);
+        let rules = CombatRules::new(GameType::Gothic1);
+        assert_eq!(compute_barrier_damage(&rules, &victim, 5).total, 0);
+        assert_eq!(compute_barrier_damage(&rules, &victim, 50).total, 40);
+    }
+
+    #[test]
+    fn weapon_talents() {
+        assert_eq!(get_weapon_talent(&sword()), NPC_TALENT_1H);
+        assert_eq!(get_weapon_talent(&bow()), NPC_TALENT_BOW);
+        let mut weapon = Item::new();
+        weapon.set_flags(Flags::TwoHandAxe.into());
+        assert_eq!(get_weapon_talent(&weapon), NPC_TALENT_2H);
+        assert_eq!(get_weapon_talent(&Item::new()), NPC_TALENT_UNKNOWN);
+    }
+}
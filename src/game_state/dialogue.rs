@@ -0,0 +1,176 @@
+use super::{run_func_with_participants, GameState};
+use crate::stdlib::prelude::*;
+use crate::vm::VirtualMachine;
+use zen_memory::Handle;
+
+/// Info the player can currently pick in a dialogue
+#[derive(Clone, Debug)]
+pub struct DialogueChoice {
+    pub info: Handle,
+    pub info_symbol: usize,
+    pub nr: i32,
+    pub description: String,
+    /// Important infos are started by the npc without being picked
+    pub important: bool,
+    /// Index into the sub-choices of the info if this is an `Info_AddChoice` entry
+    pub sub_choice: Option<usize>,
+}
+
+impl GameState {
+    /// Infos whose `npc` is the instance of the npc, in creation order
+    pub fn get_infos_of_npc(&self, npc: &Handle) -> Vec<Handle> {
+        let npc_symbol = match self.npcs.get(npc) {
+            Some(npc) => npc.get_instance_symbol(),
+            None => return vec![],
+        };
+        self.infos
+            .iter()
+            .filter(|(_, info)| {
+                info.get_npc()
+                    .is_some_and(|value| value.get() as usize == npc_symbol)
+            })
+            .map(|(handle, _)| handle)
+            .collect()
+    }
+    /// Info of the npc whose sub-choice menu is open
+    pub fn get_active_choice_info(&self, npc: &Handle) -> Option<Handle> {
+        self.get_infos_of_npc(npc).into_iter().find(|handle| {
+            self.infos
+                .get(handle)
+                .is_some_and(|info| !info.get_sub_choices().is_empty())
+        })
+    }
+    /// Sub-choices of the info, the last added one is shown first like in the game
+    pub fn get_sub_choices(&self, info: &Handle) -> Vec<DialogueChoice> {
+        let info_handle = *info;
+        let info = match self.infos.get(info) {
+            Some(info) => info,
+            None => return vec![],
+        };
+        let count = info.get_sub_choices().len();
+        info.get_sub_choices()
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, choice)| DialogueChoice {
+                info: info_handle,
+                info_symbol: info.get_instance_symbol(),
+                nr: (count - index) as i32,
+                description: choice.get_text().to_owned(),
+                important: false,
+                sub_choice: Some(index),
+            })
+            .collect()
+    }
+    /// Infos of the npc the player can pick, sorted by `nr`.
+    /// Told infos are skipped unless permanent, conditions run with `self` as the npc and `other` as the player.
+    /// While a sub-choice menu is open only its choices are returned.
+    pub fn get_dialogue_choices(
+        virtual_machine: &mut VirtualMachine,
+        npc: Handle,
+        player: Handle,
+    ) -> Vec<DialogueChoice> {
+        let game_state = virtual_machine.get_game_state();
+        if let Some(info) = game_state.get_active_choice_info(&npc) {
+            return game_state.get_sub_choices(&info);
+        }
+        let candidates: Vec<(Handle, usize, Option<usize>)> = game_state
+            .get_infos_of_npc(&npc)
+            .into_iter()
+            .filter_map(|handle| {
+                let info = game_state.infos.get(&handle)?;
+                let symbol = info.get_instance_symbol();
+                let told =
+                    game_state.is_info_told(symbol) || game_state.knows_info(&player, symbol);
+                match told && info.get_permanent().is_none() {
+                    true => None,
+                    false => Some((
+                        handle,
+                        symbol,
+                        info.get_condition().map(|func| func.get() as usize),
+                    )),
+                }
+            })
+            .collect();
+
+        let mut choices = vec![];
+        for (handle, symbol, condition) in candidates {
+            if let Some(condition) = condition {
+                match run_func_with_participants(virtual_machine, condition, npc, Some(player)) {
+                    Ok(0) => continue,
+                    Ok(_) => (),
+                    Err(error) => {
+                        log::error!("Condition of info {} failed: {}", symbol, error);
+                        continue;
+                    }
+                }
+            }
+            let info = match virtual_machine.get_game_state().infos.get(&handle) {
+                Some(info) => info,
+                None => continue,
+            };
+            choices.push(DialogueChoice {
+                info: handle,
+                info_symbol: symbol,
+                nr: info.get_nr().map_or(0, |nr| nr.get()),
+                description: info.get_description().to_owned(),
+                important: info.get_important().is_some(),
+                sub_choice: None,
+            });
+        }
+        choices.sort_by_key(|choice| choice.nr);
+        choices
+    }
+    /// Marks the info as told and known by the player and runs its `information` function
+    pub fn choose_info(
+        virtual_machine: &mut VirtualMachine,
+        npc: Handle,
+        player: Handle,
+        info: Handle,
+    ) -> Result<(), String> {
+        let game_state = virtual_machine.get_mut_game_state();
+        let (symbol, information) = match game_state.infos.get(&info) {
+            Some(info) => (info.get_instance_symbol(), info.get_information()),
+            None => return Err("Info does not exist".to_owned()),
+        };
+        game_state.set_info_told(symbol, true);
+        game_state.set_info_known(&player, symbol, true);
+        if let Some(information) = information {
+            let information = information.get() as usize;
+            run_func_with_participants(virtual_machine, information, npc, Some(player))?;
+        }
+        Ok(())
+    }
+    /// Runs the function of the sub-choice, the scripts close the menu with `Info_ClearChoices`
+    pub fn choose_sub_choice(
+        virtual_machine: &mut VirtualMachine,
+        npc: Handle,
+        player: Handle,
+        info: Handle,
+        index: usize,
+    ) -> Result<(), String> {
+        let func_sym = match virtual_machine.get_game_state().infos.get(&info) {
+            Some(info) => match info.get_sub_choices().get(index) {
+                Some(choice) => choice.get_func_sym(),
+                None => return Err(format!("Info has no sub-choice {}", index)),
+            },
+            None => return Err("Info does not exist".to_owned()),
+        };
+        run_func_with_participants(virtual_machine, func_sym as usize, npc, Some(player))?;
+        Ok(())
+    }
+    /// Picks an entry returned by `get_dialogue_choices`
+    pub fn choose_dialogue_choice(
+        virtual_machine: &mut VirtualMachine,
+        npc: Handle,
+        player: Handle,
+        choice: &DialogueChoice,
+    ) -> Result<(), String> {
+        match choice.sub_choice {
+            Some(index) => {
+                Self::choose_sub_choice(virtual_machine, npc, player, choice.info, index)
+            }
+            None => Self::choose_info(virtual_machine, npc, player, choice.info),
+        }
+    }
+}
@@ -1,18 +1,34 @@
 use zen_memory::Handle;
+
+pub type HandleFunc = Box<dyn FnMut(Handle) + Send>;
+pub type InsertNpcFunc = Box<dyn FnMut(Handle, &str) + Send>;
+pub type CreateInvItemFunc = Box<dyn FnMut(Handle, Handle) + Send>;
+pub type GetDayFunc = Box<dyn FnMut() -> i32 + Send>;
+pub type DayFunc = Box<dyn FnMut(i32) + Send>;
+pub type RoutineChangedFunc = Box<dyn FnMut(Handle, usize, &str) + Send>;
+pub type TopicFunc = Box<dyn FnMut(&str) + Send>;
+pub type TopicEntryFunc = Box<dyn FnMut(&str, &str) + Send>;
+
 #[derive(Default)]
-pub struct GameExternals<'a> {
-    pub insert_npc: Option<&'a dyn Fn(Handle, &str)>,
-    pub post_insert_npc: Option<&'a dyn Fn(Handle)>,
-    pub remove_npc: Option<&'a dyn Fn(Handle)>,
-    pub insert_item: Option<&'a dyn Fn(Handle)>,
-    pub create_inv_item: Option<&'a dyn Fn(Handle, Handle)>,
-    pub get_day: Option<&'a dyn Fn() -> i32>,
-    pub log_create_topic: Option<&'a dyn Fn(&str)>,
-    pub log_set_topic_status: Option<&'a dyn Fn(&str)>,
-    pub log_add_entry: Option<&'a dyn Fn(&str, &str)>,
+pub struct GameExternals {
+    pub insert_npc: Option<InsertNpcFunc>,
+    pub post_insert_npc: Option<HandleFunc>,
+    pub remove_npc: Option<HandleFunc>,
+    pub insert_item: Option<HandleFunc>,
+    pub create_inv_item: Option<CreateInvItemFunc>,
+    /// Overrides the day of the world clock
+    #[deprecated(
+        note = "the day is kept by the world clock, see `GameState::get_mut_world_clock`"
+    )]
+    pub get_day: Option<GetDayFunc>,
+    pub new_day: Option<DayFunc>,
+    pub routine_changed: Option<RoutineChangedFunc>,
+    pub log_create_topic: Option<TopicFunc>,
+    pub log_set_topic_status: Option<TopicFunc>,
+    pub log_add_entry: Option<TopicEntryFunc>,
 }
 
-impl<'a> GameExternals<'a> {
+impl GameExternals {
     pub fn new() -> Self {
         Default::default()
     }
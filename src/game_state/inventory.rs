@@ -0,0 +1,546 @@
+use super::{run_func_with, GameState};
+use crate::snapshot::{Persist, SnapshotReader, SnapshotWriter};
+use crate::stdlib::instances::item::{Categories, Flags};
+use crate::stdlib::prelude::*;
+use crate::vm::symbol_values::InstanceData;
+use crate::vm::VirtualMachine;
+use enumflags2::BitFlags;
+use std::collections::HashMap;
+use zen_memory::Handle;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EquipSlot {
+    MeleeWeapon,
+    RangedWeapon,
+    Armor,
+    LeftRing,
+    RightRing,
+    Amulet,
+    Belt,
+}
+
+impl EquipSlot {
+    pub const ALL: [EquipSlot; 7] = [
+        EquipSlot::MeleeWeapon,
+        EquipSlot::RangedWeapon,
+        EquipSlot::Armor,
+        EquipSlot::LeftRing,
+        EquipSlot::RightRing,
+        EquipSlot::Amulet,
+        EquipSlot::Belt,
+    ];
+
+    /// Slots the item fits into, both ring slots for rings
+    pub fn of_item(item: &Item) -> &'static [EquipSlot] {
+        let flags = item.get_flags();
+        let categories = item.get_main_flag();
+        if flags.contains(Flags::Ring) {
+            &[EquipSlot::LeftRing, EquipSlot::RightRing]
+        } else if flags.contains(Flags::Amulet) {
+            &[EquipSlot::Amulet]
+        } else if flags.contains(Flags::Belt) {
+            &[EquipSlot::Belt]
+        } else if flags.intersects(Flags::Bow | Flags::CrossBow)
+            || categories.contains(Categories::Ff)
+        {
+            &[EquipSlot::RangedWeapon]
+        } else if flags.intersects(
+            Flags::Dagger | Flags::Sword | Flags::Axe | Flags::TwoHandSword | Flags::TwoHandAxe,
+        ) || categories.contains(Categories::Nf)
+        {
+            &[EquipSlot::MeleeWeapon]
+        } else if categories.contains(Categories::Armor) {
+            &[EquipSlot::Armor]
+        } else {
+            &[]
+        }
+    }
+}
+
+impl Persist for EquipSlot {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        let index = EquipSlot::ALL.iter().position(|slot| slot == self).unwrap();
+        writer.write(&(index as u8));
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        let index = reader.read::<u8>()?;
+        match EquipSlot::ALL.get(index as usize) {
+            Some(slot) => Ok(*slot),
+            None => Err(format!("Invalid equip slot {}", index)),
+        }
+    }
+}
+
+/// Items carried by a npc, stackable items are one object with an amount
+#[derive(Default)]
+pub struct Inventory {
+    items: Vec<Handle>,
+    equipped: HashMap<EquipSlot, Handle>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Item objects in the order they were added
+    pub fn get_items(&self) -> &[Handle] {
+        &self.items
+    }
+    pub fn contains(&self, item: &Handle) -> bool {
+        self.items.contains(item)
+    }
+    pub fn get_equipped(&self, slot: EquipSlot) -> Option<Handle> {
+        self.equipped.get(&slot).copied()
+    }
+    pub fn get_slot_of(&self, item: &Handle) -> Option<EquipSlot> {
+        self.equipped
+            .iter()
+            .find(|(_, equipped)| *equipped == item)
+            .map(|(slot, _)| *slot)
+    }
+    pub fn is_equipped(&self, item: &Handle) -> bool {
+        self.get_slot_of(item).is_some()
+    }
+    pub(super) fn push(&mut self, item: Handle) {
+        self.items.push(item);
+    }
+    pub(super) fn remove(&mut self, item: &Handle) {
+        self.items.retain(|inner| inner != item);
+        self.equipped.retain(|_, equipped| equipped != item);
+    }
+    /// Takes the item out, returns its position and the slot it was equipped in
+    pub(super) fn detach(&mut self, item: &Handle) -> Option<(usize, Option<EquipSlot>)> {
+        let position = self.items.iter().position(|inner| inner == item)?;
+        let slot = self.get_slot_of(item);
+        self.items.remove(position);
+        if let Some(slot) = slot {
+            self.equipped.remove(&slot);
+        }
+        Some((position, slot))
+    }
+    /// Puts an item taken out by `detach` back to its position and slot
+    pub(super) fn restore(&mut self, item: Handle, position: usize, slot: Option<EquipSlot>) {
+        self.items.insert(position.min(self.items.len()), item);
+        if let Some(slot) = slot {
+            self.equipped.insert(slot, item);
+        }
+    }
+    pub(super) fn set_equipped(&mut self, slot: EquipSlot, item: Option<Handle>) {
+        match item {
+            Some(item) => self.equipped.insert(slot, item),
+            None => self.equipped.remove(&slot),
+        };
+    }
+}
+
+/// Runs `on_equip` or `on_unequip` of the item with `self` as the npc and `item` as the item
+pub(super) fn run_equip_func(
+    virtual_machine: &mut VirtualMachine,
+    npc: Handle,
+    item: Handle,
+    func: u32,
+) {
+    if func == 0 {
+        return;
+    }
+    let bindings = [
+        ("SELF", Some(InstanceData::new(npc, InstanceClass::Npc))),
+        ("ITEM", Some(InstanceData::new(item, InstanceClass::Item))),
+    ];
+    if let Err(error) = run_func_with(virtual_machine, func as usize, &bindings) {
+        log::error!("Equip function of item failed: {}", error);
+    }
+}
+
+impl GameState {
+    pub fn get_inv_of(&self, npc: &Handle) -> Option<&Inventory> {
+        self.npc_inventories.get(npc)
+    }
+    /// Item in the inventory which new items of the instance are merged into
+    pub(super) fn find_stack(&self, npc: &Handle, item_symbol: usize) -> Option<Handle> {
+        self.npc_inventories
+            .get(npc)?
+            .items
+            .iter()
+            .copied()
+            .find(|handle| {
+                self.items.get(handle).is_some_and(|item| {
+                    item.get_instance_symbol() == item_symbol && item.is_stackable()
+                })
+            })
+    }
+    /// Creates `amount` items of the instance in the inventory of the npc.
+    /// Stackable items are merged into one object, returns the last created or grown object
+    /// or `None` if `amount` is 0.
+    pub fn create_inv_items(
+        virtual_machine: &mut VirtualMachine,
+        npc: Handle,
+        item_symbol: usize,
+        amount: u32,
+    ) -> Result<Option<Handle>, String> {
+        if !virtual_machine
+            .get_game_state()
+            .npc_inventories
+            .contains_key(&npc)
+        {
+            return Err("Npc has no inventory".to_owned());
+        }
+        if amount == 0 {
+            return Ok(None);
+        }
+        if let Some(stack) = virtual_machine
+            .get_game_state()
+            .find_stack(&npc, item_symbol)
+        {
+            virtual_machine
+                .get_mut_game_state()
+                .items
+                .get_mut(&stack)
+                .unwrap()
+                .amount += amount;
+            return Ok(Some(stack));
+        }
+        let mut created = 0;
+        let mut handle = None;
+        while created < amount {
+            let item = virtual_machine.create_instance_of(item_symbol, InstanceClass::Item)?;
+            let game_state = virtual_machine.get_mut_game_state();
+            let object = game_state.items.get_mut(&item).unwrap();
+            object.amount = match object.is_stackable() {
+                true => amount,
+                false => 1,
+            };
+            created += object.amount;
+            game_state.npc_inventories.get_mut(&npc).unwrap().push(item);
+            if let Some(func) = &mut game_state.game_externals.create_inv_item {
+                func(item, npc);
+            }
+            handle = Some(item);
+        }
+        Ok(handle)
+    }
+    /// Moves an existing item object into the inventory.
+    /// Stackable items are merged into an existing stack, which is returned in that case.
+    pub fn add_item_to_inv(&mut self, item: &Handle, npc: &Handle) -> Result<Handle, String> {
+        let (item_symbol, amount) = match self.items.get(item) {
+            Some(object) => (object.get_instance_symbol(), object.amount.max(1)),
+            None => return Err("Item does not exist".to_owned()),
+        };
+        if !self.npc_inventories.contains_key(npc) {
+            return Err("Npc has no inventory".to_owned());
+        }
+        if let Some(stack) = self
+            .find_stack(npc, item_symbol)
+            .filter(|stack| stack != item)
+        {
+            self.items.get_mut(&stack).unwrap().amount += amount;
+            self.items.remove(item);
+            return Ok(stack);
+        }
+        let inventory = self.npc_inventories.get_mut(npc).unwrap();
+        if !inventory.contains(item) {
+            inventory.push(*item);
+        }
+        if let Some(func) = &mut self.game_externals.create_inv_item {
+            func(*item, *npc);
+        }
+        Ok(*item)
+    }
+    /// Number of items of the instance the npc carries, see `Npc_HasItems`
+    pub fn get_inv_item_count(&self, npc: &Handle, item_symbol: usize) -> u32 {
+        self.npc_inventories.get(npc).map_or(0, |inventory| {
+            inventory
+                .items
+                .iter()
+                .filter_map(|handle| self.items.get(handle))
+                .filter(|item| item.get_instance_symbol() == item_symbol)
+                .map(|item| item.amount.max(1))
+                .sum()
+        })
+    }
+    /// Removes `amount` items of the instance, equipped items are unequipped first.
+    /// Returns false without removing anything if the npc has no inventory or carries fewer items.
+    pub fn remove_inv_items(
+        virtual_machine: &mut VirtualMachine,
+        npc: Handle,
+        item_symbol: usize,
+        amount: u32,
+    ) -> bool {
+        let game_state = virtual_machine.get_game_state();
+        let inventory = match game_state.npc_inventories.get(&npc) {
+            Some(inventory) => inventory,
+            None => return false,
+        };
+        if game_state.get_inv_item_count(&npc, item_symbol) < amount {
+            return false;
+        }
+        let mut matching: Vec<Handle> = inventory
+            .items
+            .iter()
+            .copied()
+            .filter(|handle| {
+                game_state
+                    .items
+                    .get(handle)
+                    .is_some_and(|item| item.get_instance_symbol() == item_symbol)
+            })
+            .collect();
+        // unequipped items go first
+        matching.sort_by_key(|handle| inventory.is_equipped(handle));
+
+        let mut left = amount;
+        for handle in matching {
+            if left == 0 {
+                break;
+            }
+            let stack = virtual_machine
+                .get_game_state()
+                .items
+                .get(&handle)
+                .unwrap()
+                .amount
+                .max(1);
+            if stack > left {
+                virtual_machine
+                    .get_mut_game_state()
+                    .items
+                    .get_mut(&handle)
+                    .unwrap()
+                    .amount -= left;
+                break;
+            }
+            left -= stack;
+            let slot = virtual_machine
+                .get_game_state()
+                .get_inv_of(&npc)
+                .and_then(|inventory| inventory.get_slot_of(&handle));
+            if let Some(slot) = slot {
+                Self::unequip_item(virtual_machine, npc, slot);
+            }
+            let game_state = virtual_machine.get_mut_game_state();
+            if let Some(inventory) = game_state.npc_inventories.get_mut(&npc) {
+                inventory.remove(&handle);
+            }
+            game_state.items.remove(&handle);
+        }
+        true
+    }
+    /// Equips an item of the inventory, replacing the item in the slot.
+    /// Rings go into the first free ring slot.
+    pub fn equip_item(
+        virtual_machine: &mut VirtualMachine,
+        npc: Handle,
+        item: Handle,
+    ) -> Result<EquipSlot, String> {
+        let game_state = virtual_machine.get_game_state();
+        let inventory = match game_state.npc_inventories.get(&npc) {
+            Some(inventory) if inventory.contains(&item) => inventory,
+            _ => return Err("Item is not in the inventory of the npc".to_owned()),
+        };
+        if let Some(slot) = inventory.get_slot_of(&item) {
+            return Ok(slot);
+        }
+        let object = game_state.items.get(&item).unwrap();
+        let slots = EquipSlot::of_item(object);
+        let slot = match slots
+            .iter()
+            .find(|slot| inventory.get_equipped(**slot).is_none())
+            .or_else(|| slots.first())
+        {
+            Some(slot) => *slot,
+            None => return Err("Item can not be equipped".to_owned()),
+        };
+        let on_equip = object.get_on_equip();
+        Self::unequip_item(virtual_machine, npc, slot);
+        virtual_machine
+            .get_mut_game_state()
+            .npc_inventories
+            .get_mut(&npc)
+            .unwrap()
+            .set_equipped(slot, Some(item));
+        run_equip_func(virtual_machine, npc, item, on_equip);
+        Ok(slot)
+    }
+    /// Returns the previously equipped item
+    pub fn unequip_item(
+        virtual_machine: &mut VirtualMachine,
+        npc: Handle,
+        slot: EquipSlot,
+    ) -> Option<Handle> {
+        let game_state = virtual_machine.get_mut_game_state();
+        let inventory = game_state.npc_inventories.get_mut(&npc)?;
+        let item = inventory.get_equipped(slot)?;
+        inventory.set_equipped(slot, None);
+        let on_unequip = game_state
+            .items
+            .get(&item)
+            .map_or(0, |item| item.get_on_unequip());
+        run_equip_func(virtual_machine, npc, item, on_unequip);
+        Some(item)
+    }
+    /// Sum of `weight` times amount of all carried items
+    pub fn get_inv_weight(&self, npc: &Handle) -> i32 {
+        self.sum_inv(npc, |item| item.get_weight())
+    }
+    /// Sum of `value` times amount of all carried items
+    pub fn get_inv_value(&self, npc: &Handle) -> i32 {
+        self.sum_inv(npc, |item| item.get_value())
+    }
+    fn sum_inv<F: Fn(&Item) -> i32>(&self, npc: &Handle, func: F) -> i32 {
+        self.npc_inventories.get(npc).map_or(0, |inventory| {
+            inventory
+                .items
+                .iter()
+                .filter_map(|handle| self.items.get(handle))
+                .map(|item| func(item) * item.amount.max(1) as i32)
+                .sum()
+        })
+    }
+    /// Carried items with any of the categories
+    pub fn get_inv_items_of(&self, npc: &Handle, categories: BitFlags<Categories>) -> Vec<Handle> {
+        self.npc_inventories.get(npc).map_or(vec![], |inventory| {
+            inventory
+                .items
+                .iter()
+                .copied()
+                .filter(|handle| {
+                    self.items
+                        .get(handle)
+                        .is_some_and(|item| item.get_main_flag().intersects(categories))
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::file::test_file::TestFile;
+
+    const ARROW: usize = 10;
+    const SWORD: usize = 11;
+
+    fn item_with(flags: BitFlags<Flags>, categories: BitFlags<Categories>) -> Item {
+        let mut item = Item::new();
+        item.set_flags(flags);
+        item.set_main_flag(categories);
+        item
+    }
+
+    fn create_item(game_state: &mut GameState, item_symbol: usize, amount: u32) -> Handle {
+        let item = game_state.create_object(InstanceClass::Item).unwrap();
+        game_state.set_instance_symbol(InstanceClass::Item, &item, item_symbol);
+        let object = game_state.get_mut_item(&item).unwrap();
+        object.amount = amount;
+        match item_symbol {
+            SWORD => {
+                object.set_flags(Flags::Sword.into());
+                object.set_weight(5);
+                object.set_value(100);
+            }
+            _ => {
+                object.set_flags(Flags::Multi.into());
+                object.set_weight(2);
+                object.set_value(10);
+            }
+        }
+        item
+    }
+
+    fn setup() -> (VirtualMachine, Handle) {
+        let mut virtual_machine = VirtualMachine::from_file(TestFile::new().build());
+        let npc = virtual_machine
+            .get_mut_game_state()
+            .create_object(InstanceClass::Npc)
+            .unwrap();
+        (virtual_machine, npc)
+    }
+
+    #[test]
+    fn equip_slot_of_item() {
+        let slots = |flags, categories| EquipSlot::of_item(&item_with(flags, categories));
+        assert_eq!(
+            slots(Flags::Ring.into(), BitFlags::empty()),
+            &[EquipSlot::LeftRing, EquipSlot::RightRing]
+        );
+        assert_eq!(
+            slots(Flags::Amulet.into(), BitFlags::empty()),
+            &[EquipSlot::Amulet]
+        );
+        assert_eq!(
+            slots(Flags::Belt.into(), BitFlags::empty()),
+            &[EquipSlot::Belt]
+        );
+        assert_eq!(
+            slots(Flags::Bow.into(), BitFlags::empty()),
+            &[EquipSlot::RangedWeapon]
+        );
+        assert_eq!(
+            slots(BitFlags::empty(), Categories::Ff.into()),
+            &[EquipSlot::RangedWeapon]
+        );
+        assert_eq!(
+            slots(Flags::TwoHandAxe.into(), BitFlags::empty()),
+            &[EquipSlot::MeleeWeapon]
+        );
+        assert_eq!(
+            slots(BitFlags::empty(), Categories::Nf.into()),
+            &[EquipSlot::MeleeWeapon]
+        );
+        assert_eq!(
+            slots(BitFlags::empty(), Categories::Armor.into()),
+            &[EquipSlot::Armor]
+        );
+        assert!(slots(BitFlags::empty(), Categories::Food.into()).is_empty());
+    }
+
+    #[test]
+    fn stackable_items_are_merged() {
+        let (mut virtual_machine, npc) = setup();
+        let game_state = virtual_machine.get_mut_game_state();
+        assert_eq!(game_state.find_stack(&npc, ARROW), None);
+        let arrows = create_item(game_state, ARROW, 2);
+        assert_eq!(game_state.add_item_to_inv(&arrows, &npc), Ok(arrows));
+        assert_eq!(game_state.find_stack(&npc, ARROW), Some(arrows));
+
+        let more = create_item(game_state, ARROW, 3);
+        assert_eq!(game_state.add_item_to_inv(&more, &npc), Ok(arrows));
+        assert!(game_state.get_item(&more).is_none());
+        assert_eq!(game_state.get_inv_of(&npc).unwrap().get_items(), &[arrows]);
+        assert_eq!(game_state.get_item(&arrows).unwrap().amount, 5);
+        assert_eq!(game_state.get_inv_item_count(&npc, ARROW), 5);
+    }
+
+    #[test]
+    fn other_items_are_kept_apart() {
+        let (mut virtual_machine, npc) = setup();
+        let game_state = virtual_machine.get_mut_game_state();
+        let first = create_item(game_state, SWORD, 1);
+        let second = create_item(game_state, SWORD, 1);
+        assert_eq!(game_state.add_item_to_inv(&first, &npc), Ok(first));
+        assert_eq!(game_state.add_item_to_inv(&second, &npc), Ok(second));
+        assert_eq!(game_state.find_stack(&npc, SWORD), None);
+        assert_eq!(
+            game_state.get_inv_of(&npc).unwrap().get_items(),
+            &[first, second]
+        );
+        assert_eq!(game_state.get_inv_item_count(&npc, SWORD), 2);
+    }
+
+    #[test]
+    fn weight_and_value_count_every_item() {
+        let (mut virtual_machine, npc) = setup();
+        let game_state = virtual_machine.get_mut_game_state();
+        let arrows = create_item(game_state, ARROW, 4);
+        let sword = create_item(game_state, SWORD, 1);
+        game_state.add_item_to_inv(&arrows, &npc).unwrap();
+        game_state.add_item_to_inv(&sword, &npc).unwrap();
+        assert_eq!(game_state.get_inv_weight(&npc), 4 * 2 + 5);
+        assert_eq!(game_state.get_inv_value(&npc), 4 * 10 + 100);
+
+        let stranger = game_state.create_object(InstanceClass::Item).unwrap();
+        assert_eq!(game_state.get_inv_weight(&stranger), 0);
+        assert_eq!(game_state.get_inv_value(&stranger), 0);
+    }
+}
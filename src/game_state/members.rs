@@ -0,0 +1,113 @@
+use super::object_allocator::ObjectAllocator;
+use super::GameState;
+use crate::stdlib::members::{ClassMembers, MemberValue};
+use crate::stdlib::prelude::*;
+use zen_memory::Handle;
+
+fn get_member<T: ClassMembers + Default>(
+    allocator: &ObjectAllocator<T>,
+    handle: &Handle,
+    slot: usize,
+    array_index: usize,
+) -> Option<MemberValue> {
+    let member = T::MEMBERS.get(slot)?;
+    (member.get)(allocator.get(handle)?, array_index)
+}
+
+fn set_member<T: ClassMembers + Default>(
+    allocator: &mut ObjectAllocator<T>,
+    handle: &Handle,
+    slot: usize,
+    array_index: usize,
+    value: MemberValue,
+) -> bool {
+    match (T::MEMBERS.get(slot), allocator.get_mut(handle)) {
+        (Some(member), Some(object)) => (member.set)(object, array_index, value),
+        _ => false,
+    }
+}
+
+impl GameState {
+    /// Position of the member in the accessor list of the class
+    pub fn get_member_slot(instance_class: InstanceClass, name: &str) -> Option<usize> {
+        match instance_class {
+            InstanceClass::Npc => Npc::get_member_slot(name),
+            InstanceClass::Mission => Mission::get_member_slot(name),
+            InstanceClass::Info => Info::get_member_slot(name),
+            InstanceClass::Item => Item::get_member_slot(name),
+            InstanceClass::ItemReact => ItemReact::get_member_slot(name),
+            InstanceClass::Focus => Focus::get_member_slot(name),
+            InstanceClass::Menu => Menu::get_member_slot(name),
+            InstanceClass::MenuItem => MenuItem::get_member_slot(name),
+            InstanceClass::Sfx => SoundEffect::get_member_slot(name),
+            InstanceClass::Pfx => ParticleEffect::get_member_slot(name),
+            InstanceClass::MusicTheme => MusicTheme::get_member_slot(name),
+            // dynamic members are addressed by their position in the class
+            InstanceClass::Dynamic => None,
+        }
+    }
+    pub fn get_member(
+        &self,
+        instance_class: InstanceClass,
+        handle: &Handle,
+        slot: usize,
+        array_index: usize,
+    ) -> Option<MemberValue> {
+        match instance_class {
+            InstanceClass::Npc => get_member(&self.npcs, handle, slot, array_index),
+            InstanceClass::Mission => get_member(&self.missions, handle, slot, array_index),
+            InstanceClass::Info => get_member(&self.infos, handle, slot, array_index),
+            InstanceClass::Item => get_member(&self.items, handle, slot, array_index),
+            InstanceClass::ItemReact => get_member(&self.item_reacts, handle, slot, array_index),
+            InstanceClass::Focus => get_member(&self.focuses, handle, slot, array_index),
+            InstanceClass::Menu => get_member(&self.menu, handle, slot, array_index),
+            InstanceClass::MenuItem => get_member(&self.menu_items, handle, slot, array_index),
+            InstanceClass::Sfx => get_member(&self.sound_effects, handle, slot, array_index),
+            InstanceClass::Pfx => get_member(&self.particle_effects, handle, slot, array_index),
+            InstanceClass::MusicTheme => get_member(&self.music_themes, handle, slot, array_index),
+            InstanceClass::Dynamic => self
+                .dynamic_instances
+                .get(handle)?
+                .get_member(slot, array_index),
+        }
+    }
+    /// Returns false if the object does not exist or the value does not fit the member
+    pub fn set_member(
+        &mut self,
+        instance_class: InstanceClass,
+        handle: &Handle,
+        slot: usize,
+        array_index: usize,
+        value: MemberValue,
+    ) -> bool {
+        match instance_class {
+            InstanceClass::Npc => set_member(&mut self.npcs, handle, slot, array_index, value),
+            InstanceClass::Mission => {
+                set_member(&mut self.missions, handle, slot, array_index, value)
+            }
+            InstanceClass::Info => set_member(&mut self.infos, handle, slot, array_index, value),
+            InstanceClass::Item => set_member(&mut self.items, handle, slot, array_index, value),
+            InstanceClass::ItemReact => {
+                set_member(&mut self.item_reacts, handle, slot, array_index, value)
+            }
+            InstanceClass::Focus => set_member(&mut self.focuses, handle, slot, array_index, value),
+            InstanceClass::Menu => set_member(&mut self.menu, handle, slot, array_index, value),
+            InstanceClass::MenuItem => {
+                set_member(&mut self.menu_items, handle, slot, array_index, value)
+            }
+            InstanceClass::Sfx => {
+                set_member(&mut self.sound_effects, handle, slot, array_index, value)
+            }
+            InstanceClass::Pfx => {
+                set_member(&mut self.particle_effects, handle, slot, array_index, value)
+            }
+            InstanceClass::MusicTheme => {
+                set_member(&mut self.music_themes, handle, slot, array_index, value)
+            }
+            InstanceClass::Dynamic => match self.dynamic_instances.get_mut(handle) {
+                Some(object) => object.set_member(slot, array_index, value),
+                None => false,
+            },
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use super::{run_func_with_participants, GameState};
+use crate::snapshot::{Persist, SnapshotReader, SnapshotWriter};
+use crate::stdlib::instances::MAX_MISSIONS;
+use crate::stdlib::prelude::*;
+use crate::vm::VirtualMachine;
+use zen_memory::Handle;
+
+/// Progress of a Gothic 1 `C_MISSION` between the npc offering it and the other npc
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MissionState {
+    NotOffered = 0,
+    Running = 1,
+    Success = 2,
+    Failed = 3,
+    Obsolete = 4,
+}
+
+impl MissionState {
+    pub fn from_int(value: i32) -> Option<MissionState> {
+        match value {
+            0 => Some(MissionState::NotOffered),
+            1 => Some(MissionState::Running),
+            2 => Some(MissionState::Success),
+            3 => Some(MissionState::Failed),
+            4 => Some(MissionState::Obsolete),
+            _ => None,
+        }
+    }
+}
+
+impl Persist for MissionState {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(*self as i32));
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        let value = reader.read::<i32>()?;
+        MissionState::from_int(value).ok_or(format!("Invalid mission state {}", value))
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MissionProgress {
+    pub state: MissionState,
+    /// Day the mission was offered on
+    pub start_day: i32,
+}
+
+/// Key of the progress: mission instance symbol, offering npc and other npc
+pub(super) type MissionKey = (usize, Handle, Handle);
+
+impl GameState {
+    pub fn get_mission_state(
+        &self,
+        mission_symbol: usize,
+        npc: &Handle,
+        other: &Handle,
+    ) -> MissionState {
+        self.mission_progress
+            .get(&(mission_symbol, *npc, *other))
+            .map_or(MissionState::NotOffered, |progress| progress.state)
+    }
+    pub fn get_mission_progress(
+        &self,
+        mission_symbol: usize,
+        npc: &Handle,
+        other: &Handle,
+    ) -> Option<MissionProgress> {
+        self.mission_progress
+            .get(&(mission_symbol, *npc, *other))
+            .copied()
+    }
+    /// Mission instances listed in `C_NPC.MISSION` of the npc
+    pub fn get_missions_of_npc(&self, npc: &Handle) -> Vec<usize> {
+        match self.npcs.get(npc) {
+            Some(npc) => (0..MAX_MISSIONS)
+                .map(|index| npc.get_mission(index) as usize)
+                .filter(|symbol| *symbol != 0)
+                .collect(),
+            None => vec![],
+        }
+    }
+    fn get_mission_object(
+        virtual_machine: &mut VirtualMachine,
+        mission_symbol: usize,
+    ) -> Result<Handle, String> {
+        let existing = virtual_machine
+            .get_game_state()
+            .missions
+            .iter()
+            .find(|(_, mission)| mission.get_instance_symbol() == mission_symbol)
+            .map(|(handle, _)| handle);
+        match existing {
+            Some(handle) => Ok(handle),
+            None => virtual_machine.create_instance_of(mission_symbol, InstanceClass::Mission),
+        }
+    }
+    /// Zero means the mission has no such condition
+    fn check_mission_condition(
+        virtual_machine: &mut VirtualMachine,
+        condition: u32,
+        npc: Handle,
+        other: Handle,
+    ) -> bool {
+        if condition == 0 {
+            return false;
+        }
+        match run_func_with_participants(virtual_machine, condition as usize, npc, Some(other)) {
+            Ok(result) => result != 0,
+            Err(error) => {
+                log::error!("Mission condition failed: {}", error);
+                false
+            }
+        }
+    }
+    fn run_mission_transition(
+        virtual_machine: &mut VirtualMachine,
+        key: MissionKey,
+        state: MissionState,
+        transition: u32,
+    ) {
+        let (_, npc, other) = key;
+        if let Some(progress) = virtual_machine
+            .get_mut_game_state()
+            .mission_progress
+            .get_mut(&key)
+        {
+            progress.state = state;
+        }
+        if transition != 0 {
+            let transition = transition as usize;
+            if let Err(error) =
+                run_func_with_participants(virtual_machine, transition, npc, Some(other))
+            {
+                log::error!("Mission transition failed: {}", error);
+            }
+        }
+    }
+    /// Offers every mission of the npc which was not offered to the other npc yet
+    /// and whose offer conditions hold, returns the offered mission instances
+    pub fn offer_missions(
+        virtual_machine: &mut VirtualMachine,
+        npc: Handle,
+        other: Handle,
+    ) -> Result<Vec<usize>, String> {
+        let mut offered = vec![];
+        for mission_symbol in virtual_machine.get_game_state().get_missions_of_npc(&npc) {
+            let game_state = virtual_machine.get_game_state();
+            if game_state.get_mission_state(mission_symbol, &npc, &other)
+                != MissionState::NotOffered
+            {
+                continue;
+            }
+            let handle = Self::get_mission_object(virtual_machine, mission_symbol)?;
+            let (offer_conditions, offer) =
+                match virtual_machine.get_game_state().missions.get(&handle) {
+                    Some(mission) => (mission.get_offer_conditions(), mission.get_offer()),
+                    None => continue,
+                };
+            // missions without offer conditions are always offered
+            if offer_conditions != 0
+                && !Self::check_mission_condition(virtual_machine, offer_conditions, npc, other)
+            {
+                continue;
+            }
+            let game_state = virtual_machine.get_mut_game_state();
+            let start_day = game_state.get_current_day();
+            let key = (mission_symbol, npc, other);
+            game_state.mission_progress.insert(
+                key,
+                MissionProgress {
+                    state: MissionState::NotOffered,
+                    start_day,
+                },
+            );
+            Self::run_mission_transition(virtual_machine, key, MissionState::Running, offer);
+            offered.push(mission_symbol);
+        }
+        Ok(offered)
+    }
+    /// Checks the success, failure and obsolete conditions of all running missions between the npcs
+    /// and runs the matching transition. Missions running longer than their duration in days fail,
+    /// the others run their `running` function.
+    pub fn update_missions(virtual_machine: &mut VirtualMachine, npc: Handle, other: Handle) {
+        let game_state = virtual_machine.get_mut_game_state();
+        let day = game_state.get_current_day();
+        let mut running: Vec<(MissionKey, MissionProgress)> = game_state
+            .mission_progress
+            .iter()
+            .filter(|((_, key_npc, key_other), progress)| {
+                *key_npc == npc && *key_other == other && progress.state == MissionState::Running
+            })
+            .map(|(key, progress)| (*key, *progress))
+            .collect();
+        running.sort_unstable_by_key(|((mission_symbol, _, _), _)| *mission_symbol);
+
+        for (key, progress) in running {
+            let mission = virtual_machine
+                .get_game_state()
+                .missions
+                .iter()
+                .find(|(_, mission)| mission.get_instance_symbol() == key.0)
+                .map(|(_, mission)| {
+                    (
+                        mission.get_duration(),
+                        [
+                            (
+                                MissionState::Success,
+                                mission.get_success_conditions(),
+                                mission.get_success(),
+                            ),
+                            (
+                                MissionState::Failed,
+                                mission.get_failure_conditions(),
+                                mission.get_failure(),
+                            ),
+                            (
+                                MissionState::Obsolete,
+                                mission.get_obsolete_conditions(),
+                                mission.get_obsolete(),
+                            ),
+                        ],
+                        mission.get_failure(),
+                        mission.get_running(),
+                    )
+                });
+            let (duration, transitions, failure, running) = match mission {
+                Some(mission) => mission,
+                None => continue,
+            };
+            let transition = transitions.iter().find(|(_, condition, _)| {
+                Self::check_mission_condition(virtual_machine, *condition, npc, other)
+            });
+            match transition {
+                Some((state, _, transition)) => {
+                    Self::run_mission_transition(virtual_machine, key, *state, *transition)
+                }
+                None if duration > 0 && day - progress.start_day >= duration => {
+                    Self::run_mission_transition(
+                        virtual_machine,
+                        key,
+                        MissionState::Failed,
+                        failure,
+                    )
+                }
+                None => {
+                    if running != 0 {
+                        let running = running as usize;
+                        if let Err(error) =
+                            run_func_with_participants(virtual_machine, running, npc, Some(other))
+                        {
+                            log::error!("Running function of mission failed: {}", error);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,44 @@
 use crate::stdlib::prelude::*;
+use crate::vm::file::sym_table::SymTable;
+use crate::vm::symbol_values::InstanceData;
 use crate::vm::VirtualMachine;
-pub use game_externals::GameExternals;
+pub use combat::{
+    compute_barrier_damage, compute_fall_damage, compute_hit_damage, get_weapon_talent,
+    CombatRules, DamageResult,
+};
+pub use dialogue::DialogueChoice;
+pub use game_externals::{
+    CreateInvItemFunc, DayFunc, GameExternals, GetDayFunc, HandleFunc, InsertNpcFunc,
+    RoutineChangedFunc, TopicEntryFunc, TopicFunc,
+};
+pub use inventory::{EquipSlot, Inventory};
+use missions::MissionKey;
+pub use missions::{MissionProgress, MissionState};
 use object_allocator::ObjectAllocator;
-use std::collections::HashMap;
+pub use quest_log::{QuestLog, Topic, TopicSection, TopicStatus};
+pub use routine::{RoutineChange, RoutineEntry, Schedule};
+pub use snapshot::HandleSlots;
+use std::collections::{HashMap, HashSet};
+pub use trade::{BarterResult, TradeError, TradeSession};
+#[cfg(feature = "serde")]
+pub use view::{GameStateView, InventoryView, ObjectView};
+pub use world_clock::{WorldClock, MINUTES_PER_DAY, MINUTES_PER_HOUR};
 use zen_memory::Handle;
 
+mod combat;
+mod dialogue;
 mod game_externals;
+mod inventory;
+mod members;
+mod missions;
 mod object_allocator;
+mod quest_log;
+mod routine;
+mod snapshot;
+mod trade;
+#[cfg(feature = "serde")]
+mod view;
+mod world_clock;
 
 const MAX_NUM_MISC: usize = 1024;
 const MAX_NUM_NPCS: usize = 12000;
@@ -20,10 +52,48 @@ const MAX_NUM_MENUITEM: usize = MAX_NUM_MISC;
 const MAX_NUM_SFX: usize = 4096; // G2 has 1700
 const MAX_NUM_PFX: usize = 1024;
 const MAX_NUM_MUSICTHEME: usize = 512;
+const MAX_NUM_DYNAMIC: usize = 16000;
 
-type Inventory = Vec<Handle>;
+/// Runs a script function and returns its int result
+fn run_func(virtual_machine: &mut VirtualMachine, func_symbol: usize) -> Result<i32, String> {
+    virtual_machine.prepare_run_func();
+    virtual_machine.run_func_by_sym_index(func_symbol, false)
+}
+
+/// Runs a script function with the instance symbols bound, `None` unbinds a symbol.
+/// The previous bindings are restored afterwards as the caller may be a running script.
+fn run_func_with(
+    virtual_machine: &mut VirtualMachine,
+    func_symbol: usize,
+    bindings: &[(&str, Option<InstanceData>)],
+) -> Result<i32, String> {
+    let previous: Vec<_> = bindings
+        .iter()
+        .map(|(name, instance)| (*name, virtual_machine.replace_instance(name, *instance)))
+        .collect();
+    let result = run_func(virtual_machine, func_symbol);
+    for (name, instance) in previous.into_iter().rev() {
+        virtual_machine.replace_instance(name, instance);
+    }
+    result
+}
 
-pub struct GameState<'a> {
+/// Runs a condition or transition with `self` as the npc and `other` as the other npc
+fn run_func_with_participants(
+    virtual_machine: &mut VirtualMachine,
+    func_symbol: usize,
+    npc: Handle,
+    other: Option<Handle>,
+) -> Result<i32, String> {
+    let npc_data = |handle| InstanceData::new(handle, InstanceClass::Npc);
+    let bindings = [
+        ("SELF", Some(npc_data(npc))),
+        ("OTHER", other.map(npc_data)),
+    ];
+    run_func_with(virtual_machine, func_symbol, &bindings)
+}
+
+pub struct GameState {
     npcs: ObjectAllocator<Npc>,
     items: ObjectAllocator<Item>,
     item_reacts: ObjectAllocator<ItemReact>,
@@ -35,12 +105,25 @@ pub struct GameState<'a> {
     sound_effects: ObjectAllocator<SoundEffect>,
     particle_effects: ObjectAllocator<ParticleEffect>,
     music_themes: ObjectAllocator<MusicTheme>,
+    dynamic_instances: ObjectAllocator<DynamicInstance>,
     npc_inventories: HashMap<Handle, Inventory>,
-    game_externals: GameExternals<'a>,
+    // info instances the player has already heard
+    told_infos: HashSet<usize>,
+    // info instances heard by each npc
+    known_infos: HashMap<Handle, HashSet<usize>>,
+    // npc told infos count as known for
+    player: Option<Handle>,
+    quest_log: QuestLog,
+    mission_progress: HashMap<MissionKey, MissionProgress>,
+    world_clock: WorldClock,
+    // seconds each npc spent in its current ai state
+    npc_state_times: HashMap<Handle, f32>,
+    schedules: HashMap<Handle, Schedule>,
+    game_externals: GameExternals,
 }
 
-impl<'a> GameState<'a> {
-    pub fn new(game_externals: GameExternals<'a>) -> Self {
+impl GameState {
+    pub fn new(game_externals: GameExternals) -> Self {
         Self {
             npcs: ObjectAllocator::<Npc>::new(MAX_NUM_NPCS),
             items: ObjectAllocator::<Item>::new(MAX_NUM_ITEMS),
@@ -53,120 +136,266 @@ impl<'a> GameState<'a> {
             sound_effects: ObjectAllocator::<SoundEffect>::new(MAX_NUM_SFX),
             particle_effects: ObjectAllocator::<ParticleEffect>::new(MAX_NUM_PFX),
             music_themes: ObjectAllocator::<MusicTheme>::new(MAX_NUM_MUSICTHEME),
+            dynamic_instances: ObjectAllocator::<DynamicInstance>::new(MAX_NUM_DYNAMIC),
             npc_inventories: HashMap::new(),
+            told_infos: HashSet::new(),
+            known_infos: HashMap::new(),
+            player: None,
+            quest_log: QuestLog::new(),
+            mission_progress: HashMap::new(),
+            world_clock: WorldClock::new(),
+            npc_state_times: HashMap::new(),
+            schedules: HashMap::new(),
             game_externals,
         }
     }
-    pub fn insert_npc(
-        &self,
-        instance: usize,
-        waypoint: &str,
-        virtual_machine: &VirtualMachine,
-    ) -> Handle {
-        let handle = self.npcs.create().unwrap();
-        let npc = self.npcs.get_mut(&handle).unwrap();
-        npc.set_waypoint(waypoint);
-        npc.set_instance_symbol(instance);
-        let symbol = virtual_machine
-            .get_file()
-            .sym_table
-            .get_symbol_by_index(instance)
-            .unwrap();
-        if let Some(func) = self.game_externals.insert_npc {
-            func(handle, waypoint);
-        }
-        virtual_machine.initialise_instance(handle, instance, InstanceClass::Npc);
-        if let Some(func) = self.game_externals.post_insert_npc {
-            func(handle);
+    pub fn set_game_externals(&mut self, game_externals: GameExternals) {
+        self.game_externals = game_externals;
+    }
+    pub fn take_game_externals(&mut self) -> GameExternals {
+        std::mem::take(&mut self.game_externals)
+    }
+    pub fn set_info_told(&mut self, info_symbol: usize, told: bool) {
+        match told {
+            true => self.told_infos.insert(info_symbol),
+            false => self.told_infos.remove(&info_symbol),
+        };
+    }
+    pub fn is_info_told(&self, info_symbol: usize) -> bool {
+        self.told_infos.contains(&info_symbol)
+    }
+    pub fn set_info_known(&mut self, npc: &Handle, info_symbol: usize, known: bool) {
+        match known {
+            true => {
+                self.known_infos
+                    .entry(*npc)
+                    .or_default()
+                    .insert(info_symbol);
+            }
+            false => {
+                if let Some(infos) = self.known_infos.get_mut(npc) {
+                    infos.remove(&info_symbol);
+                }
+            }
         }
-        handle
     }
-    pub fn insert_item(&self, instance: usize, virtual_machine: &VirtualMachine) -> Handle {
-        let handle = self.items.create().unwrap();
-        virtual_machine.initialise_instance(handle, instance, InstanceClass::Item);
-        handle
+    pub fn get_player(&self) -> Option<Handle> {
+        self.player
     }
-    pub fn insert_sound_effect(&self, instance: usize, virtual_machine: &VirtualMachine) -> Handle {
-        let handle = self.sound_effects.create().unwrap();
-        virtual_machine.initialise_instance(handle, instance, InstanceClass::Sfx);
-        handle
+    /// Sets the npc which knows all told infos, e.g. the ones imported from a savegame
+    pub fn set_player(&mut self, player: Option<Handle>) {
+        self.player = player;
     }
-    pub fn insert_music_theme(&self, instance: usize, virtual_machine: &VirtualMachine) -> Handle {
-        let handle = self.music_themes.create().unwrap();
-        virtual_machine.initialise_instance(handle, instance, InstanceClass::MusicTheme);
-        handle
+    /// Whether the npc has heard the info, see `Npc_KnowsInfo`.
+    /// Told infos count as known by the player
+    pub fn knows_info(&self, npc: &Handle, info_symbol: usize) -> bool {
+        if self.player == Some(*npc) && self.is_info_told(info_symbol) {
+            return true;
+        }
+        self.known_infos
+            .get(npc)
+            .is_some_and(|infos| infos.contains(&info_symbol))
     }
-
-    pub fn create_inv_item(
-        &mut self,
-        item_symbol: usize,
-        npc: &Handle,
-        amount: u32,
-        virtual_machine: &VirtualMachine,
-    ) -> Handle {
-        if amount == 0 {
-            amount = 1;
+    /// Moves all stored symbol indices to the ones of a reloaded file,
+    /// objects whose instance no longer exists point to symbol 0 afterwards
+    pub fn remap_instance_symbols<F: Fn(usize) -> Option<usize>>(&mut self, remap: F) {
+        fn remap_allocator<T: Default + Instance, F: Fn(usize) -> Option<usize>>(
+            allocator: &mut ObjectAllocator<T>,
+            remap: &F,
+        ) {
+            allocator.for_each_mut(|_, object| {
+                let index = remap(object.get_instance_symbol()).unwrap_or(0);
+                object.set_instance_symbol(index);
+            });
         }
-        let items = self.npc_inventories.get(npc).unwrap();
-        for handle in items {
-            let item = self.items.get_mut(handle).unwrap();
-            if item.get_instance_symbol() == item_symbol {
-                item.amount += amount;
-                return *handle;
-            }
+        remap_allocator(&mut self.npcs, &remap);
+        remap_allocator(&mut self.items, &remap);
+        remap_allocator(&mut self.item_reacts, &remap);
+        remap_allocator(&mut self.missions, &remap);
+        remap_allocator(&mut self.focuses, &remap);
+        remap_allocator(&mut self.infos, &remap);
+        remap_allocator(&mut self.menu, &remap);
+        remap_allocator(&mut self.menu_items, &remap);
+        remap_allocator(&mut self.sound_effects, &remap);
+        remap_allocator(&mut self.particle_effects, &remap);
+        remap_allocator(&mut self.music_themes, &remap);
+        remap_allocator(&mut self.dynamic_instances, &remap);
+        self.dynamic_instances.for_each_mut(|_, object| {
+            object.set_class_symbol(remap(object.get_class_symbol()).unwrap_or(0));
+        });
+        self.told_infos = self
+            .told_infos
+            .iter()
+            .filter_map(|index| remap(*index))
+            .collect();
+        for infos in self.known_infos.values_mut() {
+            *infos = infos.iter().filter_map(|index| remap(*index)).collect();
         }
-        let handle = self.items.create().unwrap();
-        let item = self.items.get_mut(&handle).unwrap();
-        item.amount = amount;
-
-        virtual_machine.initialise_instance(handle, item_symbol, InstanceClass::Item);
-        self.add_item_to_inv(&handle, npc);
-        handle
-    }
-    pub fn add_item_to_inv(&mut self, item_handle: &Handle, npc: &Handle) -> Handle {
-        let items = self.npc_inventories.get(npc).unwrap();
-        for handle in items {
-            let item = self.items.get_mut(handle).unwrap();
-            if item.get_instance_symbol()
-                == self.items.get(&item_handle).unwrap().get_instance_symbol()
-            {
-                item.amount += 1;
-                return *handle;
-            }
+        self.mission_progress = self
+            .mission_progress
+            .drain()
+            .filter_map(|((mission, npc, other), progress)| {
+                Some(((remap(mission)?, npc, other), progress))
+            })
+            .collect();
+        for schedule in self.schedules.values_mut() {
+            schedule.remap_state_funcs(&remap);
         }
-        items.push(*item_handle);
-        if let Some(func) = self.game_externals.create_inv_item {
-            func(*item_handle, *npc);
+    }
+    /// Allocates an instance with the member layout of the class symbol
+    pub fn create_dynamic_instance(
+        &mut self,
+        sym_table: &SymTable,
+        class_symbol: usize,
+    ) -> Result<Handle, String> {
+        let instance = DynamicInstance::new(sym_table, class_symbol)?;
+        Ok(self.dynamic_instances.insert(instance)?)
+    }
+    pub fn get_npc(&self, handle: &Handle) -> Option<&Npc> {
+        self.npcs.get(handle)
+    }
+    pub fn get_mut_npc(&mut self, handle: &Handle) -> Option<&mut Npc> {
+        self.npcs.get_mut(handle)
+    }
+    pub fn get_item(&self, handle: &Handle) -> Option<&Item> {
+        self.items.get(handle)
+    }
+    pub fn get_mut_item(&mut self, handle: &Handle) -> Option<&mut Item> {
+        self.items.get_mut(handle)
+    }
+    pub fn get_info(&self, handle: &Handle) -> Option<&Info> {
+        self.infos.get(handle)
+    }
+    pub fn get_mut_info(&mut self, handle: &Handle) -> Option<&mut Info> {
+        self.infos.get_mut(handle)
+    }
+    pub fn get_dynamic_instance(&self, handle: &Handle) -> Option<&DynamicInstance> {
+        self.dynamic_instances.get(handle)
+    }
+    pub fn get_mut_dynamic_instance(&mut self, handle: &Handle) -> Option<&mut DynamicInstance> {
+        self.dynamic_instances.get_mut(handle)
+    }
+    /// Handles of all objects of the class in creation order
+    pub fn get_handles_of(&self, instance_class: InstanceClass) -> &[Handle] {
+        match instance_class {
+            InstanceClass::Npc => self.npcs.get_handles(),
+            InstanceClass::Mission => self.missions.get_handles(),
+            InstanceClass::Info => self.infos.get_handles(),
+            InstanceClass::Item => self.items.get_handles(),
+            InstanceClass::ItemReact => self.item_reacts.get_handles(),
+            InstanceClass::Focus => self.focuses.get_handles(),
+            InstanceClass::Menu => self.menu.get_handles(),
+            InstanceClass::MenuItem => self.menu_items.get_handles(),
+            InstanceClass::Sfx => self.sound_effects.get_handles(),
+            InstanceClass::Pfx => self.particle_effects.get_handles(),
+            InstanceClass::MusicTheme => self.music_themes.get_handles(),
+            InstanceClass::Dynamic => self.dynamic_instances.get_handles(),
         }
-        *item_handle
-    }
-    pub fn remove_inv_item(&mut self, item_symbol: usize, npc: &Handle, amount: u32) -> bool {
-        let items = self.npc_inventories.get_mut(npc).unwrap();
-        let handle = None;
-        for inner_handle in items {
-            let item = self.items.get(inner_handle).unwrap();
-            if item.get_instance_symbol() == item_symbol {
-                handle = Some(inner_handle);
+    }
+    /// Allocates a default object of the class, dynamic instances need `create_dynamic_instance`
+    pub fn create_object(&mut self, instance_class: InstanceClass) -> Result<Handle, String> {
+        let handle = match instance_class {
+            InstanceClass::Npc => {
+                let handle = self.npcs.create()?;
+                self.npc_inventories.insert(handle, Inventory::new());
+                handle
             }
-        }
-        let handle = match handle {
-            Some(handle) => {
-                let item = self.items.get_mut(handle).unwrap();
-                match item.amount > amount {
-                    true => {
-                        item.amount -= amount;
-                        return true;
-                    }
-                    false => handle,
-                }
+            InstanceClass::Mission => self.missions.create()?,
+            InstanceClass::Info => self.infos.create()?,
+            InstanceClass::Item => self.items.create()?,
+            InstanceClass::ItemReact => self.item_reacts.create()?,
+            InstanceClass::Focus => self.focuses.create()?,
+            InstanceClass::Menu => self.menu.create()?,
+            InstanceClass::MenuItem => self.menu_items.create()?,
+            InstanceClass::Sfx => self.sound_effects.create()?,
+            InstanceClass::Pfx => self.particle_effects.create()?,
+            InstanceClass::MusicTheme => self.music_themes.create()?,
+            InstanceClass::Dynamic => {
+                return Err("Dynamic instances need the symbol of their class".to_owned())
             }
-            None => return false,
         };
-        self.items.remove(handle);
-        items.remove_item(handle);
-        return true;
+        Ok(handle)
+    }
+    pub fn set_instance_symbol(
+        &mut self,
+        instance_class: InstanceClass,
+        handle: &Handle,
+        instance_symbol: usize,
+    ) {
+        fn set<T: Default + Instance>(
+            allocator: &mut ObjectAllocator<T>,
+            handle: &Handle,
+            instance_symbol: usize,
+        ) {
+            if let Some(object) = allocator.get_mut(handle) {
+                object.set_instance_symbol(instance_symbol);
+            }
+        }
+        match instance_class {
+            InstanceClass::Npc => set(&mut self.npcs, handle, instance_symbol),
+            InstanceClass::Mission => set(&mut self.missions, handle, instance_symbol),
+            InstanceClass::Info => set(&mut self.infos, handle, instance_symbol),
+            InstanceClass::Item => set(&mut self.items, handle, instance_symbol),
+            InstanceClass::ItemReact => set(&mut self.item_reacts, handle, instance_symbol),
+            InstanceClass::Focus => set(&mut self.focuses, handle, instance_symbol),
+            InstanceClass::Menu => set(&mut self.menu, handle, instance_symbol),
+            InstanceClass::MenuItem => set(&mut self.menu_items, handle, instance_symbol),
+            InstanceClass::Sfx => set(&mut self.sound_effects, handle, instance_symbol),
+            InstanceClass::Pfx => set(&mut self.particle_effects, handle, instance_symbol),
+            InstanceClass::MusicTheme => set(&mut self.music_themes, handle, instance_symbol),
+            InstanceClass::Dynamic => set(&mut self.dynamic_instances, handle, instance_symbol),
+        }
+    }
+    pub fn insert_npc(
+        virtual_machine: &mut VirtualMachine,
+        instance: usize,
+        waypoint: &str,
+    ) -> Result<Handle, String> {
+        if virtual_machine.get_instance_class(instance) != Some(InstanceClass::Npc) {
+            return Err(format!("Symbol {} is not a npc instance", instance));
+        }
+        let game_state = virtual_machine.get_mut_game_state();
+        let handle = game_state.create_object(InstanceClass::Npc)?;
+        game_state
+            .npcs
+            .get_mut(&handle)
+            .unwrap()
+            .set_waypoint(waypoint);
+        if let Some(func) = &mut game_state.game_externals.insert_npc {
+            func(handle, waypoint);
+        }
+        virtual_machine.initialise_instance(handle, instance, InstanceClass::Npc)?;
+        Self::build_routine(virtual_machine, handle)?;
+        let game_state = virtual_machine.get_mut_game_state();
+        if let Some(func) = &mut game_state.game_externals.post_insert_npc {
+            func(handle);
+        }
+        Ok(handle)
     }
-    pub fn get_inv_of(&self, npc: &Handle) -> Option<&mut Inventory> {
-        self.npc_inventories.get_mut(npc)
+    pub fn insert_item(
+        virtual_machine: &mut VirtualMachine,
+        instance: usize,
+    ) -> Result<Handle, String> {
+        let handle = virtual_machine.create_instance_of(instance, InstanceClass::Item)?;
+        if let Some(func) = &mut virtual_machine
+            .get_mut_game_state()
+            .game_externals
+            .insert_item
+        {
+            func(handle);
+        }
+        Ok(handle)
+    }
+    pub fn insert_sound_effect(
+        virtual_machine: &mut VirtualMachine,
+        instance: usize,
+    ) -> Result<Handle, String> {
+        virtual_machine.create_instance_of(instance, InstanceClass::Sfx)
+    }
+    pub fn insert_music_theme(
+        virtual_machine: &mut VirtualMachine,
+        instance: usize,
+    ) -> Result<Handle, String> {
+        virtual_machine.create_instance_of(instance, InstanceClass::MusicTheme)
     }
 }
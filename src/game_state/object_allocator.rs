@@ -2,6 +2,8 @@ use zen_memory::{Allocator, Handle};
 
 pub struct ObjectAllocator<T: Default> {
     allocator: Allocator<T>,
+    // handles in creation order
+    handles: Vec<Handle>,
     current: usize,
     limit: usize,
 }
@@ -11,6 +13,7 @@ impl<T: Default> ObjectAllocator<T> {
         let allocator = Allocator::<T>::new();
         Self {
             allocator,
+            handles: vec![],
             current: 0,
             limit,
         }
@@ -21,20 +24,59 @@ impl<T: Default> ObjectAllocator<T> {
     pub fn create(&mut self) -> Result<Handle, &str> {
         match self.left_space() > 0 {
             true => {
+                let handle = self.allocator.create()?;
                 self.current += 1;
-                self.allocator.create()
+                self.handles.push(handle);
+                Ok(handle)
             }
             false => Err("No space left"),
         }
     }
+    /// Creates a new object and moves the given value into it
+    pub fn insert(&mut self, object: T) -> Result<Handle, &str> {
+        let handle = match self.create() {
+            Ok(handle) => handle,
+            Err(_) => return Err("No space left"),
+        };
+        *self.allocator.get_mut(&handle).unwrap() = object;
+        Ok(handle)
+    }
     pub fn remove(&mut self, handle: &Handle) {
-        self.current -= 1;
-        self.allocator.remove(handle);
+        if let Some(position) = self.handles.iter().position(|inner| inner == handle) {
+            self.handles.remove(position);
+            self.current -= 1;
+            self.allocator.remove(handle);
+        }
     }
     pub fn get(&self, handle: &Handle) -> Option<&T> {
         self.allocator.get(handle)
     }
-    pub fn get_mut(&self, handle: &Handle) -> Option<&mut T> {
+    pub fn get_mut(&mut self, handle: &Handle) -> Option<&mut T> {
         self.allocator.get_mut(handle)
     }
+    /// All living handles in creation order
+    pub fn get_handles(&self) -> &[Handle] {
+        &self.handles
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (Handle, &T)> {
+        self.handles
+            .iter()
+            .filter_map(move |handle| self.allocator.get(handle).map(|object| (*handle, object)))
+    }
+    pub fn for_each_mut<F: FnMut(Handle, &mut T)>(&mut self, mut func: F) {
+        for handle in self.handles.iter() {
+            if let Some(object) = self.allocator.get_mut(handle) {
+                func(*handle, object);
+            }
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.current
+    }
+    pub fn clear(&mut self) {
+        for handle in std::mem::take(&mut self.handles) {
+            self.allocator.remove(&handle);
+        }
+        self.current = 0;
+    }
 }
@@ -0,0 +1,258 @@
+use super::GameState;
+use crate::persist_struct;
+use crate::snapshot::{Persist, SnapshotReader, SnapshotWriter};
+
+/// `LOG_MISSION` and `LOG_NOTE` of the scripts
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TopicSection {
+    Mission = 0,
+    Note = 1,
+}
+
+impl TopicSection {
+    pub fn from_int(value: i32) -> Option<TopicSection> {
+        match value {
+            0 => Some(TopicSection::Mission),
+            1 => Some(TopicSection::Note),
+            _ => None,
+        }
+    }
+}
+
+/// `LOG_RUNNING`, `LOG_SUCCESS`, `LOG_FAILED` and `LOG_OBSOLETE` of the scripts
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TopicStatus {
+    /// Created but no status set yet
+    Free = 0,
+    Running = 1,
+    Success = 2,
+    Failed = 3,
+    Obsolete = 4,
+}
+
+impl TopicStatus {
+    pub fn from_int(value: i32) -> Option<TopicStatus> {
+        match value {
+            0 => Some(TopicStatus::Free),
+            1 => Some(TopicStatus::Running),
+            2 => Some(TopicStatus::Success),
+            3 => Some(TopicStatus::Failed),
+            4 => Some(TopicStatus::Obsolete),
+            _ => None,
+        }
+    }
+}
+
+impl Persist for TopicSection {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(*self as i32));
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        let value = reader.read::<i32>()?;
+        TopicSection::from_int(value).ok_or(format!("Invalid topic section {}", value))
+    }
+}
+
+impl Persist for TopicStatus {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(*self as i32));
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        let value = reader.read::<i32>()?;
+        TopicStatus::from_int(value).ok_or(format!("Invalid topic status {}", value))
+    }
+}
+
+pub struct Topic {
+    name: String,
+    section: TopicSection,
+    status: TopicStatus,
+    entries: Vec<String>,
+}
+
+impl Topic {
+    pub fn new(name: &str, section: TopicSection) -> Self {
+        Self {
+            name: name.to_owned(),
+            section,
+            status: TopicStatus::Free,
+            entries: vec![],
+        }
+    }
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn get_section(&self) -> TopicSection {
+        self.section
+    }
+    pub fn get_status(&self) -> TopicStatus {
+        self.status
+    }
+    pub fn set_status(&mut self, status: TopicStatus) {
+        self.status = status;
+    }
+    /// Entries in the order they were added
+    pub fn get_entries(&self) -> &[String] {
+        &self.entries
+    }
+    pub fn add_entry(&mut self, entry: &str) {
+        self.entries.push(entry.to_owned());
+    }
+}
+
+persist_struct!(Topic {
+    name,
+    section,
+    status,
+    entries,
+});
+
+/// Topics of the player in creation order
+#[derive(Default)]
+pub struct QuestLog {
+    topics: Vec<Topic>,
+}
+
+impl QuestLog {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Creating an existing topic keeps it, like the game does
+    pub fn create_topic(&mut self, name: &str, section: TopicSection) -> &mut Topic {
+        match self.topics.iter().position(|topic| topic.name == name) {
+            Some(position) => &mut self.topics[position],
+            None => {
+                self.topics.push(Topic::new(name, section));
+                self.topics.last_mut().unwrap()
+            }
+        }
+    }
+    pub fn get_topic(&self, name: &str) -> Option<&Topic> {
+        self.topics.iter().find(|topic| topic.name == name)
+    }
+    pub fn get_mut_topic(&mut self, name: &str) -> Option<&mut Topic> {
+        self.topics.iter_mut().find(|topic| topic.name == name)
+    }
+    pub fn get_topics(&self) -> &[Topic] {
+        &self.topics
+    }
+    pub fn get_topics_of(&self, section: TopicSection) -> impl Iterator<Item = &Topic> {
+        self.topics
+            .iter()
+            .filter(move |topic| topic.section == section)
+    }
+    pub fn get_topics_with(&self, status: TopicStatus) -> impl Iterator<Item = &Topic> {
+        self.topics
+            .iter()
+            .filter(move |topic| topic.status == status)
+    }
+    pub fn clear(&mut self) {
+        self.topics.clear();
+    }
+}
+
+persist_struct!(QuestLog { topics });
+
+impl GameState {
+    pub fn get_quest_log(&self) -> &QuestLog {
+        &self.quest_log
+    }
+    pub fn get_mut_quest_log(&mut self) -> &mut QuestLog {
+        &mut self.quest_log
+    }
+    pub fn create_log_topic(&mut self, name: &str, section: TopicSection) {
+        self.quest_log.create_topic(name, section);
+        if let Some(func) = &mut self.game_externals.log_create_topic {
+            func(name);
+        }
+    }
+    /// Returns false if the topic does not exist
+    pub fn set_log_topic_status(&mut self, name: &str, status: TopicStatus) -> bool {
+        match self.quest_log.get_mut_topic(name) {
+            Some(topic) => topic.set_status(status),
+            None => return false,
+        }
+        if let Some(func) = &mut self.game_externals.log_set_topic_status {
+            func(name);
+        }
+        true
+    }
+    /// Returns false if the topic does not exist
+    pub fn add_log_entry(&mut self, name: &str, entry: &str) -> bool {
+        match self.quest_log.get_mut_topic(name) {
+            Some(topic) => topic.add_entry(entry),
+            None => return false,
+        }
+        if let Some(func) = &mut self.game_externals.log_add_entry {
+            func(name, entry);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topics_keep_creation_order() {
+        let mut quest_log = QuestLog::new();
+        quest_log.create_topic("Der Sumpf", TopicSection::Mission);
+        quest_log.create_topic("Notizen", TopicSection::Note);
+        quest_log.create_topic("Die Mine", TopicSection::Mission);
+        let names: Vec<_> = quest_log.get_topics().iter().map(Topic::get_name).collect();
+        assert_eq!(names, ["Der Sumpf", "Notizen", "Die Mine"]);
+        let missions: Vec<_> = quest_log
+            .get_topics_of(TopicSection::Mission)
+            .map(Topic::get_name)
+            .collect();
+        assert_eq!(missions, ["Der Sumpf", "Die Mine"]);
+    }
+
+    #[test]
+    fn creating_an_existing_topic_keeps_it() {
+        let mut quest_log = QuestLog::new();
+        let topic = quest_log.create_topic("Der Sumpf", TopicSection::Mission);
+        topic.set_status(TopicStatus::Running);
+        topic.add_entry("Erster Eintrag");
+        let topic = quest_log.create_topic("Der Sumpf", TopicSection::Note);
+        assert_eq!(topic.get_section(), TopicSection::Mission);
+        assert_eq!(topic.get_status(), TopicStatus::Running);
+        assert_eq!(quest_log.get_topics().len(), 1);
+    }
+
+    #[test]
+    fn entries_keep_insertion_order() {
+        let mut quest_log = QuestLog::new();
+        let topic = quest_log.create_topic("Der Sumpf", TopicSection::Mission);
+        topic.add_entry("Erster Eintrag");
+        topic.add_entry("Zweiter Eintrag");
+        assert_eq!(topic.get_entries(), ["Erster Eintrag", "Zweiter Eintrag"]);
+    }
+
+    #[test]
+    fn quest_log_round_trips() {
+        let mut quest_log = QuestLog::new();
+        let topic = quest_log.create_topic("Der Sumpf", TopicSection::Mission);
+        topic.set_status(TopicStatus::Success);
+        topic.add_entry("Erledigt");
+        quest_log.create_topic("Notizen", TopicSection::Note);
+
+        let mut writer = SnapshotWriter::new();
+        writer.write(&quest_log);
+        let bytes = writer.into_bytes();
+        let read = SnapshotReader::new(&bytes).read::<QuestLog>().unwrap();
+        let topic = read.get_topic("Der Sumpf").unwrap();
+        assert_eq!(topic.get_status(), TopicStatus::Success);
+        assert_eq!(topic.get_entries(), ["Erledigt"]);
+        assert_eq!(read.get_topics()[1].get_name(), "Notizen");
+    }
+
+    #[test]
+    fn invalid_status_fails() {
+        let bytes = 9i32.to_le_bytes();
+        assert!(SnapshotReader::new(&bytes).read::<TopicStatus>().is_err());
+        assert_eq!(TopicStatus::from_int(9), None);
+        assert_eq!(TopicSection::from_int(1), Some(TopicSection::Note));
+    }
+}
@@ -0,0 +1,223 @@
+use super::world_clock::{is_in_range, MINUTES_PER_HOUR};
+use super::{run_func_with, GameState};
+use crate::persist_struct;
+use crate::stdlib::prelude::*;
+use crate::vm::symbol_values::InstanceData;
+use crate::vm::VirtualMachine;
+use zen_memory::Handle;
+
+/// One `TA_*` entry of a daily routine, times are minutes since midnight
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RoutineEntry {
+    pub start: i32,
+    pub end: i32,
+    /// Function symbol of the `ZS_*` state
+    pub state_func: usize,
+    pub waypoint: String,
+}
+
+impl RoutineEntry {
+    pub fn new(
+        start_hour: i32,
+        start_minute: i32,
+        end_hour: i32,
+        end_minute: i32,
+        state_func: usize,
+        waypoint: &str,
+    ) -> Self {
+        Self {
+            start: start_hour * MINUTES_PER_HOUR + start_minute,
+            end: end_hour * MINUTES_PER_HOUR + end_minute,
+            state_func,
+            waypoint: waypoint.to_owned(),
+        }
+    }
+    pub fn is_active_at(&self, minute_of_day: i32) -> bool {
+        is_in_range(minute_of_day, self.start, self.end)
+    }
+}
+
+persist_struct!(RoutineEntry {
+    start,
+    end,
+    state_func,
+    waypoint,
+});
+
+/// Entries of the daily routine of a npc in the order the routine function added them
+#[derive(Default)]
+pub struct Schedule {
+    entries: Vec<RoutineEntry>,
+    /// Entry reported by the last `update_routines`
+    active: Option<usize>,
+}
+
+impl Schedule {
+    pub fn get_entries(&self) -> &[RoutineEntry] {
+        &self.entries
+    }
+    /// Entries added later win over earlier ones covering the same time, like in the game
+    pub fn get_active_entry(&self, minute_of_day: i32) -> Option<&RoutineEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.is_active_at(minute_of_day))
+    }
+    pub(super) fn remap_state_funcs<F: Fn(usize) -> Option<usize>>(&mut self, remap: &F) {
+        for entry in self.entries.iter_mut() {
+            entry.state_func = remap(entry.state_func).unwrap_or(0);
+        }
+    }
+    fn get_active_index(&self, minute_of_day: i32) -> Option<usize> {
+        self.entries
+            .iter()
+            .rposition(|entry| entry.is_active_at(minute_of_day))
+    }
+}
+
+persist_struct!(Schedule { entries, active });
+
+/// A npc switched to another routine entry
+#[derive(Clone, Debug)]
+pub struct RoutineChange {
+    pub npc: Handle,
+    pub entry: RoutineEntry,
+}
+
+impl GameState {
+    pub fn get_schedule(&self, npc: &Handle) -> Option<&Schedule> {
+        self.schedules.get(npc)
+    }
+    /// Adds an entry to the schedule being built, called by `TA` and `TA_Min`
+    pub fn add_routine_entry(&mut self, npc: &Handle, entry: RoutineEntry) {
+        self.schedules.entry(*npc).or_default().entries.push(entry);
+    }
+    /// Entry of the schedule active at the current time of the world clock
+    pub fn get_active_routine(&self, npc: &Handle) -> Option<&RoutineEntry> {
+        let minute_of_day = self.world_clock.get_minute_of_day();
+        self.schedules.get(npc)?.get_active_entry(minute_of_day)
+    }
+    /// Runs `daily_routine` of the npc with it as `self` and collects the `TA_*` entries it adds
+    pub fn build_routine(virtual_machine: &mut VirtualMachine, npc: Handle) -> Result<(), String> {
+        let game_state = virtual_machine.get_mut_game_state();
+        let routine = match game_state.npcs.get(&npc) {
+            Some(object) => object.get_daily_routine(),
+            None => return Err("Npc does not exist".to_owned()),
+        };
+        game_state.schedules.insert(npc, Schedule::default());
+        if routine == 0 {
+            return Ok(());
+        }
+        let bindings = [("SELF", Some(InstanceData::new(npc, InstanceClass::Npc)))];
+        run_func_with(virtual_machine, routine as usize, &bindings)?;
+        Ok(())
+    }
+    /// Replaces the routine by `Rtn_<name>_<id>` and rebuilds the schedule, see `Npc_ExchangeRoutine`
+    pub fn exchange_routine(
+        virtual_machine: &mut VirtualMachine,
+        npc: Handle,
+        name: &str,
+    ) -> Result<(), String> {
+        let id = match virtual_machine.get_game_state().npcs.get(&npc) {
+            Some(object) => object.get_id(),
+            None => return Err("Npc does not exist".to_owned()),
+        };
+        let func_name = format!("RTN_{}_{}", name, id).to_uppercase();
+        let routine = match virtual_machine
+            .get_file()
+            .get_sym_table()
+            .get_symbol_index_by_name(&func_name)
+        {
+            Some(routine) => routine,
+            None => return Err(format!("Routine {} not found", func_name)),
+        };
+        virtual_machine
+            .get_mut_game_state()
+            .npcs
+            .get_mut(&npc)
+            .unwrap()
+            .set_daily_routine(routine as u32);
+        Self::build_routine(virtual_machine, npc)
+    }
+    /// Compares the active entries against the world clock and reports every npc that switched
+    pub fn update_routines(&mut self) -> Vec<RoutineChange> {
+        let minute_of_day = self.world_clock.get_minute_of_day();
+        let mut changes = vec![];
+        for (npc, schedule) in self.schedules.iter_mut() {
+            let active = schedule.get_active_index(minute_of_day);
+            if active == schedule.active {
+                continue;
+            }
+            schedule.active = active;
+            if let Some(index) = active {
+                changes.push(RoutineChange {
+                    npc: *npc,
+                    entry: schedule.entries[index].clone(),
+                });
+            }
+        }
+        for change in changes.iter() {
+            if let Some(func) = &mut self.game_externals.routine_changed {
+                func(change.npc, change.entry.state_func, &change.entry.waypoint);
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORK: usize = 1;
+    const SLEEP: usize = 2;
+    const EAT: usize = 3;
+
+    fn schedule() -> Schedule {
+        Schedule {
+            entries: vec![
+                RoutineEntry::new(8, 0, 22, 0, WORK, "WP_WORK"),
+                RoutineEntry::new(22, 0, 8, 0, SLEEP, "WP_BED"),
+                RoutineEntry::new(12, 0, 13, 0, EAT, "WP_TABLE"),
+            ],
+            active: None,
+        }
+    }
+
+    fn active_at(schedule: &Schedule, hour: i32, minute: i32) -> Option<usize> {
+        schedule
+            .get_active_entry(hour * MINUTES_PER_HOUR + minute)
+            .map(|entry| entry.state_func)
+    }
+
+    #[test]
+    fn later_entries_win() {
+        let schedule = schedule();
+        assert_eq!(active_at(&schedule, 11, 59), Some(WORK));
+        assert_eq!(active_at(&schedule, 12, 0), Some(EAT));
+        assert_eq!(active_at(&schedule, 12, 59), Some(EAT));
+        assert_eq!(active_at(&schedule, 13, 0), Some(WORK));
+    }
+
+    #[test]
+    fn entries_wrap_at_midnight() {
+        let schedule = schedule();
+        assert_eq!(active_at(&schedule, 21, 59), Some(WORK));
+        assert_eq!(active_at(&schedule, 22, 0), Some(SLEEP));
+        assert_eq!(active_at(&schedule, 0, 0), Some(SLEEP));
+        assert_eq!(active_at(&schedule, 7, 59), Some(SLEEP));
+        assert_eq!(active_at(&schedule, 8, 0), Some(WORK));
+    }
+
+    #[test]
+    fn gaps_have_no_entry() {
+        let mut schedule = Schedule::default();
+        assert_eq!(active_at(&schedule, 12, 0), None);
+        schedule
+            .entries
+            .push(RoutineEntry::new(9, 0, 17, 0, WORK, "WP_WORK"));
+        assert_eq!(active_at(&schedule, 8, 59), None);
+        assert_eq!(active_at(&schedule, 17, 0), None);
+        assert_eq!(active_at(&schedule, 9, 0), Some(WORK));
+    }
+}
@@ -0,0 +1,267 @@
+use super::object_allocator::ObjectAllocator;
+use super::{EquipSlot, GameState, Inventory, MissionProgress};
+use crate::snapshot::{Persist, SnapshotReader, SnapshotWriter};
+use crate::stdlib::InstanceClass;
+use std::collections::HashMap;
+use zen_memory::Handle;
+
+const NO_SLOT: u32 = u32::MAX;
+
+/// Position of every living handle inside its allocator.
+/// Handles are only valid for one session, so snapshots store positions instead.
+pub struct HandleSlots {
+    slots: HashMap<(InstanceClass, Handle), u32>,
+}
+
+impl HandleSlots {
+    pub fn get(&self, instance_class: InstanceClass, handle: &Handle) -> Option<u32> {
+        self.slots.get(&(instance_class, *handle)).copied()
+    }
+    pub fn write_handle(
+        &self,
+        writer: &mut SnapshotWriter,
+        instance_class: InstanceClass,
+        handle: &Handle,
+    ) {
+        writer.write(&self.get(instance_class, handle).unwrap_or(NO_SLOT));
+    }
+}
+
+fn write_allocator<T: Default + Persist>(
+    writer: &mut SnapshotWriter,
+    allocator: &ObjectAllocator<T>,
+) {
+    writer.write(&(allocator.len() as u32));
+    for (_, object) in allocator.iter() {
+        writer.write(object);
+    }
+}
+
+fn read_allocator<T: Default + Persist>(
+    reader: &mut SnapshotReader,
+    allocator: &mut ObjectAllocator<T>,
+) -> Result<(), String> {
+    allocator.clear();
+    let count = reader.read::<u32>()?;
+    for _ in 0..count {
+        allocator.insert(reader.read()?)?;
+    }
+    Ok(())
+}
+
+impl GameState {
+    pub fn get_handle_slots(&self) -> HandleSlots {
+        let mut slots = HashMap::new();
+        for instance_class in InstanceClass::ALL.iter() {
+            for (slot, handle) in self.get_handles_of(*instance_class).iter().enumerate() {
+                slots.insert((*instance_class, *handle), slot as u32);
+            }
+        }
+        HandleSlots { slots }
+    }
+    /// Reads a handle written by `HandleSlots::write_handle`,
+    /// only valid after the allocators have been restored
+    pub fn read_handle(
+        &self,
+        reader: &mut SnapshotReader,
+        instance_class: InstanceClass,
+    ) -> Result<Option<Handle>, String> {
+        let slot = reader.read::<u32>()?;
+        if slot == NO_SLOT {
+            return Ok(None);
+        }
+        match self.get_handles_of(instance_class).get(slot as usize) {
+            Some(handle) => Ok(Some(*handle)),
+            None => Err(format!(
+                "Snapshot references missing {:?} object {}",
+                instance_class, slot
+            )),
+        }
+    }
+    pub fn write_snapshot(&self, writer: &mut SnapshotWriter, slots: &HandleSlots) {
+        write_allocator(writer, &self.npcs);
+        write_allocator(writer, &self.items);
+        write_allocator(writer, &self.item_reacts);
+        write_allocator(writer, &self.missions);
+        write_allocator(writer, &self.focuses);
+        write_allocator(writer, &self.infos);
+        write_allocator(writer, &self.menu);
+        write_allocator(writer, &self.menu_items);
+        write_allocator(writer, &self.sound_effects);
+        write_allocator(writer, &self.particle_effects);
+        write_allocator(writer, &self.music_themes);
+        write_allocator(writer, &self.dynamic_instances);
+
+        writer.write(&(self.npc_inventories.len() as u32));
+        for (npc, inventory) in self.npc_inventories.iter() {
+            slots.write_handle(writer, InstanceClass::Npc, npc);
+            writer.write(&(inventory.get_items().len() as u32));
+            for item in inventory.get_items().iter() {
+                slots.write_handle(writer, InstanceClass::Item, item);
+            }
+            let equipped: Vec<_> = EquipSlot::ALL
+                .iter()
+                .filter_map(|slot| Some((*slot, inventory.get_equipped(*slot)?)))
+                .collect();
+            writer.write(&(equipped.len() as u32));
+            for (slot, item) in equipped {
+                writer.write(&slot);
+                slots.write_handle(writer, InstanceClass::Item, &item);
+            }
+        }
+
+        let mut told_infos: Vec<usize> = self.told_infos.iter().copied().collect();
+        told_infos.sort_unstable();
+        writer.write(&told_infos);
+
+        writer.write(&(self.known_infos.len() as u32));
+        for (npc, infos) in self.known_infos.iter() {
+            slots.write_handle(writer, InstanceClass::Npc, npc);
+            let mut infos: Vec<usize> = infos.iter().copied().collect();
+            infos.sort_unstable();
+            writer.write(&infos);
+        }
+        match &self.player {
+            Some(player) => slots.write_handle(writer, InstanceClass::Npc, player),
+            None => writer.write(&NO_SLOT),
+        }
+
+        writer.write(&self.quest_log);
+
+        let mut missions: Vec<_> = self.mission_progress.iter().collect();
+        missions.sort_unstable_by_key(|((mission, npc, other), _)| {
+            (
+                *mission,
+                slots.get(InstanceClass::Npc, npc),
+                slots.get(InstanceClass::Npc, other),
+            )
+        });
+        writer.write(&(missions.len() as u32));
+        for ((mission, npc, other), progress) in missions {
+            writer.write(mission);
+            slots.write_handle(writer, InstanceClass::Npc, npc);
+            slots.write_handle(writer, InstanceClass::Npc, other);
+            writer.write(&progress.state);
+            writer.write(&progress.start_day);
+        }
+
+        writer.write(&self.world_clock);
+        let mut state_times: Vec<_> = self
+            .npc_state_times
+            .iter()
+            .filter_map(|(npc, seconds)| {
+                Some((slots.get(InstanceClass::Npc, npc)?, *npc, *seconds))
+            })
+            .collect();
+        state_times.sort_unstable_by_key(|(slot, _, _)| *slot);
+        writer.write(&(state_times.len() as u32));
+        for (_, npc, seconds) in state_times {
+            slots.write_handle(writer, InstanceClass::Npc, &npc);
+            writer.write(&seconds);
+        }
+
+        let mut schedules: Vec<_> = self
+            .schedules
+            .iter()
+            .filter_map(|(npc, schedule)| {
+                Some((slots.get(InstanceClass::Npc, npc)?, *npc, schedule))
+            })
+            .collect();
+        schedules.sort_unstable_by_key(|(slot, _, _)| *slot);
+        writer.write(&(schedules.len() as u32));
+        for (_, npc, schedule) in schedules {
+            slots.write_handle(writer, InstanceClass::Npc, &npc);
+            writer.write(schedule);
+        }
+    }
+    pub fn read_snapshot(&mut self, reader: &mut SnapshotReader) -> Result<(), String> {
+        read_allocator(reader, &mut self.npcs)?;
+        read_allocator(reader, &mut self.items)?;
+        read_allocator(reader, &mut self.item_reacts)?;
+        read_allocator(reader, &mut self.missions)?;
+        read_allocator(reader, &mut self.focuses)?;
+        read_allocator(reader, &mut self.infos)?;
+        read_allocator(reader, &mut self.menu)?;
+        read_allocator(reader, &mut self.menu_items)?;
+        read_allocator(reader, &mut self.sound_effects)?;
+        read_allocator(reader, &mut self.particle_effects)?;
+        read_allocator(reader, &mut self.music_themes)?;
+        read_allocator(reader, &mut self.dynamic_instances)?;
+
+        self.npc_inventories.clear();
+        let count = reader.read::<u32>()?;
+        for _ in 0..count {
+            let npc = self.read_handle(reader, InstanceClass::Npc)?;
+            let item_count = reader.read::<u32>()?;
+            let mut inventory = Inventory::new();
+            for _ in 0..item_count {
+                if let Some(item) = self.read_handle(reader, InstanceClass::Item)? {
+                    inventory.push(item);
+                }
+            }
+            let equipped_count = reader.read::<u32>()?;
+            for _ in 0..equipped_count {
+                let slot = reader.read::<EquipSlot>()?;
+                if let Some(item) = self.read_handle(reader, InstanceClass::Item)? {
+                    inventory.set_equipped(slot, Some(item));
+                }
+            }
+            if let Some(npc) = npc {
+                self.npc_inventories.insert(npc, inventory);
+            }
+        }
+
+        self.told_infos = reader.read::<Vec<usize>>()?.into_iter().collect();
+
+        self.known_infos.clear();
+        let count = reader.read::<u32>()?;
+        for _ in 0..count {
+            let npc = self.read_handle(reader, InstanceClass::Npc)?;
+            let infos = reader.read::<Vec<usize>>()?;
+            if let Some(npc) = npc {
+                self.known_infos.insert(npc, infos.into_iter().collect());
+            }
+        }
+        self.player = self.read_handle(reader, InstanceClass::Npc)?;
+
+        self.quest_log = reader.read()?;
+
+        self.mission_progress.clear();
+        let count = reader.read::<u32>()?;
+        for _ in 0..count {
+            let mission = reader.read::<usize>()?;
+            let npc = self.read_handle(reader, InstanceClass::Npc)?;
+            let other = self.read_handle(reader, InstanceClass::Npc)?;
+            let progress = MissionProgress {
+                state: reader.read()?,
+                start_day: reader.read()?,
+            };
+            if let (Some(npc), Some(other)) = (npc, other) {
+                self.mission_progress
+                    .insert((mission, npc, other), progress);
+            }
+        }
+
+        self.world_clock = reader.read()?;
+        self.npc_state_times.clear();
+        let count = reader.read::<u32>()?;
+        for _ in 0..count {
+            let npc = self.read_handle(reader, InstanceClass::Npc)?;
+            let seconds = reader.read::<f32>()?;
+            if let Some(npc) = npc {
+                self.npc_state_times.insert(npc, seconds);
+            }
+        }
+
+        self.schedules.clear();
+        let count = reader.read::<u32>()?;
+        for _ in 0..count {
+            let npc = self.read_handle(reader, InstanceClass::Npc)?;
+            let schedule = reader.read()?;
+            if let Some(npc) = npc {
+                self.schedules.insert(npc, schedule);
+            }
+        }
+        Ok(())
+    }
+}
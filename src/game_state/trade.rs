@@ -0,0 +1,712 @@
+use super::inventory::run_equip_func;
+use super::{run_func_with_participants, EquipSlot, GameState};
+use crate::stdlib::instances::item::Categories;
+use crate::stdlib::prelude::*;
+use crate::vm::VirtualMachine;
+use enumflags2::BitFlags;
+use std::fmt;
+use zen_memory::Handle;
+
+/// Why a trade did not happen, a failed trade leaves all inventories unchanged
+#[derive(Clone, Debug, PartialEq)]
+pub enum TradeError {
+    /// No item changed owner
+    Failed(String),
+}
+
+impl From<String> for TradeError {
+    fn from(error: String) -> Self {
+        TradeError::Failed(error)
+    }
+}
+
+impl fmt::Display for TradeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TradeError::Failed(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// Items of one instance changing owner
+#[derive(Copy, Clone, Debug)]
+struct Transfer {
+    from: Handle,
+    to: Handle,
+    item_symbol: usize,
+    amount: u32,
+}
+
+/// Change made to the inventories by a running trade
+enum Change {
+    /// The item left the inventory of the npc, it was at `position` and equipped in `slot`
+    Detached {
+        npc: Handle,
+        item: Handle,
+        position: usize,
+        slot: Option<EquipSlot>,
+    },
+    Attached {
+        npc: Handle,
+        item: Handle,
+    },
+    Grown {
+        item: Handle,
+        amount: u32,
+    },
+    Shrunk {
+        item: Handle,
+        amount: u32,
+    },
+    /// Split off a stack for the new owner
+    Created {
+        item: Handle,
+    },
+    /// Merged into a stack of the new owner, removed once the trade is done
+    Merged {
+        item: Handle,
+    },
+}
+
+/// Moves the item objects between inventories and records every change,
+/// so a failed trade can be undone without running scripts
+#[derive(Default)]
+struct Transaction {
+    changes: Vec<Change>,
+}
+
+impl Transaction {
+    fn transfer(&mut self, game_state: &mut GameState, transfer: Transfer) -> Result<(), String> {
+        if transfer.amount == 0 {
+            return Ok(());
+        }
+        if !game_state.npc_inventories.contains_key(&transfer.to) {
+            return Err("Npc has no inventory".to_owned());
+        }
+        if game_state.get_inv_item_count(&transfer.from, transfer.item_symbol) < transfer.amount {
+            return Err(format!(
+                "Npc does not carry {} items of {}",
+                transfer.amount, transfer.item_symbol
+            ));
+        }
+        let inventory = &game_state.npc_inventories[&transfer.from];
+        let mut matching: Vec<Handle> = inventory
+            .get_items()
+            .iter()
+            .copied()
+            .filter(|handle| {
+                game_state
+                    .items
+                    .get(handle)
+                    .is_some_and(|item| item.get_instance_symbol() == transfer.item_symbol)
+            })
+            .collect();
+        // unequipped items go first
+        matching.sort_by_key(|handle| inventory.is_equipped(handle));
+
+        let mut left = transfer.amount;
+        for item in matching {
+            if left == 0 {
+                break;
+            }
+            let stack = game_state.items.get(&item).unwrap().amount.max(1);
+            match stack > left {
+                true => self.split_item(game_state, transfer.to, item, left)?,
+                false => self.move_item(game_state, transfer.from, transfer.to, item),
+            }
+            left -= stack.min(left);
+        }
+        Ok(())
+    }
+    /// Moves the whole object, stackable items are merged into a stack of the new owner
+    fn move_item(&mut self, game_state: &mut GameState, from: Handle, to: Handle, item: Handle) {
+        let (position, slot) = game_state
+            .npc_inventories
+            .get_mut(&from)
+            .and_then(|inventory| inventory.detach(&item))
+            .unwrap();
+        self.changes.push(Change::Detached {
+            npc: from,
+            item,
+            position,
+            slot,
+        });
+        let object = game_state.items.get(&item).unwrap();
+        let amount = object.amount.max(1);
+        match game_state.find_stack(&to, object.get_instance_symbol()) {
+            Some(stack) if object.is_stackable() => {
+                game_state.items.get_mut(&stack).unwrap().amount += amount;
+                self.changes.push(Change::Grown {
+                    item: stack,
+                    amount,
+                });
+                self.changes.push(Change::Merged { item });
+            }
+            _ => {
+                game_state.npc_inventories.get_mut(&to).unwrap().push(item);
+                self.changes.push(Change::Attached { npc: to, item });
+            }
+        }
+    }
+    /// Moves `amount` items of the stack, a new stack is a copy of the old one
+    fn split_item(
+        &mut self,
+        game_state: &mut GameState,
+        to: Handle,
+        item: Handle,
+        amount: u32,
+    ) -> Result<(), String> {
+        let object = game_state.items.get(&item).unwrap();
+        match game_state.find_stack(&to, object.get_instance_symbol()) {
+            Some(stack) => {
+                game_state.items.get_mut(&stack).unwrap().amount += amount;
+                self.changes.push(Change::Grown {
+                    item: stack,
+                    amount,
+                });
+            }
+            None => {
+                let mut split = object.clone();
+                split.amount = amount;
+                let split = game_state.items.insert(split)?;
+                self.changes.push(Change::Created { item: split });
+                game_state.npc_inventories.get_mut(&to).unwrap().push(split);
+                self.changes.push(Change::Attached {
+                    npc: to,
+                    item: split,
+                });
+            }
+        }
+        game_state.items.get_mut(&item).unwrap().amount -= amount;
+        self.changes.push(Change::Shrunk { item, amount });
+        Ok(())
+    }
+    /// Undoes all changes in reverse order
+    fn rollback(self, game_state: &mut GameState) {
+        for change in self.changes.into_iter().rev() {
+            match change {
+                Change::Detached {
+                    npc,
+                    item,
+                    position,
+                    slot,
+                } => {
+                    if let Some(inventory) = game_state.npc_inventories.get_mut(&npc) {
+                        inventory.restore(item, position, slot);
+                    }
+                }
+                Change::Attached { npc, item } => {
+                    if let Some(inventory) = game_state.npc_inventories.get_mut(&npc) {
+                        inventory.remove(&item);
+                    }
+                }
+                Change::Grown { item, amount } => {
+                    if let Some(object) = game_state.items.get_mut(&item) {
+                        object.amount -= amount;
+                    }
+                }
+                Change::Shrunk { item, amount } => {
+                    if let Some(object) = game_state.items.get_mut(&item) {
+                        object.amount += amount;
+                    }
+                }
+                Change::Created { item } => game_state.items.remove(&item),
+                Change::Merged { .. } => (),
+            }
+        }
+    }
+    /// Runs `on_unequip` of the items which were equipped by their old owner,
+    /// removes merged objects and reports the new items to the host
+    fn commit(self, virtual_machine: &mut VirtualMachine) {
+        for change in self.changes.iter() {
+            if let Change::Detached {
+                npc,
+                item,
+                slot: Some(_),
+                ..
+            } = change
+            {
+                let on_unequip = virtual_machine
+                    .get_game_state()
+                    .get_item(item)
+                    .map_or(0, |item| item.get_on_unequip());
+                run_equip_func(virtual_machine, *npc, *item, on_unequip);
+            }
+        }
+        let game_state = virtual_machine.get_mut_game_state();
+        for change in self.changes {
+            match change {
+                Change::Merged { item } => game_state.items.remove(&item),
+                Change::Attached { npc, item } => {
+                    if let Some(func) = &mut game_state.game_externals.create_inv_item {
+                        func(item, npc);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Runs all transfers or none of them
+fn transfer_all(
+    virtual_machine: &mut VirtualMachine,
+    transfers: &[Transfer],
+) -> Result<(), TradeError> {
+    let mut transaction = Transaction::default();
+    let game_state = virtual_machine.get_mut_game_state();
+    for transfer in transfers.iter() {
+        if let Err(error) = transaction.transfer(game_state, *transfer) {
+            transaction.rollback(game_state);
+            return Err(TradeError::Failed(error));
+        }
+    }
+    transaction.commit(virtual_machine);
+    Ok(())
+}
+
+/// Outcome of a barter offer
+#[derive(Clone, Debug, PartialEq)]
+pub enum BarterResult {
+    /// The trader gave `amount` items of the instance
+    Accepted { item_symbol: usize, amount: u32 },
+    /// A matching reaction function returned false
+    Declined,
+    /// No `C_ITEMREACT` of the trader requests the offered items
+    NoReaction,
+}
+
+/// Trade between a trader and a customer, prices are `Item::value` times the multipliers
+pub struct TradeSession {
+    trader: Handle,
+    customer: Handle,
+    currency: usize,
+    buy_multiplier: f32,
+    sell_multiplier: f32,
+}
+
+impl TradeSession {
+    /// `currency` is the item instance used as money, e.g. `ItMiNugget` or `ItMi_Gold`
+    pub fn new(trader: Handle, customer: Handle, currency: usize) -> Self {
+        Self {
+            trader,
+            customer,
+            currency,
+            buy_multiplier: 1.0,
+            sell_multiplier: 1.0,
+        }
+    }
+    /// Factor on the value when the customer buys from the trader
+    pub fn with_buy_multiplier(mut self, buy_multiplier: f32) -> Self {
+        self.buy_multiplier = buy_multiplier;
+        self
+    }
+    /// Factor on the value when the customer sells to the trader
+    pub fn with_sell_multiplier(mut self, sell_multiplier: f32) -> Self {
+        self.sell_multiplier = sell_multiplier;
+        self
+    }
+    pub fn get_trader(&self) -> Handle {
+        self.trader
+    }
+    pub fn get_customer(&self) -> Handle {
+        self.customer
+    }
+    pub fn get_currency(&self) -> usize {
+        self.currency
+    }
+    fn get_unit_value(game_state: &GameState, npc: &Handle, item_symbol: usize) -> Option<i32> {
+        game_state
+            .get_inv_of(npc)?
+            .get_items()
+            .iter()
+            .filter_map(|handle| game_state.get_item(handle))
+            .find(|item| item.get_instance_symbol() == item_symbol)
+            .map(|item| item.get_value())
+    }
+    fn get_price(value: i32, multiplier: f32, amount: u32) -> u32 {
+        ((value as f32 * multiplier).round().max(0.0) as u32) * amount
+    }
+    /// Price the customer pays for items the trader carries
+    pub fn get_buy_price(
+        &self,
+        game_state: &GameState,
+        item_symbol: usize,
+        amount: u32,
+    ) -> Option<u32> {
+        let value = Self::get_unit_value(game_state, &self.trader, item_symbol)?;
+        Some(Self::get_price(value, self.buy_multiplier, amount))
+    }
+    /// Price the trader pays for items the customer carries
+    pub fn get_sell_price(
+        &self,
+        game_state: &GameState,
+        item_symbol: usize,
+        amount: u32,
+    ) -> Option<u32> {
+        let value = Self::get_unit_value(game_state, &self.customer, item_symbol)?;
+        Some(Self::get_price(value, self.sell_multiplier, amount))
+    }
+    /// Customer buys from the trader, returns the paid price
+    pub fn buy(
+        &self,
+        virtual_machine: &mut VirtualMachine,
+        item_symbol: usize,
+        amount: u32,
+    ) -> Result<u32, TradeError> {
+        let price = match self.get_buy_price(virtual_machine.get_game_state(), item_symbol, amount)
+        {
+            Some(price) => price,
+            None => {
+                return Err(TradeError::Failed(
+                    "Trader does not carry the item".to_owned(),
+                ))
+            }
+        };
+        let transfers = [
+            Transfer {
+                from: self.customer,
+                to: self.trader,
+                item_symbol: self.currency,
+                amount: price,
+            },
+            Transfer {
+                from: self.trader,
+                to: self.customer,
+                item_symbol,
+                amount,
+            },
+        ];
+        transfer_all(virtual_machine, &transfers)?;
+        Ok(price)
+    }
+    /// Customer sells to the trader, returns the received price
+    pub fn sell(
+        &self,
+        virtual_machine: &mut VirtualMachine,
+        item_symbol: usize,
+        amount: u32,
+    ) -> Result<u32, TradeError> {
+        let price = match self.get_sell_price(virtual_machine.get_game_state(), item_symbol, amount)
+        {
+            Some(price) => price,
+            None => {
+                return Err(TradeError::Failed(
+                    "Customer does not carry the item".to_owned(),
+                ))
+            }
+        };
+        let transfers = [
+            Transfer {
+                from: self.customer,
+                to: self.trader,
+                item_symbol,
+                amount,
+            },
+            Transfer {
+                from: self.trader,
+                to: self.customer,
+                item_symbol: self.currency,
+                amount: price,
+            },
+        ];
+        transfer_all(virtual_machine, &transfers)?;
+        Ok(price)
+    }
+    /// Reactions of the trader requesting the item, either by instance or by category
+    pub fn get_item_reactions(&self, game_state: &GameState, item_symbol: usize) -> Vec<Handle> {
+        let trader_symbol = match game_state.get_npc(&self.trader) {
+            Some(npc) => npc.get_instance_symbol(),
+            None => return vec![],
+        };
+        let categories = Self::get_categories(game_state, &self.customer, item_symbol);
+        game_state
+            .item_reacts
+            .iter()
+            .filter(|(_, react)| react.get_npc() as usize == trader_symbol)
+            .filter(|(_, react)| {
+                react.get_requested_item() as usize == item_symbol
+                    || (react.get_requested_cat() != 0
+                        && categories.bits() & react.get_requested_cat() as u32 != 0)
+            })
+            .map(|(handle, _)| handle)
+            .collect()
+    }
+    fn get_categories(
+        game_state: &GameState,
+        npc: &Handle,
+        item_symbol: usize,
+    ) -> BitFlags<Categories> {
+        game_state
+            .get_inv_of(npc)
+            .and_then(|inventory| {
+                inventory
+                    .get_items()
+                    .iter()
+                    .filter_map(|handle| game_state.get_item(handle))
+                    .find(|item| item.get_instance_symbol() == item_symbol)
+            })
+            .map_or(BitFlags::empty(), |item| item.get_main_flag())
+    }
+    /// Offers items of the customer in exchange for the `trade_item` of a matching reaction.
+    /// The first reaction whose amount is met and whose function accepts is carried out,
+    /// either all items change owner or none.
+    pub fn barter(
+        &self,
+        virtual_machine: &mut VirtualMachine,
+        item_symbol: usize,
+        amount: u32,
+    ) -> Result<BarterResult, TradeError> {
+        let game_state = virtual_machine.get_game_state();
+        if game_state.get_inv_item_count(&self.customer, item_symbol) < amount {
+            return Err(TradeError::Failed(
+                "Customer does not carry the offered items".to_owned(),
+            ));
+        }
+        let reactions: Vec<(i32, u32, u32, i32)> = self
+            .get_item_reactions(game_state, item_symbol)
+            .iter()
+            .filter_map(|handle| game_state.item_reacts.get(handle))
+            .map(|react| {
+                (
+                    react.get_trade_item(),
+                    react.get_trade_amount().max(0) as u32,
+                    react.get_requested_amount().max(0) as u32,
+                    react.get_reaction() as i32,
+                )
+            })
+            .collect();
+        let mut result = BarterResult::NoReaction;
+        for (trade_item, trade_amount, requested_amount, reaction) in reactions {
+            if amount < requested_amount {
+                continue;
+            }
+            if reaction != 0 {
+                let (trader, customer) = (self.trader, Some(self.customer));
+                let reaction = reaction as usize;
+                if run_func_with_participants(virtual_machine, reaction, trader, customer)? == 0 {
+                    result = BarterResult::Declined;
+                    continue;
+                }
+            }
+            let transfers = [
+                Transfer {
+                    from: self.customer,
+                    to: self.trader,
+                    item_symbol,
+                    amount: requested_amount,
+                },
+                Transfer {
+                    from: self.trader,
+                    to: self.customer,
+                    item_symbol: trade_item as usize,
+                    amount: trade_amount,
+                },
+            ];
+            transfer_all(virtual_machine, &transfers)?;
+            return Ok(BarterResult::Accepted {
+                item_symbol: trade_item as usize,
+                amount: trade_amount,
+            });
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::instances::item::Flags;
+    use crate::vm::file::test_file::TestFile;
+
+    const GOLD: usize = 10;
+    const SWORD: usize = 11;
+    const ARROW: usize = 12;
+    const AMULET: usize = 13;
+
+    fn create_npc(virtual_machine: &mut VirtualMachine, instance_symbol: usize) -> Handle {
+        let game_state = virtual_machine.get_mut_game_state();
+        let npc = game_state.create_object(InstanceClass::Npc).unwrap();
+        game_state.set_instance_symbol(InstanceClass::Npc, &npc, instance_symbol);
+        npc
+    }
+
+    fn give(
+        virtual_machine: &mut VirtualMachine,
+        npc: Handle,
+        item_symbol: usize,
+        amount: u32,
+    ) -> Handle {
+        let game_state = virtual_machine.get_mut_game_state();
+        let item = game_state.create_object(InstanceClass::Item).unwrap();
+        game_state.set_instance_symbol(InstanceClass::Item, &item, item_symbol);
+        let object = game_state.get_mut_item(&item).unwrap();
+        object.amount = amount;
+        object.set_value(5);
+        match item_symbol {
+            SWORD => object.set_flags(Flags::Sword.into()),
+            AMULET => object.set_flags(Flags::Amulet.into()),
+            _ => object.set_flags(Flags::Multi.into()),
+        }
+        game_state.add_item_to_inv(&item, &npc).unwrap()
+    }
+
+    /// Items of the inventory with their instance, amount and slot
+    fn contents(
+        virtual_machine: &VirtualMachine,
+        npc: Handle,
+    ) -> Vec<(Handle, usize, u32, Option<EquipSlot>)> {
+        let game_state = virtual_machine.get_game_state();
+        let inventory = game_state.get_inv_of(&npc).unwrap();
+        inventory
+            .get_items()
+            .iter()
+            .map(|handle| {
+                let item = game_state.get_item(handle).unwrap();
+                let slot = inventory.get_slot_of(handle);
+                (*handle, item.get_instance_symbol(), item.amount, slot)
+            })
+            .collect()
+    }
+
+    fn add_reaction(
+        virtual_machine: &mut VirtualMachine,
+        trader_symbol: usize,
+        requested: (usize, u32),
+        traded: (usize, u32),
+    ) {
+        let game_state = virtual_machine.get_mut_game_state();
+        let react = game_state.create_object(InstanceClass::ItemReact).unwrap();
+        let react = game_state.item_reacts.get_mut(&react).unwrap();
+        react.set_npc(trader_symbol as i32);
+        react.set_requested_item(requested.0 as i32);
+        react.set_requested_amount(requested.1 as i32);
+        react.set_trade_item(traded.0 as i32);
+        react.set_trade_amount(traded.1 as i32);
+    }
+
+    /// Trader with 20 gold and one amulet, customer with 3 swords, one equipped, and 10 arrows
+    fn setup() -> (VirtualMachine, Handle, Handle) {
+        let mut virtual_machine = VirtualMachine::from_file(TestFile::new().build());
+        let trader = create_npc(&mut virtual_machine, 1);
+        let customer = create_npc(&mut virtual_machine, 2);
+        give(&mut virtual_machine, trader, GOLD, 20);
+        give(&mut virtual_machine, trader, AMULET, 1);
+        let sword = give(&mut virtual_machine, customer, SWORD, 1);
+        give(&mut virtual_machine, customer, SWORD, 1);
+        give(&mut virtual_machine, customer, SWORD, 1);
+        give(&mut virtual_machine, customer, ARROW, 10);
+        GameState::equip_item(&mut virtual_machine, customer, sword).unwrap();
+        (virtual_machine, trader, customer)
+    }
+
+    #[test]
+    fn failed_barter_leaves_inventories_unchanged() {
+        let (mut virtual_machine, trader, customer) = setup();
+        // the trader owes more gold than it carries
+        add_reaction(&mut virtual_machine, 1, (SWORD, 3), (GOLD, 50));
+        let (trader_items, customer_items) = (
+            contents(&virtual_machine, trader),
+            contents(&virtual_machine, customer),
+        );
+        let session = TradeSession::new(trader, customer, GOLD);
+        assert!(session.barter(&mut virtual_machine, SWORD, 3).is_err());
+        assert_eq!(contents(&virtual_machine, trader), trader_items);
+        assert_eq!(contents(&virtual_machine, customer), customer_items);
+    }
+
+    #[test]
+    fn failed_barter_restores_split_stacks() {
+        let (mut virtual_machine, trader, customer) = setup();
+        add_reaction(&mut virtual_machine, 1, (ARROW, 4), (AMULET, 2));
+        let (trader_items, customer_items) = (
+            contents(&virtual_machine, trader),
+            contents(&virtual_machine, customer),
+        );
+        let item_count = virtual_machine
+            .get_game_state()
+            .get_handles_of(InstanceClass::Item)
+            .len();
+        let session = TradeSession::new(trader, customer, GOLD);
+        assert!(session.barter(&mut virtual_machine, ARROW, 4).is_err());
+        assert_eq!(contents(&virtual_machine, trader), trader_items);
+        assert_eq!(contents(&virtual_machine, customer), customer_items);
+        let game_state = virtual_machine.get_game_state();
+        assert_eq!(
+            game_state.get_handles_of(InstanceClass::Item).len(),
+            item_count
+        );
+    }
+
+    #[test]
+    fn barter_moves_item_objects() {
+        let (mut virtual_machine, trader, customer) = setup();
+        add_reaction(&mut virtual_machine, 1, (SWORD, 3), (AMULET, 1));
+        let swords: Vec<Handle> = contents(&virtual_machine, customer)
+            .into_iter()
+            .filter(|(_, item_symbol, _, _)| *item_symbol == SWORD)
+            .map(|(handle, _, _, _)| handle)
+            .collect();
+        let amulet = contents(&virtual_machine, trader)[1].0;
+        let session = TradeSession::new(trader, customer, GOLD);
+        let result = session.barter(&mut virtual_machine, SWORD, 3).unwrap();
+        assert_eq!(
+            result,
+            BarterResult::Accepted {
+                item_symbol: AMULET,
+                amount: 1
+            }
+        );
+        let game_state = virtual_machine.get_game_state();
+        let (trader_inv, customer_inv) = (
+            game_state.get_inv_of(&trader).unwrap(),
+            game_state.get_inv_of(&customer).unwrap(),
+        );
+        for sword in swords.iter() {
+            assert!(trader_inv.contains(sword));
+            assert!(!trader_inv.is_equipped(sword));
+        }
+        assert!(customer_inv.contains(&amulet));
+        assert_eq!(customer_inv.get_equipped(EquipSlot::MeleeWeapon), None);
+    }
+
+    #[test]
+    fn barter_without_reaction() {
+        let (mut virtual_machine, trader, customer) = setup();
+        let session = TradeSession::new(trader, customer, GOLD);
+        let result = session.barter(&mut virtual_machine, ARROW, 1).unwrap();
+        assert_eq!(result, BarterResult::NoReaction);
+        assert!(session.barter(&mut virtual_machine, ARROW, 11).is_err());
+    }
+
+    #[test]
+    fn buy_splits_and_merges_stacks() {
+        let (mut virtual_machine, trader, customer) = setup();
+        give(&mut virtual_machine, customer, GOLD, 10);
+        let gold = contents(&virtual_machine, trader)[0].0;
+        let session = TradeSession::new(customer, trader, GOLD).with_buy_multiplier(2.0);
+        // roles swapped, `trader` pays 20 gold for 2 arrows of `customer`
+        assert_eq!(session.buy(&mut virtual_machine, ARROW, 2), Ok(20));
+        let game_state = virtual_machine.get_game_state();
+        assert_eq!(game_state.get_inv_item_count(&trader, GOLD), 0);
+        assert_eq!(game_state.get_inv_item_count(&customer, GOLD), 30);
+        assert_eq!(game_state.get_inv_item_count(&customer, ARROW), 8);
+        assert_eq!(game_state.get_inv_item_count(&trader, ARROW), 2);
+        // the whole stack was merged into the gold of the customer
+        assert!(game_state.get_item(&gold).is_none());
+    }
+
+    #[test]
+    fn failed_buy_leaves_inventories_unchanged() {
+        let (mut virtual_machine, trader, customer) = setup();
+        let (trader_items, customer_items) = (
+            contents(&virtual_machine, trader),
+            contents(&virtual_machine, customer),
+        );
+        let session = TradeSession::new(trader, customer, GOLD);
+        // the customer carries no gold
+        assert!(session.buy(&mut virtual_machine, AMULET, 1).is_err());
+        assert_eq!(contents(&virtual_machine, trader), trader_items);
+        assert_eq!(contents(&virtual_machine, customer), customer_items);
+    }
+}
@@ -0,0 +1,91 @@
+use super::object_allocator::ObjectAllocator;
+use super::GameState;
+use crate::stdlib::prelude::*;
+use serde::Serialize;
+
+/// Object together with its handle.
+/// Handles are only valid for one session, so the position in the allocator is used instead.
+#[derive(Serialize)]
+pub struct ObjectView<'a, T> {
+    pub handle: u32,
+    pub object: &'a T,
+}
+
+#[derive(Serialize)]
+pub struct InventoryView {
+    pub npc: u32,
+    pub items: Vec<u32>,
+    pub equipped: Vec<u32>,
+}
+
+/// Serialisable snapshot of all objects, e.g. to dump items or npcs as JSON
+#[derive(Serialize)]
+pub struct GameStateView<'a> {
+    pub npcs: Vec<ObjectView<'a, Npc>>,
+    pub items: Vec<ObjectView<'a, Item>>,
+    pub item_reacts: Vec<ObjectView<'a, ItemReact>>,
+    pub missions: Vec<ObjectView<'a, Mission>>,
+    pub focuses: Vec<ObjectView<'a, Focus>>,
+    pub infos: Vec<ObjectView<'a, Info>>,
+    pub menu: Vec<ObjectView<'a, Menu>>,
+    pub menu_items: Vec<ObjectView<'a, MenuItem>>,
+    pub sound_effects: Vec<ObjectView<'a, SoundEffect>>,
+    pub particle_effects: Vec<ObjectView<'a, ParticleEffect>>,
+    pub music_themes: Vec<ObjectView<'a, MusicTheme>>,
+    pub dynamic_instances: Vec<ObjectView<'a, DynamicInstance>>,
+    pub npc_inventories: Vec<InventoryView>,
+}
+
+fn view_allocator<T: Default>(allocator: &ObjectAllocator<T>) -> Vec<ObjectView<'_, T>> {
+    allocator
+        .iter()
+        .enumerate()
+        .map(|(slot, (_, object))| ObjectView {
+            handle: slot as u32,
+            object,
+        })
+        .collect()
+}
+
+impl GameState {
+    pub fn get_view(&self) -> GameStateView<'_> {
+        let slots = self.get_handle_slots();
+        let mut npc_inventories: Vec<InventoryView> = self
+            .npc_inventories
+            .iter()
+            .filter_map(|(npc, inventory)| {
+                Some(InventoryView {
+                    npc: slots.get(InstanceClass::Npc, npc)?,
+                    items: inventory
+                        .get_items()
+                        .iter()
+                        .filter_map(|item| slots.get(InstanceClass::Item, item))
+                        .collect(),
+                    equipped: inventory
+                        .get_items()
+                        .iter()
+                        .filter(|item| inventory.is_equipped(item))
+                        .filter_map(|item| slots.get(InstanceClass::Item, item))
+                        .collect(),
+                })
+            })
+            .collect();
+        npc_inventories.sort_unstable_by_key(|inventory| inventory.npc);
+
+        GameStateView {
+            npcs: view_allocator(&self.npcs),
+            items: view_allocator(&self.items),
+            item_reacts: view_allocator(&self.item_reacts),
+            missions: view_allocator(&self.missions),
+            focuses: view_allocator(&self.focuses),
+            infos: view_allocator(&self.infos),
+            menu: view_allocator(&self.menu),
+            menu_items: view_allocator(&self.menu_items),
+            sound_effects: view_allocator(&self.sound_effects),
+            particle_effects: view_allocator(&self.particle_effects),
+            music_themes: view_allocator(&self.music_themes),
+            dynamic_instances: view_allocator(&self.dynamic_instances),
+            npc_inventories,
+        }
+    }
+}
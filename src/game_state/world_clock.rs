@@ -0,0 +1,227 @@
+use super::GameState;
+use crate::persist_struct;
+use zen_memory::Handle;
+
+pub const MINUTES_PER_HOUR: i32 = 60;
+pub const MINUTES_PER_DAY: i32 = 24 * MINUTES_PER_HOUR;
+
+/// In-game time, advanced by the host
+pub struct WorldClock {
+    day: i32,
+    /// Minute of the day including the fraction
+    minute_of_day: f32,
+    /// Game minutes passing per real second, the default lasts a day about 100 minutes
+    time_scale: f32,
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        Self {
+            day: 0,
+            minute_of_day: 0.0,
+            time_scale: 0.24,
+        }
+    }
+}
+
+impl WorldClock {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn with_time_scale(mut self, time_scale: f32) -> Self {
+        self.time_scale = time_scale;
+        self
+    }
+    pub fn get_day(&self) -> i32 {
+        self.day
+    }
+    pub fn get_hour(&self) -> i32 {
+        self.minute_of_day as i32 / MINUTES_PER_HOUR
+    }
+    pub fn get_minute(&self) -> i32 {
+        self.minute_of_day as i32 % MINUTES_PER_HOUR
+    }
+    /// Minutes since midnight
+    pub fn get_minute_of_day(&self) -> i32 {
+        self.minute_of_day as i32
+    }
+    pub fn get_time_scale(&self) -> f32 {
+        self.time_scale
+    }
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale;
+    }
+    pub fn set_day(&mut self, day: i32) {
+        self.day = day;
+    }
+    /// Moves to the time on the current day without passing midnight
+    pub fn set_time_of_day(&mut self, hour: i32, minute: i32) {
+        self.minute_of_day = (hour * MINUTES_PER_HOUR + minute).rem_euclid(MINUTES_PER_DAY) as f32;
+    }
+    /// Advances by real seconds scaled by the time scale, returns the number of started days
+    pub fn advance(&mut self, real_seconds: f32) -> i32 {
+        self.advance_minutes(real_seconds * self.time_scale)
+    }
+    /// Advances by game minutes, returns the number of started days
+    pub fn advance_minutes(&mut self, minutes: f32) -> i32 {
+        self.minute_of_day += minutes.max(0.0);
+        let days = (self.minute_of_day / MINUTES_PER_DAY as f32) as i32;
+        self.minute_of_day -= (days * MINUTES_PER_DAY) as f32;
+        self.day += days;
+        days
+    }
+    /// Whether the current time lies in `[start, end)`, ranges may wrap around midnight
+    pub fn is_time(
+        &self,
+        start_hour: i32,
+        start_minute: i32,
+        end_hour: i32,
+        end_minute: i32,
+    ) -> bool {
+        let start = start_hour * MINUTES_PER_HOUR + start_minute;
+        let end = end_hour * MINUTES_PER_HOUR + end_minute;
+        is_in_range(self.get_minute_of_day(), start, end)
+    }
+}
+
+/// Whether the minute of the day lies in `[start, end)`, wrapping around midnight if `start > end`
+pub fn is_in_range(minute_of_day: i32, start: i32, end: i32) -> bool {
+    match start <= end {
+        true => start <= minute_of_day && minute_of_day < end,
+        false => minute_of_day >= start || minute_of_day < end,
+    }
+}
+
+persist_struct!(WorldClock {
+    day,
+    minute_of_day,
+    time_scale,
+});
+
+impl GameState {
+    pub fn get_world_clock(&self) -> &WorldClock {
+        &self.world_clock
+    }
+    pub fn get_mut_world_clock(&mut self) -> &mut WorldClock {
+        &mut self.world_clock
+    }
+    /// Day of the world clock, used to expire missions
+    #[allow(deprecated)]
+    pub fn get_current_day(&mut self) -> i32 {
+        match &mut self.game_externals.get_day {
+            Some(func) => func(),
+            None => self.world_clock.get_day(),
+        }
+    }
+    fn fire_new_days(&mut self, days: i32) {
+        for day in self.world_clock.get_day() - days + 1..=self.world_clock.get_day() {
+            if let Some(func) = &mut self.game_externals.new_day {
+                func(day);
+            }
+        }
+    }
+    /// Advances the world clock and the state time of all npcs by real seconds
+    pub fn advance_time(&mut self, real_seconds: f32) {
+        let days = self.world_clock.advance(real_seconds);
+        for state_time in self.npc_state_times.values_mut() {
+            *state_time += real_seconds;
+        }
+        self.fire_new_days(days);
+    }
+    /// Like `Wld_SetTime`, an earlier time than the current one starts the next day
+    pub fn set_time(&mut self, hour: i32, minute: i32) {
+        let target = (hour * MINUTES_PER_HOUR + minute).rem_euclid(MINUTES_PER_DAY);
+        let current = self.world_clock.get_minute_of_day();
+        let minutes = (target - current).rem_euclid(MINUTES_PER_DAY);
+        let days = self.world_clock.advance_minutes(minutes as f32);
+        self.world_clock.set_time_of_day(hour, minute);
+        self.fire_new_days(days);
+    }
+    /// Whether the timed particle effect is visible at the current time
+    pub fn is_particle_effect_active(&self, handle: &Handle) -> bool {
+        let hour = self.world_clock.get_minute_of_day() as f32 / MINUTES_PER_HOUR as f32;
+        self.particle_effects
+            .get(handle)
+            .is_some_and(|effect| effect.is_active_at(hour))
+    }
+    /// Seconds the npc spent in its current ai state
+    pub fn get_npc_state_time(&self, npc: &Handle) -> f32 {
+        self.npc_state_times.get(npc).copied().unwrap_or(0.0)
+    }
+    pub fn set_npc_state_time(&mut self, npc: &Handle, seconds: f32) {
+        self.npc_state_times.insert(*npc, seconds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::GameExternals;
+    use std::sync::{Arc, Mutex};
+
+    fn game_state_with_new_days() -> (GameState, Arc<Mutex<Vec<i32>>>) {
+        let days = Arc::new(Mutex::new(vec![]));
+        let mut game_externals = GameExternals::new();
+        let new_days = days.clone();
+        game_externals.new_day = Some(Box::new(move |day| new_days.lock().unwrap().push(day)));
+        (GameState::new(game_externals), days)
+    }
+
+    #[test]
+    fn range_wraps_around_midnight() {
+        assert!(is_in_range(8 * 60, 7 * 60, 22 * 60));
+        assert!(!is_in_range(22 * 60, 7 * 60, 22 * 60));
+        assert!(is_in_range(23 * 60, 22 * 60, 7 * 60));
+        assert!(is_in_range(3 * 60, 22 * 60, 7 * 60));
+        assert!(!is_in_range(12 * 60, 22 * 60, 7 * 60));
+    }
+
+    #[test]
+    fn advance_minutes_counts_started_days() {
+        let mut world_clock = WorldClock::new();
+        world_clock.set_time_of_day(23, 0);
+        assert_eq!(world_clock.advance_minutes(30.0), 0);
+        assert_eq!(
+            world_clock.advance_minutes(30.0 + 2.0 * MINUTES_PER_DAY as f32),
+            3
+        );
+        assert_eq!(world_clock.get_day(), 3);
+        assert_eq!(world_clock.get_hour(), 0);
+        assert_eq!(world_clock.get_minute(), 0);
+        assert_eq!(world_clock.advance_minutes(-10.0), 0);
+        assert_eq!(world_clock.get_minute_of_day(), 0);
+    }
+
+    #[test]
+    fn advance_scales_real_seconds() {
+        let mut world_clock = WorldClock::new().with_time_scale(1.0);
+        world_clock.advance(90.0);
+        assert_eq!(world_clock.get_hour(), 1);
+        assert_eq!(world_clock.get_minute(), 30);
+        assert!(world_clock.is_time(1, 0, 2, 0));
+        assert!(!world_clock.is_time(2, 0, 1, 0));
+    }
+
+    #[test]
+    fn set_time_rolls_over_to_the_next_day() {
+        let (mut game_state, days) = game_state_with_new_days();
+        game_state.set_time(20, 0);
+        assert_eq!(game_state.get_world_clock().get_day(), 0);
+        game_state.set_time(6, 30);
+        assert_eq!(game_state.get_world_clock().get_day(), 1);
+        assert_eq!(game_state.get_world_clock().get_hour(), 6);
+        assert_eq!(game_state.get_world_clock().get_minute(), 30);
+        assert_eq!(*days.lock().unwrap(), [1]);
+    }
+
+    #[test]
+    fn advance_time_fires_every_new_day() {
+        let (mut game_state, days) = game_state_with_new_days();
+        game_state
+            .get_mut_world_clock()
+            .set_time_scale(MINUTES_PER_DAY as f32);
+        game_state.advance_time(2.5);
+        assert_eq!(game_state.get_current_day(), 2);
+        assert_eq!(*days.lock().unwrap(), [1, 2]);
+    }
+}
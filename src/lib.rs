@@ -1,6 +1,9 @@
-#![feature(vec_remove_item)]
+// lets `#[derive(DaedalusClass)]` refer to this crate as `::daedalus`
+extern crate self as daedalus;
 
 pub mod game_state;
+pub mod savegame;
+pub mod snapshot;
 pub mod stdlib;
 pub mod vm;
 #[cfg(test)]
@@ -9,4 +12,9 @@ mod tests {
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+    #[test]
+    fn virtual_machine_is_send() {
+        fn is_send<T: Send>() {}
+        is_send::<crate::vm::VirtualMachine>();
+    }
 }
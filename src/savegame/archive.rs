@@ -0,0 +1,316 @@
+use zen_parser::ZenParser;
+
+// Value types of a BIN_SAFE archive
+const BS_STRING: u8 = 0x01;
+const BS_INT: u8 = 0x02;
+const BS_FLOAT: u8 = 0x03;
+const BS_BYTE: u8 = 0x04;
+const BS_WORD: u8 = 0x05;
+const BS_BOOL: u8 = 0x06;
+const BS_VEC3: u8 = 0x07;
+const BS_COLOR: u8 = 0x08;
+const BS_RAW: u8 = 0x09;
+const BS_RAW_FLOAT: u8 = 0x10;
+const BS_ENUM: u8 = 0x11;
+const BS_HASH: u8 = 0x12;
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    String(String),
+    Int(i32),
+    Float(f32),
+    Bool(bool),
+    Enum(u32),
+    Raw(Vec<u8>),
+}
+
+impl Value {
+    pub fn as_int(&self) -> Option<i32> {
+        match self {
+            Value::Int(val) => Some(*val),
+            Value::Enum(val) => Some(*val as i32),
+            Value::Bool(val) => Some(*val as i32),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(val) => Some(val.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Single named value of a ZenGin archive, object brackets are skipped
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub value: Value,
+}
+
+fn read_line(parser: &ZenParser) -> Result<String, String> {
+    let mut line = String::new();
+    loop {
+        match parser.read_binary::<u8>() {
+            Ok(b'\n') => break,
+            Ok(b'\r') => (),
+            Ok(ch) => line.push(ch as char),
+            Err(_) if !line.is_empty() => break,
+            Err(_) => return Err("Unexpected end of archive".to_owned()),
+        }
+    }
+    Ok(line)
+}
+
+fn read_string(parser: &ZenParser, len: usize) -> Result<String, String> {
+    let bytes = parser
+        .read_binary_as_vec::<u8>(len)
+        .map_err(|_| "Unexpected end of archive".to_owned())?;
+    Ok(bytes.into_iter().map(|ch| ch as char).collect())
+}
+
+fn read<T>(parser: &ZenParser) -> Result<T, String> {
+    parser
+        .read_binary::<T>()
+        .map_err(|_| "Unexpected end of archive".to_owned())
+}
+
+/// Reads all values of a ZenGin archive in the order they are stored
+pub fn read_entries(parser: &ZenParser) -> Result<Vec<Entry>, String> {
+    if read_line(parser)? != "ZenGin Archive" {
+        return Err("Not a ZenGin archive".to_owned());
+    }
+    let mut format = String::new();
+    loop {
+        let line = read_line(parser)?;
+        match line.trim() {
+            "END" => break,
+            "ASCII" | "BINARY" | "BIN_SAFE" => format = line.trim().to_owned(),
+            _ => (),
+        }
+    }
+    match format.as_str() {
+        "BIN_SAFE" => read_bin_safe(parser),
+        "ASCII" => {
+            // object count block
+            while read_line(parser)?.trim() != "END" {}
+            read_ascii(parser)
+        }
+        _ => Err(format!("Unsupported archive format {}", format)),
+    }
+}
+
+fn read_bin_safe(parser: &ZenParser) -> Result<Vec<Entry>, String> {
+    let _version = read::<u32>(parser)?;
+    let _object_count = read::<u32>(parser)?;
+    let hash_table_offset = read::<u32>(parser)? as usize;
+    let data_start = parser.get_seek();
+
+    parser.set_seek(hash_table_offset);
+    let count = read::<u32>(parser)? as usize;
+    let mut keys = vec![String::new(); count];
+    for _ in 0..count {
+        let len = read::<u16>(parser)? as usize;
+        let insertion_index = read::<u16>(parser)? as usize;
+        let _hash = read::<u32>(parser)?;
+        let name = read_string(parser, len)?;
+        match keys.get_mut(insertion_index) {
+            Some(key) => *key = name,
+            None => return Err(format!("Invalid key index {}", insertion_index)),
+        }
+    }
+
+    parser.set_seek(data_start);
+    let mut entries = vec![];
+    let mut name = String::new();
+    while parser.get_seek() < hash_table_offset {
+        let value = match read::<u8>(parser)? {
+            BS_HASH => {
+                let key = read::<u32>(parser)? as usize;
+                name = keys.get(key).cloned().unwrap_or_default();
+                continue;
+            }
+            BS_STRING => {
+                let len = read::<u16>(parser)? as usize;
+                let string = read_string(parser, len)?;
+                // object brackets are stored as strings without a name
+                if string.starts_with('[') {
+                    continue;
+                }
+                Value::String(string)
+            }
+            BS_INT => Value::Int(read::<i32>(parser)?),
+            BS_FLOAT => Value::Float(read::<f32>(parser)?),
+            BS_BYTE => Value::Int(read::<u8>(parser)? as i32),
+            BS_WORD => Value::Int(read::<u16>(parser)? as i32),
+            BS_BOOL => Value::Bool(read::<u32>(parser)? != 0),
+            BS_VEC3 => {
+                parser.set_seek(parser.get_seek() + 12);
+                continue;
+            }
+            BS_COLOR => {
+                parser.set_seek(parser.get_seek() + 4);
+                continue;
+            }
+            BS_RAW | BS_RAW_FLOAT => {
+                let len = read::<u16>(parser)? as usize;
+                Value::Raw(
+                    parser
+                        .read_binary_as_vec::<u8>(len)
+                        .map_err(|_| "Unexpected end of archive".to_owned())?,
+                )
+            }
+            BS_ENUM => Value::Enum(read::<u32>(parser)?),
+            kind => return Err(format!("Unknown value type {} in archive", kind)),
+        };
+        entries.push(Entry {
+            name: std::mem::take(&mut name),
+            value,
+        });
+    }
+    Ok(entries)
+}
+
+fn read_ascii(parser: &ZenParser) -> Result<Vec<Entry>, String> {
+    let mut entries = vec![];
+    while parser.get_seek() < parser.get_file_size() {
+        let line = read_line(parser)?;
+        let line = line.trim();
+        // object brackets and empty lines
+        let (name, rest) = match line.find('=') {
+            Some(position) => (&line[..position], &line[position + 1..]),
+            None => continue,
+        };
+        let (kind, raw) = match rest.find(':') {
+            Some(position) => (&rest[..position], &rest[position + 1..]),
+            None => continue,
+        };
+        let value = match kind {
+            "string" => Value::String(raw.to_owned()),
+            "int" | "byte" | "word" => Value::Int(raw.parse().unwrap_or(0)),
+            "float" => Value::Float(raw.parse().unwrap_or(0.0)),
+            "bool" => Value::Bool(raw != "0"),
+            "enum" => Value::Enum(raw.parse().unwrap_or(0)),
+            _ => continue,
+        };
+        entries.push(Entry {
+            name: name.to_owned(),
+            value,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "ZenGin Archive\nver 1\nzCArchiverGeneric\n";
+
+    /// Archives are only read from files
+    fn parse(name: &str, bytes: &[u8]) -> Result<Vec<Entry>, String> {
+        let path = std::env::temp_dir().join(format!("daedalus-archive-{}", name));
+        std::fs::write(&path, bytes).unwrap();
+        let parser = ZenParser::new(path.to_string_lossy().into_owned());
+        let entries = read_entries(&parser);
+        std::fs::remove_file(&path).unwrap();
+        entries
+    }
+
+    fn bin_safe(keys: &[&str], data: &[u8]) -> Vec<u8> {
+        let mut bytes = format!("{}BIN_SAFE\nsaveGame 1\nEND\n", HEADER).into_bytes();
+        let hash_table_offset = bytes.len() + 12 + data.len();
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&(hash_table_offset as u32).to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+        for (index, key) in keys.iter().enumerate() {
+            bytes.extend_from_slice(&(key.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(&(index as u16).to_le_bytes());
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+            bytes.extend_from_slice(key.as_bytes());
+        }
+        bytes
+    }
+
+    fn bin_safe_string(data: &mut Vec<u8>, string: &str) {
+        data.push(BS_STRING);
+        data.extend_from_slice(&(string.len() as u16).to_le_bytes());
+        data.extend_from_slice(string.as_bytes());
+    }
+
+    fn bin_safe_key(data: &mut Vec<u8>, key: u32) {
+        data.push(BS_HASH);
+        data.extend_from_slice(&key.to_le_bytes());
+    }
+
+    #[test]
+    fn reads_bin_safe() {
+        let mut data = vec![];
+        bin_safe_string(&mut data, "[% oCSavegameInfo 0 0]");
+        bin_safe_key(&mut data, 0);
+        data.push(BS_INT);
+        data.extend_from_slice(&3i32.to_le_bytes());
+        bin_safe_key(&mut data, 1);
+        bin_safe_string(&mut data, "Hallo");
+        bin_safe_key(&mut data, 2);
+        data.push(BS_VEC3);
+        data.extend_from_slice(&[0; 12]);
+        bin_safe_key(&mut data, 2);
+        data.push(BS_BOOL);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        bin_safe_string(&mut data, "[]");
+
+        let entries = parse("bin-safe", &bin_safe(&["day", "name", "Told"], &data)).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].name, "day");
+        assert_eq!(entries[0].value.as_int(), Some(3));
+        assert_eq!(entries[1].name, "name");
+        assert_eq!(entries[1].value.as_str(), Some("Hallo"));
+        assert_eq!(entries[2].name, "Told");
+        assert_eq!(entries[2].value.as_int(), Some(1));
+    }
+
+    #[test]
+    fn bin_safe_unknown_type_fails() {
+        let bytes = bin_safe(&[], &[0x42]);
+        assert!(parse("bin-safe-unknown", &bytes).is_err());
+    }
+
+    #[test]
+    fn bin_safe_invalid_key_index_fails() {
+        let mut bytes = bin_safe(&["day"], &[]);
+        // insertion index of the only key
+        let position = bytes.len() - "day".len() - 6;
+        bytes[position] = 5;
+        assert!(parse("bin-safe-key", &bytes).is_err());
+    }
+
+    #[test]
+    fn reads_ascii() {
+        let text = format!(
+            "{}ASCII\nsaveGame 0\nEND\nobjects 1\nEND\n\n\
+             [% oCSavegameInfo 0 0]\n\
+             \tday=int:12\n\
+             \ttitle=string:Alter Turm\n\
+             \tTold=bool:1\n\
+             \tpos=vec3:1 2 3\n\
+             []\n",
+            HEADER
+        );
+        let entries = parse("ascii", text.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].name, "day");
+        assert_eq!(entries[0].value.as_int(), Some(12));
+        assert_eq!(entries[1].value.as_str(), Some("Alter Turm"));
+        assert_eq!(entries[2].value.as_int(), Some(1));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(parse("other", b"MZ\n").is_err());
+        let binary = format!("{}BINARY\nEND\n", HEADER);
+        assert!(parse("binary", binary.as_bytes()).is_err());
+    }
+}
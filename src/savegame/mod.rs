@@ -0,0 +1,150 @@
+use crate::game_state::{TopicSection, TopicStatus};
+use crate::vm::file::{Flag, Kind};
+use crate::vm::VirtualMachine;
+use archive::Entry;
+use zen_parser::ZenParser;
+
+pub mod archive;
+
+/// Value of a global script variable as stored in a savegame
+#[derive(Clone, Debug, Default)]
+pub struct SaveGameSymbol {
+    pub name: String,
+    pub values: Vec<i32>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SaveGameInfo {
+    pub name: String,
+    pub told: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SaveGameTopic {
+    pub description: String,
+    pub section: i32,
+    pub status: i32,
+    pub entries: Vec<String>,
+}
+
+/// Script state of an original Gothic or Gothic II savegame (SAVEDAT.SAV)
+#[derive(Clone, Debug, Default)]
+pub struct SaveGame {
+    pub day: i32,
+    pub hour: i32,
+    pub minute: i32,
+    pub symbols: Vec<SaveGameSymbol>,
+    pub infos: Vec<SaveGameInfo>,
+    pub topics: Vec<SaveGameTopic>,
+}
+
+impl SaveGame {
+    pub fn open(file: String) -> Result<SaveGame, String> {
+        let parser = ZenParser::new(file);
+        let entries = archive::read_entries(&parser)?;
+        Ok(Self::from_entries(&entries))
+    }
+    pub fn from_entries(entries: &[Entry]) -> SaveGame {
+        let mut save_game = SaveGame::default();
+        let mut told = None;
+        for entry in entries {
+            let name = entry.name.as_str();
+            match name {
+                "day" => save_game.day = entry.value.as_int().unwrap_or(0),
+                "hour" => save_game.hour = entry.value.as_int().unwrap_or(0),
+                "min" => save_game.minute = entry.value.as_int().unwrap_or(0),
+                "Told" => told = entry.value.as_int(),
+                "InstName" => save_game.infos.push(SaveGameInfo {
+                    name: entry.value.as_str().unwrap_or("").to_uppercase(),
+                    told: told.take().unwrap_or(0) != 0,
+                }),
+                "TopicDescription" => save_game.topics.push(SaveGameTopic {
+                    description: entry.value.as_str().unwrap_or("").to_owned(),
+                    ..Default::default()
+                }),
+                "TopicSection" | "TopicStatus" | "EntryCount" => {
+                    if let Some(topic) = save_game.topics.last_mut() {
+                        let value = entry.value.as_int().unwrap_or(0);
+                        match name {
+                            "TopicSection" => topic.section = value,
+                            "TopicStatus" => topic.status = value,
+                            _ => (),
+                        }
+                    }
+                }
+                _ if name.starts_with("Entry") => {
+                    if let (Some(topic), Some(text)) =
+                        (save_game.topics.last_mut(), entry.value.as_str())
+                    {
+                        topic.entries.push(text.to_owned());
+                    }
+                }
+                // array size of the following values
+                _ if name.starts_with("symName") && name.ends_with("cnt") => (),
+                _ if name.starts_with("symName") => save_game.symbols.push(SaveGameSymbol {
+                    name: entry.value.as_str().unwrap_or("").to_uppercase(),
+                    values: vec![],
+                }),
+                _ if name.starts_with("symValue") => {
+                    if let (Some(symbol), Some(value)) =
+                        (save_game.symbols.last_mut(), entry.value.as_int())
+                    {
+                        symbol.values.push(value);
+                    }
+                }
+                _ => (),
+            }
+        }
+        save_game
+    }
+    /// Writes the global variables, the told state of infos by name, the world time and replaces the quest log.
+    /// Told infos are known by the npc set with `GameState::set_player`.
+    /// Returns the names which do not exist in the loaded file or whose kind differs.
+    pub fn apply(&self, virtual_machine: &mut VirtualMachine) -> Vec<String> {
+        let file = virtual_machine.get_file().clone();
+        let sym_table = file.get_sym_table();
+        let mut skipped = vec![];
+        for symbol in self.symbols.iter() {
+            let index = match sym_table.get_symbol_index_by_name(&symbol.name) {
+                Some(index) => index,
+                None => {
+                    skipped.push(symbol.name.clone());
+                    continue;
+                }
+            };
+            let properties = &sym_table.get_symbol_by_index(index).unwrap().properties;
+            if properties.has_flag(Flag::Const)
+                || properties.get_kind() != Kind::Int
+                || symbol.values.len() > properties.get_count() as usize
+            {
+                skipped.push(symbol.name.clone());
+                continue;
+            }
+            for (array_index, value) in symbol.values.iter().enumerate() {
+                virtual_machine.set_int_value(index, array_index, *value);
+            }
+        }
+        for info in self.infos.iter() {
+            match sym_table.get_symbol_index_by_name(&info.name) {
+                Some(index) => virtual_machine
+                    .get_mut_game_state()
+                    .set_info_told(index, info.told),
+                None => skipped.push(info.name.clone()),
+            }
+        }
+        let quest_log = virtual_machine.get_mut_game_state().get_mut_quest_log();
+        quest_log.clear();
+        for topic in self.topics.iter() {
+            let section = TopicSection::from_int(topic.section).unwrap_or(TopicSection::Note);
+            let log_topic = quest_log.create_topic(&topic.description, section);
+            log_topic.set_status(TopicStatus::from_int(topic.status).unwrap_or(TopicStatus::Free));
+            for entry in topic.entries.iter() {
+                log_topic.add_entry(entry);
+            }
+        }
+        let world_clock = virtual_machine.get_mut_game_state().get_mut_world_clock();
+        world_clock.set_day(self.day);
+        world_clock.set_time_of_day(self.hour, self.minute);
+        skipped
+    }
+}
@@ -0,0 +1,326 @@
+use enumflags2::{BitFlag, BitFlags};
+use std::convert::TryFrom;
+use std::num::{NonZeroI32, NonZeroU32};
+
+/// Magic bytes at the start of every snapshot
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"DVMS";
+/// Increased whenever the layout of a snapshot changes
+pub const SNAPSHOT_VERSION: u32 = 10;
+
+/// Little endian buffer a snapshot is written to
+#[derive(Default)]
+pub struct SnapshotWriter {
+    buffer: Vec<u8>,
+}
+
+impl SnapshotWriter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+    pub fn write<T: Persist>(&mut self, value: &T) {
+        value.write(self);
+    }
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+pub struct SnapshotReader<'a> {
+    buffer: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self {
+            buffer,
+            position: 0,
+        }
+    }
+    pub fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position.saturating_add(count);
+        match self.buffer.get(self.position..end) {
+            Some(bytes) => {
+                self.position += count;
+                Ok(bytes)
+            }
+            None => Err(format!(
+                "Snapshot ended unexpectedly at byte {}",
+                self.position
+            )),
+        }
+    }
+    pub fn read<T: Persist>(&mut self) -> Result<T, String> {
+        T::read(self)
+    }
+    /// Bytes left to read
+    pub fn remaining(&self) -> usize {
+        self.buffer.len().saturating_sub(self.position)
+    }
+    pub fn is_at_end(&self) -> bool {
+        self.position >= self.buffer.len()
+    }
+}
+
+/// Values which can be stored in a snapshot
+pub trait Persist: Sized {
+    fn write(&self, writer: &mut SnapshotWriter);
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String>;
+}
+
+macro_rules! persist_number {
+    ($($ty:ty),*) => {
+        $(
+            impl Persist for $ty {
+                fn write(&self, writer: &mut SnapshotWriter) {
+                    writer.write_bytes(&self.to_le_bytes());
+                }
+                fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+                    let bytes = reader.read_bytes(std::mem::size_of::<$ty>())?;
+                    Ok(<$ty>::from_le_bytes(<[u8; std::mem::size_of::<$ty>()]>::try_from(bytes).unwrap()))
+                }
+            }
+        )*
+    };
+}
+persist_number!(u8, u16, u32, u64, i32, f32);
+
+/// Implements `Persist` for a struct by storing the listed fields in order
+#[macro_export]
+macro_rules! persist_struct {
+    ($name:ident { $($field:ident),* $(,)? }) => {
+        impl $crate::snapshot::Persist for $name {
+            fn write(&self, writer: &mut $crate::snapshot::SnapshotWriter) {
+                $(writer.write(&self.$field);)*
+            }
+            fn read(reader: &mut $crate::snapshot::SnapshotReader) -> Result<Self, String> {
+                Ok(Self {
+                    $($field: reader.read()?,)*
+                })
+            }
+        }
+    };
+}
+
+impl Persist for usize {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(*self as u64));
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        Ok(reader.read::<u64>()? as usize)
+    }
+}
+
+impl Persist for bool {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(*self as u8));
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        Ok(reader.read::<u8>()? != 0)
+    }
+}
+
+impl Persist for String {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.len() as u32));
+        writer.write_bytes(self.as_bytes());
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        let len = reader.read::<u32>()? as usize;
+        let bytes = reader.read_bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|error| error.to_string())
+    }
+}
+
+impl Persist for NonZeroI32 {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.get());
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        NonZeroI32::new(reader.read()?).ok_or_else(|| "Expected non zero value".to_owned())
+    }
+}
+
+impl Persist for NonZeroU32 {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.get());
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        NonZeroU32::new(reader.read()?).ok_or_else(|| "Expected non zero value".to_owned())
+    }
+}
+
+impl<T: Persist> Persist for Option<T> {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        match self {
+            Some(value) => {
+                writer.write(&1u8);
+                writer.write(value);
+            }
+            None => writer.write(&0u8),
+        }
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(None),
+            _ => Ok(Some(reader.read()?)),
+        }
+    }
+}
+
+impl<T: Persist> Persist for Vec<T> {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.len() as u32));
+        for value in self {
+            writer.write(value);
+        }
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        let len = reader.read::<u32>()? as usize;
+        // every element takes at least one byte, a corrupt length must not reserve more
+        let mut vec = Vec::with_capacity(len.min(reader.remaining()));
+        for _ in 0..len {
+            vec.push(reader.read()?);
+        }
+        Ok(vec)
+    }
+}
+
+impl<T: Persist, const N: usize> Persist for [T; N] {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        for value in self {
+            writer.write(value);
+        }
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        let mut vec = Vec::with_capacity(N);
+        for _ in 0..N {
+            vec.push(reader.read()?);
+        }
+        <[T; N]>::try_from(vec).map_err(|_| format!("Expected an array of {} values", N))
+    }
+}
+
+impl<A: Persist, B: Persist> Persist for (A, B) {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.0);
+        writer.write(&self.1);
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        Ok((reader.read()?, reader.read()?))
+    }
+}
+
+impl<A: Persist, B: Persist, C: Persist> Persist for (A, B, C) {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.0);
+        writer.write(&self.1);
+        writer.write(&self.2);
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        Ok((reader.read()?, reader.read()?, reader.read()?))
+    }
+}
+
+impl<T> Persist for BitFlags<T>
+where
+    T: BitFlag,
+    T::Numeric: Persist,
+{
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.bits());
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        BitFlags::from_bits(reader.read()?).map_err(|_| "Unknown flag bits".to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Persist>(value: &T) -> T {
+        let mut writer = SnapshotWriter::new();
+        writer.write(value);
+        let bytes = writer.into_bytes();
+        let mut reader = SnapshotReader::new(&bytes);
+        let value = reader.read().unwrap();
+        assert!(reader.is_at_end());
+        value
+    }
+
+    #[test]
+    fn numbers_round_trip() {
+        assert_eq!(round_trip(&0xABu8), 0xAB);
+        assert_eq!(round_trip(&-12345i32), -12345);
+        assert_eq!(round_trip(&u64::MAX), u64::MAX);
+        assert_eq!(round_trip(&1.5f32), 1.5);
+        assert_eq!(round_trip(&42usize), 42);
+        assert!(round_trip(&true));
+    }
+
+    #[test]
+    fn containers_round_trip() {
+        assert_eq!(round_trip(&"Hallo".to_owned()), "Hallo");
+        assert_eq!(round_trip(&vec![1i32, 2, 3]), vec![1, 2, 3]);
+        assert_eq!(round_trip(&[4u16, 5, 6]), [4, 5, 6]);
+        assert_eq!(round_trip(&Some(7i32)), Some(7));
+        assert_eq!(round_trip(&None::<i32>), None);
+        assert_eq!(
+            round_trip(&(1u8, "a".to_owned(), 2i32)),
+            (1, "a".to_owned(), 2)
+        );
+        assert_eq!(round_trip(&NonZeroU32::new(9)), NonZeroU32::new(9));
+    }
+
+    #[test]
+    fn flags_round_trip() {
+        use crate::stdlib::instances::item::Flags;
+        let flags = Flags::Multi | Flags::Torch;
+        assert_eq!(round_trip(&flags), flags);
+    }
+
+    #[test]
+    fn unknown_flag_bits_fail() {
+        use crate::stdlib::instances::item::Flags;
+        let bytes = (1u32 << 30).to_le_bytes();
+        let mut reader = SnapshotReader::new(&bytes);
+        assert!(reader.read::<BitFlags<Flags>>().is_err());
+    }
+
+    #[test]
+    fn truncated_input_fails() {
+        let mut reader = SnapshotReader::new(&[1, 2]);
+        assert!(reader.read::<u32>().is_err());
+    }
+
+    #[test]
+    fn corrupt_vec_length_fails() {
+        let mut writer = SnapshotWriter::new();
+        writer.write(&u32::MAX);
+        writer.write(&1u8);
+        let bytes = writer.into_bytes();
+        let mut reader = SnapshotReader::new(&bytes);
+        assert!(reader.read::<Vec<u8>>().is_err());
+    }
+
+    #[test]
+    fn zero_for_non_zero_fails() {
+        let bytes = 0i32.to_le_bytes();
+        let mut reader = SnapshotReader::new(&bytes);
+        assert!(reader.read::<NonZeroI32>().is_err());
+    }
+
+    #[test]
+    fn invalid_string_fails() {
+        let mut writer = SnapshotWriter::new();
+        writer.write(&2u32);
+        writer.write_bytes(&[0xFF, 0xFE]);
+        let bytes = writer.into_bytes();
+        let mut reader = SnapshotReader::new(&bytes);
+        assert!(reader.read::<String>().is_err());
+    }
+}
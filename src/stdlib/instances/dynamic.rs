@@ -0,0 +1,179 @@
+use super::Instance;
+use crate::persist_struct;
+use crate::stdlib::members::MemberValue;
+use crate::vm::file::sym_table::SymTable;
+use crate::vm::file::symbol::Data;
+use crate::vm::file::Kind;
+
+/// Instance of a class without a native struct, e.g. `C_SVM` or classes of mods.
+/// Members are stored by their position in the class.
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DynamicInstance {
+    instance_symbol: usize,
+    class_symbol: usize,
+    members: Vec<Data>,
+}
+
+impl DynamicInstance {
+    /// Creates zeroed members following the layout of the class symbol
+    pub fn new(sym_table: &SymTable, class_symbol: usize) -> Result<Self, String> {
+        let class = sym_table.get_symbol_by_index(class_symbol)?;
+        if class.properties.get_kind() != Kind::Class {
+            return Err(format!("Symbol {} is not a class", class_symbol));
+        }
+        let count = class.properties.get_count() as usize;
+        let mut members = Vec::with_capacity(count);
+        for index in class_symbol + 1..=class_symbol + count {
+            let properties = &sym_table.get_symbol_by_index(index)?.properties;
+            let array_size = properties.get_count().max(1) as usize;
+            let data = match properties.get_kind() {
+                Kind::Float => Data::FloatSequence(vec![0.0; array_size]),
+                Kind::CharString => Data::StringSequence(vec![String::new(); array_size]),
+                _ => Data::IntSequence(vec![0; array_size]),
+            };
+            members.push(data);
+        }
+        Ok(Self {
+            instance_symbol: 0,
+            class_symbol,
+            members,
+        })
+    }
+    pub fn get_class_symbol(&self) -> usize {
+        self.class_symbol
+    }
+    pub fn set_class_symbol(&mut self, class_symbol: usize) {
+        self.class_symbol = class_symbol;
+    }
+    pub fn get_members(&self) -> &[Data] {
+        &self.members
+    }
+    pub fn get_member(&self, slot: usize, array_index: usize) -> Option<MemberValue> {
+        match self.members.get(slot)? {
+            Data::IntSequence(vec) => vec.get(array_index).map(|value| MemberValue::Int(*value)),
+            Data::FloatSequence(vec) => {
+                vec.get(array_index).map(|value| MemberValue::Float(*value))
+            }
+            Data::StringSequence(vec) => vec
+                .get(array_index)
+                .map(|value| MemberValue::String(value.clone())),
+        }
+    }
+    /// Returns false if the slot or index does not exist or the value is of the wrong kind
+    pub fn set_member(&mut self, slot: usize, array_index: usize, value: MemberValue) -> bool {
+        let member = match self.members.get_mut(slot) {
+            Some(member) => member,
+            None => return false,
+        };
+        match (member, value) {
+            (Data::IntSequence(vec), MemberValue::Int(value)) => match vec.get_mut(array_index) {
+                Some(element) => *element = value,
+                None => return false,
+            },
+            (Data::FloatSequence(vec), value) => match vec.get_mut(array_index) {
+                Some(element) => *element = value.as_float(),
+                None => return false,
+            },
+            (Data::StringSequence(vec), MemberValue::String(value)) => {
+                match vec.get_mut(array_index) {
+                    Some(element) => *element = value,
+                    None => return false,
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+    /// Looks up a member by its name without the class prefix, e.g. `VOICE` of `C_SVM`
+    pub fn get_member_by_name(&self, sym_table: &SymTable, name: &str) -> Option<&Data> {
+        let class_name = sym_table
+            .get_symbol_by_index(self.class_symbol)
+            .ok()?
+            .get_name()?;
+        let index = sym_table.get_symbol_index_by_name(&format!("{}.{}", class_name, name))?;
+        self.members.get(index.checked_sub(self.class_symbol + 1)?)
+    }
+}
+
+impl Instance for DynamicInstance {
+    fn get_instance_symbol(&self) -> usize {
+        self.instance_symbol
+    }
+    fn set_instance_symbol(&mut self, instance_symbol: usize) {
+        self.instance_symbol = instance_symbol;
+    }
+}
+
+persist_struct!(DynamicInstance {
+    instance_symbol,
+    class_symbol,
+    members,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::file::file::File;
+    use crate::vm::file::test_file::TestFile;
+    use std::sync::Arc;
+
+    fn svm_file() -> (Arc<File>, usize) {
+        let mut test_file = TestFile::new();
+        test_file.var("BEFORE", Kind::Int, 1);
+        let class = test_file.class(
+            "C_SVM",
+            &[
+                ("VOICE", Kind::Int, 1),
+                ("NAME", Kind::CharString, 2),
+                ("SCALE", Kind::Float, 1),
+            ],
+        );
+        (test_file.build(), class)
+    }
+
+    #[test]
+    fn members_follow_the_class_layout() {
+        let (file, class) = svm_file();
+        let svm = DynamicInstance::new(file.get_sym_table(), class).unwrap();
+        assert_eq!(svm.get_class_symbol(), class);
+        assert_eq!(svm.get_members().len(), 3);
+        assert_eq!(svm.get_member(0, 0), Some(MemberValue::Int(0)));
+        assert_eq!(
+            svm.get_member(1, 1),
+            Some(MemberValue::String(String::new()))
+        );
+        assert_eq!(svm.get_member(1, 2), None);
+        assert_eq!(svm.get_member(2, 0), Some(MemberValue::Float(0.0)));
+        assert_eq!(svm.get_member(3, 0), None);
+        assert!(DynamicInstance::new(file.get_sym_table(), class + 1).is_err());
+    }
+
+    #[test]
+    fn members_are_written_by_kind() {
+        let (file, class) = svm_file();
+        let mut svm = DynamicInstance::new(file.get_sym_table(), class).unwrap();
+        assert!(svm.set_member(0, 0, MemberValue::Int(7)));
+        assert!(svm.set_member(1, 1, MemberValue::String("Diego".to_owned())));
+        assert!(svm.set_member(2, 0, MemberValue::Float(1.5)));
+        assert!(!svm.set_member(0, 0, MemberValue::String("7".to_owned())));
+        assert!(!svm.set_member(1, 2, MemberValue::String("Milten".to_owned())));
+        assert!(!svm.set_member(3, 0, MemberValue::Int(1)));
+        assert_eq!(svm.get_member(0, 0), Some(MemberValue::Int(7)));
+        assert_eq!(svm.get_member(2, 0), Some(MemberValue::Float(1.5)));
+    }
+
+    #[test]
+    fn members_are_found_by_name() {
+        let (file, class) = svm_file();
+        let sym_table = file.get_sym_table();
+        let mut svm = DynamicInstance::new(sym_table, class).unwrap();
+        svm.set_member(1, 1, MemberValue::String("Diego".to_owned()));
+        match svm.get_member_by_name(sym_table, "NAME") {
+            Some(Data::StringSequence(names)) => assert_eq!(names[1], "Diego"),
+            _ => panic!("NAME is not a string member"),
+        }
+        assert!(svm.get_member_by_name(sym_table, "MISSING").is_none());
+        assert!(svm.get_member_by_name(sym_table, "BEFORE").is_none());
+    }
+}
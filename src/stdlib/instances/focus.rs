@@ -1,24 +1,47 @@
 use super::Instance;
-#[derive(Default)]
+use crate::persist_struct;
+use crate::stdlib::members::DaedalusClass;
+#[derive(Default, DaedalusClass)]
+#[daedalus(class = "C_FOCUS")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Focus {
     instance_symbol: usize,
     // NSCs
+    #[daedalus(name = "NPC_LONGRANGE")]
     npc_long_range: f32,
+    #[daedalus(name = "NPC_RANGE1", element = 0)]
+    #[daedalus(name = "NPC_RANGE2", element = 1)]
     npc_range: (f32, f32),
-    npc_azi: f32,         // Azimuth
+    #[daedalus(name = "NPC_AZI")]
+    npc_azi: f32, // Azimuth
+    #[daedalus(name = "NPC_ELEVDO", element = 0)]
+    #[daedalus(name = "NPC_ELEVUP", element = 1)]
     npc_elev: (f32, f32), // Elevation down, up
+    #[daedalus(name = "NPC_PRIO")]
     npc_prio: i32,
 
     // Item
+    #[daedalus(name = "ITEM_RANGE1", element = 0)]
+    #[daedalus(name = "ITEM_RANGE2", element = 1)]
     item_range: (f32, f32),
+    #[daedalus(name = "ITEM_AZI")]
     item_azi: f32,
+    #[daedalus(name = "ITEM_ELEVDO", element = 0)]
+    #[daedalus(name = "ITEM_ELEVUP", element = 1)]
     item_elev: (f32, f32),
+    #[daedalus(name = "ITEM_PRIO")]
     item_prio: i32,
 
     // Mobs
+    #[daedalus(name = "MOB_RANGE1", element = 0)]
+    #[daedalus(name = "MOB_RANGE2", element = 1)]
     mob_range: (f32, f32),
+    #[daedalus(name = "MOB_AZI")]
     mob_azi: f32,
+    #[daedalus(name = "MOB_ELEVDO", element = 0)]
+    #[daedalus(name = "MOB_ELEVUP", element = 1)]
     mob_elev: (f32, f32),
+    #[daedalus(name = "MOB_PRIO")]
     mob_prio: i32,
 }
 impl Focus {
@@ -27,6 +50,22 @@ impl Focus {
     }
 }
 
+accessors!(Focus {
+    value npc_long_range: f32 => get_npc_long_range, set_npc_long_range;
+    value npc_range: (f32, f32) => get_npc_range, set_npc_range;
+    value npc_azi: f32 => get_npc_azi, set_npc_azi;
+    value npc_elev: (f32, f32) => get_npc_elev, set_npc_elev;
+    value npc_prio: i32 => get_npc_prio, set_npc_prio;
+    value item_range: (f32, f32) => get_item_range, set_item_range;
+    value item_azi: f32 => get_item_azi, set_item_azi;
+    value item_elev: (f32, f32) => get_item_elev, set_item_elev;
+    value item_prio: i32 => get_item_prio, set_item_prio;
+    value mob_range: (f32, f32) => get_mob_range, set_mob_range;
+    value mob_azi: f32 => get_mob_azi, set_mob_azi;
+    value mob_elev: (f32, f32) => get_mob_elev, set_mob_elev;
+    value mob_prio: i32 => get_mob_prio, set_mob_prio;
+});
+
 impl Instance for Focus {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -35,3 +74,20 @@ impl Instance for Focus {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(Focus {
+    instance_symbol,
+    npc_long_range,
+    npc_range,
+    npc_azi,
+    npc_elev,
+    npc_prio,
+    item_range,
+    item_azi,
+    item_elev,
+    item_prio,
+    mob_range,
+    mob_azi,
+    mob_elev,
+    mob_prio,
+});
@@ -1,6 +1,9 @@
 use super::Instance;
+use crate::persist_struct;
+use crate::stdlib::members::DaedalusClass;
 use std::num::{NonZeroI32, NonZeroU32};
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SubChoice {
     text: String,
     func_sym: u32,
@@ -12,16 +15,31 @@ impl SubChoice {
     }
 }
 
-#[derive(Default)]
+accessors!(SubChoice {
+    string text: String => get_text, set_text;
+    value func_sym: u32 => get_func_sym, set_func_sym;
+});
+
+#[derive(Default, DaedalusClass)]
+#[daedalus(class = "C_INFO")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Info {
     instance_symbol: usize,
+    #[daedalus(name = "NPC")]
     npc: Option<NonZeroI32>,
+    #[daedalus(name = "NR")]
     nr: Option<NonZeroI32>,
+    #[daedalus(name = "IMPORTANT")]
     important: Option<NonZeroI32>,
+    #[daedalus(name = "CONDITION")]
     condition: Option<NonZeroU32>,
+    #[daedalus(name = "INFORMATION")]
     information: Option<NonZeroU32>,
+    #[daedalus(name = "DESCRIPTION")]
     description: String,
+    #[daedalus(name = "TRADE")]
     trade: Option<NonZeroI32>,
+    #[daedalus(name = "PERMANENT")]
     permanent: Option<NonZeroI32>,
     sub_choices: Vec<SubChoice>,
 }
@@ -36,8 +54,26 @@ impl Info {
     pub fn remove_choice(&mut self, index: usize) {
         self.sub_choices.remove(index);
     }
+    pub fn clear_choices(&mut self) {
+        self.sub_choices.clear();
+    }
+    /// Choices in the order they were added
+    pub fn get_sub_choices(&self) -> &[SubChoice] {
+        &self.sub_choices
+    }
 }
 
+accessors!(Info {
+    value npc: Option<NonZeroI32> => get_npc, set_npc;
+    value nr: Option<NonZeroI32> => get_nr, set_nr;
+    value important: Option<NonZeroI32> => get_important, set_important;
+    value condition: Option<NonZeroU32> => get_condition, set_condition;
+    value information: Option<NonZeroU32> => get_information, set_information;
+    string description: String => get_description, set_description;
+    value trade: Option<NonZeroI32> => get_trade, set_trade;
+    value permanent: Option<NonZeroI32> => get_permanent, set_permanent;
+});
+
 impl Instance for Info {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -46,3 +82,18 @@ impl Instance for Info {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(SubChoice { text, func_sym });
+
+persist_struct!(Info {
+    instance_symbol,
+    npc,
+    nr,
+    important,
+    condition,
+    information,
+    description,
+    trade,
+    permanent,
+    sub_choices,
+});
@@ -1,8 +1,12 @@
 use super::*;
-use enumflags2::BitFlags;
+use crate::persist_struct;
+use crate::stdlib::members::DaedalusClass;
+use enumflags2::{bitflags, BitFlags};
 
-#[derive(BitFlags, Copy, Clone)]
+#[bitflags]
 #[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Categories {
     Nil = 1 << 0,
     /// Equipable
@@ -23,77 +27,125 @@ pub enum Categories {
     //Equipable = Nf | Ff | Armor | Rune | Magic,
 }
 
-#[derive(BitFlags, Copy, Clone)]
+#[bitflags]
 #[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Flags {
+    Dropped = 1 << 10,
     Dagger = 1 << 13,
     Sword = 1 << 14,
     Axe = 1 << 15,
     TwoHandSword = 1 << 16,
     TwoHandAxe = 1 << 17,
+    Shield = 1 << 18,
     Bow = 1 << 19,
     CrossBow = 1 << 20,
+    /// Stacks in inventories
+    Multi = 1 << 21,
     Amulet = 1 << 22,
     Ring = 1 << 11,
     Belt = 1 << 24,
+    Throw = 1 << 25,
+    Torch = 1 << 28,
     Mission = 1 << 12,
 }
 
-impl Default for Flags {
-    fn default() -> Self {
-        BitFlags::emtpy()
-    }
-}
-
-#[derive(Default)]
+#[derive(Clone, Default, DaedalusClass)]
+#[daedalus(class = "C_ITEM")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Item {
     instance_symbol: usize,
+    #[daedalus(name = "ID")]
     id: i32,
+    #[daedalus(name = "NAME")]
     name: String,
+    #[daedalus(name = "NAMEID")]
     name_id: String,
+    #[daedalus(name = "HP")]
     hp: i32,
+    #[daedalus(name = "HP_MAX")]
     hp_max: i32,
-    main_flag: Flags,
-    flags: Flags,
+    /// Raw script bits, see `get_main_flag` for the categories
+    #[daedalus(name = "MAINFLAG")]
+    main_flag: i32,
+    /// Raw script bits, mods use bits without a variant in `Flags`
+    #[daedalus(name = "FLAGS")]
+    flags: i32,
+    #[daedalus(name = "WEIGHT")]
     weight: i32,
+    #[daedalus(name = "VALUE")]
     value: i32,
+    #[daedalus(name = "DAMAGETYPE")]
     damage_type: i32,
+    #[daedalus(name = "DAMAGETOTAL")]
     damage_total: i32,
+    #[daedalus(name = "DAMAGE")]
     damage: [i32; DAM_INDEX_MAX],
+    #[daedalus(name = "WEAR")]
     wear: i32,
+    #[daedalus(name = "PROTECTION")]
     protection: [i32; PROT_INDEX_MAX],
+    #[daedalus(name = "NUTRITION")]
     nutrition: i32,
+    #[daedalus(name = "COND_ATR")]
     cond_atr: [i32; COND_ATR_MAX],
+    #[daedalus(name = "COND_VALUE")]
     cond_value: [i32; COND_ATR_MAX],
 
+    #[daedalus(name = "MAGIC")]
     magic: u32,
+    #[daedalus(name = "ON_EQUIP")]
     on_equip: u32,
+    #[daedalus(name = "ON_UNEQUIP")]
     on_unequip: u32,
+    #[daedalus(name = "ON_STATE")]
     on_state: [u32; 4],
 
+    #[daedalus(name = "OWNER")]
     owner: u32,
+    #[daedalus(name = "OWNERGUILD")]
     owner_guild: i32,
     //	Zur Schau getragene Gilde durch Verkleidung
+    #[daedalus(name = "DISGUISEGUILD")]
     disguise_guild: i32,
 
+    #[daedalus(name = "VISUAL")]
     visual: String,
+    #[daedalus(name = "VISUAL_CHANGE")]
     visual_change: String,
+    #[daedalus(name = "EFFECT")]
     effect: String,
+    #[daedalus(name = "VISUAL_SKIN")]
     visual_skin: i32,
 
+    #[daedalus(name = "SCEMENAME")]
     sceme_name: String,
+    #[daedalus(name = "MATERIAL")]
     material: i32,
+    #[daedalus(name = "MUNITION")]
     munition: i32,
+    #[daedalus(name = "SPELL")]
     spell: i32,
+    #[daedalus(name = "RANGE")]
     range: i32,
+    #[daedalus(name = "MAG_CIRCLE")]
     mag_circle: i32,
 
+    #[daedalus(name = "DESCRIPTION")]
     description: String,
+    #[daedalus(name = "TEXT")]
     text: [String; ITM_TEXT_MAX],
+    #[daedalus(name = "COUNT")]
     count: [i32; ITM_TEXT_MAX],
 
+    #[daedalus(name = "INV_ZBIAS")]
     inv_zbias: i32,
+    #[daedalus(name = "INV_ROTX", element = 0)]
+    #[daedalus(name = "INV_ROTY", element = 1)]
+    #[daedalus(name = "INV_ROTZ", element = 2)]
     inv_rot: (i32, i32, i32),
+    #[daedalus(name = "INV_ANIMATE")]
     inv_animate: i32,
 
     pub amount: u32,
@@ -103,8 +155,60 @@ impl Item {
     pub fn new() -> Self {
         Default::default()
     }
+    /// Items flagged `ITEM_MULTI` and consumables share one object per inventory
+    pub fn is_stackable(&self) -> bool {
+        self.get_flags().contains(Flags::Multi)
+            || self
+                .get_main_flag()
+                .intersects(Categories::Mun | Categories::Food | Categories::Potion)
+    }
 }
 
+accessors!(Item {
+    value id: i32 => get_id, set_id;
+    string name: String => get_name, set_name;
+    string name_id: String => get_name_id, set_name_id;
+    value hp: i32 => get_hp, set_hp;
+    value hp_max: i32 => get_hp_max, set_hp_max;
+    value main_flag: i32 => get_raw_main_flag, set_raw_main_flag;
+    flags main_flag: Categories => get_main_flag, set_main_flag;
+    value flags: i32 => get_raw_flags, set_raw_flags;
+    flags flags: Flags => get_flags, set_flags;
+    value weight: i32 => get_weight, set_weight;
+    value value: i32 => get_value, set_value;
+    value damage_type: i32 => get_damage_type, set_damage_type;
+    value damage_total: i32 => get_damage_total, set_damage_total;
+    array damage: i32 => get_damage, set_damage;
+    value wear: i32 => get_wear, set_wear;
+    array protection: i32 => get_protection, set_protection;
+    value nutrition: i32 => get_nutrition, set_nutrition;
+    array cond_atr: i32 => get_cond_atr, set_cond_atr;
+    array cond_value: i32 => get_cond_value, set_cond_value;
+    value magic: u32 => get_magic, set_magic;
+    value on_equip: u32 => get_on_equip, set_on_equip;
+    value on_unequip: u32 => get_on_unequip, set_on_unequip;
+    array on_state: u32 => get_on_state, set_on_state;
+    value owner: u32 => get_owner, set_owner;
+    value owner_guild: i32 => get_owner_guild, set_owner_guild;
+    value disguise_guild: i32 => get_disguise_guild, set_disguise_guild;
+    string visual: String => get_visual, set_visual;
+    string visual_change: String => get_visual_change, set_visual_change;
+    string effect: String => get_effect, set_effect;
+    value visual_skin: i32 => get_visual_skin, set_visual_skin;
+    string sceme_name: String => get_sceme_name, set_sceme_name;
+    value material: i32 => get_material, set_material;
+    value munition: i32 => get_munition, set_munition;
+    value spell: i32 => get_spell, set_spell;
+    value range: i32 => get_range, set_range;
+    value mag_circle: i32 => get_mag_circle, set_mag_circle;
+    string description: String => get_description, set_description;
+    string_array text: String => get_text, set_text;
+    array count: i32 => get_count, set_count;
+    value inv_zbias: i32 => get_inv_zbias, set_inv_zbias;
+    value inv_rot: (i32, i32, i32) => get_inv_rot, set_inv_rot;
+    value inv_animate: i32 => get_inv_animate, set_inv_animate;
+});
+
 impl Instance for Item {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -113,3 +217,48 @@ impl Instance for Item {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(Item {
+    instance_symbol,
+    id,
+    name,
+    name_id,
+    hp,
+    hp_max,
+    main_flag,
+    flags,
+    weight,
+    value,
+    damage_type,
+    damage_total,
+    damage,
+    wear,
+    protection,
+    nutrition,
+    cond_atr,
+    cond_value,
+    magic,
+    on_equip,
+    on_unequip,
+    on_state,
+    owner,
+    owner_guild,
+    disguise_guild,
+    visual,
+    visual_change,
+    effect,
+    visual_skin,
+    sceme_name,
+    material,
+    munition,
+    spell,
+    range,
+    mag_circle,
+    description,
+    text,
+    count,
+    inv_zbias,
+    inv_rot,
+    inv_animate,
+    amount,
+});
@@ -1,13 +1,24 @@
 use super::Instance;
-#[derive(Default)]
+use crate::persist_struct;
+use crate::stdlib::members::DaedalusClass;
+#[derive(Default, DaedalusClass)]
+#[daedalus(class = "C_ITEMREACT")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ItemReact {
     instance_symbol: usize,
+    #[daedalus(name = "NPC")]
     npc: i32,
+    #[daedalus(name = "TRADE_ITEM")]
     trade_item: i32,
+    #[daedalus(name = "TRADE_AMOUNT")]
     trade_amount: i32,
+    #[daedalus(name = "REQUESTED_CAT")]
     requested_cat: i32,
+    #[daedalus(name = "REQUESTED_ITEM")]
     requested_item: i32,
+    #[daedalus(name = "REQUESTED_AMOUNT")]
     requested_amount: i32,
+    #[daedalus(name = "REACTION")]
     reaction: u32,
 }
 
@@ -17,6 +28,16 @@ impl ItemReact {
     }
 }
 
+accessors!(ItemReact {
+    value npc: i32 => get_npc, set_npc;
+    value trade_item: i32 => get_trade_item, set_trade_item;
+    value trade_amount: i32 => get_trade_amount, set_trade_amount;
+    value requested_cat: i32 => get_requested_cat, set_requested_cat;
+    value requested_item: i32 => get_requested_item, set_requested_item;
+    value requested_amount: i32 => get_requested_amount, set_requested_amount;
+    value reaction: u32 => get_reaction, set_reaction;
+});
+
 impl Instance for ItemReact {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -25,3 +46,14 @@ impl Instance for ItemReact {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(ItemReact {
+    instance_symbol,
+    npc,
+    trade_item,
+    trade_amount,
+    requested_cat,
+    requested_item,
+    requested_amount,
+    reaction,
+});
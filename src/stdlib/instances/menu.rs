@@ -1,14 +1,17 @@
 use super::Instance;
-use enumflags2::BitFlags;
+use crate::persist_struct;
+use crate::stdlib::members::DaedalusClass;
+use enumflags2::{bitflags, BitFlags};
 
-const MAX_USERSTRINGS: i8 = 10;
-const MAX_ITEMS: usize = 150;
-const MAX_EVENTS: i8 = 10;
-const MAX_SEL_ACTIONS: i8 = 5;
-const MAX_USERVARS: i8 = 4;
+pub const MAX_USERSTRINGS: usize = 10;
+pub const MAX_ITEMS: usize = 150;
+pub const MAX_EVENTS: usize = 10;
+pub const MAX_SEL_ACTIONS: usize = 5;
+pub const MAX_USERVARS: usize = 4;
 const SEL_EVENT_UNDEF: i8 = 0;
 
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Event {
     Execute,
     Changed,
@@ -21,6 +24,7 @@ pub enum Event {
 }
 
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Action {
     Undef,
     Back,
@@ -31,9 +35,11 @@ pub enum Action {
     PlaySound,
     ExecCommands,
 }
-#[derive(BitFlags, Copy, Clone)]
+#[bitflags]
 #[repr(u8)]
-enum MenuFlags {
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MenuFlags {
     Overtop = 1,
     Exclusive = 2,
     Noani = 4,
@@ -43,20 +49,36 @@ enum MenuFlags {
     ShowInfo = 64,
 }
 
-#[derive(Default)]
+#[derive(Default, DaedalusClass)]
+#[daedalus(class = "C_MENU")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Menu {
     instance_symbol: usize,
+    #[daedalus(name = "BACKPIC")]
     back_pic: String,
+    #[daedalus(name = "BACKWORLD")]
     back_world: String,
+    #[daedalus(name = "POSX", element = 0)]
+    #[daedalus(name = "POSY", element = 1)]
     pos: (i32, i32),
+    #[daedalus(name = "DIMX", element = 0)]
+    #[daedalus(name = "DIMY", element = 1)]
     dim: (i32, i32),
+    #[daedalus(name = "ALPHA")]
     alpha: i32,
+    #[daedalus(name = "MUSICTHEME")]
     music_theme: String,
+    #[daedalus(name = "EVENTTIMERMSEC")]
     event_timer_millisec: i32,
     //items: [String; MAX_ITEMS],
+    #[daedalus(name = "ITEMS")]
     items: Vec<String>,
+    /// Raw script bits, mods use bits without a variant in `MenuFlags`
+    #[daedalus(name = "FLAGS")]
     flags: i32,
+    #[daedalus(name = "DEFAULTOUTGAME")]
     default_out_game: i32,
+    #[daedalus(name = "DEFAULTINGAME")]
     default_in_game: i32,
 }
 
@@ -66,6 +88,21 @@ impl Menu {
     }
 }
 
+accessors!(Menu {
+    string back_pic: String => get_back_pic, set_back_pic;
+    string back_world: String => get_back_world, set_back_world;
+    value pos: (i32, i32) => get_pos, set_pos;
+    value dim: (i32, i32) => get_dim, set_dim;
+    value alpha: i32 => get_alpha, set_alpha;
+    string music_theme: String => get_music_theme, set_music_theme;
+    value event_timer_millisec: i32 => get_event_timer_millisec, set_event_timer_millisec;
+    string_vec items[MAX_ITEMS]: String => get_items, set_items;
+    value flags: i32 => get_raw_flags, set_raw_flags;
+    flags flags: MenuFlags => get_flags, set_flags;
+    value default_out_game: i32 => get_default_out_game, set_default_out_game;
+    value default_in_game: i32 => get_default_in_game, set_default_in_game;
+});
+
 impl Instance for Menu {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -74,3 +111,18 @@ impl Instance for Menu {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(Menu {
+    instance_symbol,
+    back_pic,
+    back_world,
+    pos,
+    dim,
+    alpha,
+    music_theme,
+    event_timer_millisec,
+    items,
+    flags,
+    default_out_game,
+    default_in_game,
+});
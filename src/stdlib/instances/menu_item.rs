@@ -1,7 +1,12 @@
+use super::menu::{MAX_EVENTS, MAX_SEL_ACTIONS, MAX_USERSTRINGS, MAX_USERVARS};
 use super::Instance;
-use enumflags2::BitFlags;
-#[derive(BitFlags, Copy, Clone)]
+use crate::persist_struct;
+use crate::stdlib::members::DaedalusClass;
+use enumflags2::{bitflags, BitFlags};
+#[bitflags]
 #[repr(u16)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ItemFlags {
     Chromakeyed = 1,
     Transparent = 2,
@@ -21,6 +26,7 @@ pub enum ItemFlags {
 }
 
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ItemKind {
     Undef,
     Text,
@@ -31,32 +37,61 @@ pub enum ItemKind {
     Button,
     ListBox,
 }
-#[derive(Default)]
+#[derive(Default, DaedalusClass)]
+#[daedalus(class = "C_MENU_ITEM")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MenuItem {
     instance_symbol: usize,
+    #[daedalus(name = "FONTNAME")]
     font_name: String,
+    #[daedalus(name = "TEXT")]
     text: Vec<String>,
+    #[daedalus(name = "BACKPIC")]
     back_pic: String,
-    alphaMode: String,
+    #[daedalus(name = "ALPHAMODE")]
+    alpha_mode: String,
+    #[daedalus(name = "ALPHA")]
     alpha: i32,
+    #[daedalus(name = "TYPE")]
     kind: i32,
+    #[daedalus(name = "ONSELACTION")]
     on_sel_action: Vec<i32>,
+    #[daedalus(name = "ONSELACTION_S")]
     on_sel_action_s: Vec<String>,
+    #[daedalus(name = "ONCHGSETOPTION")]
     on_chg_set_option: String,
+    #[daedalus(name = "ONCHGSETOPTIONSECTION")]
     on_chg_set_option_section: String,
 
+    #[daedalus(name = "ONEVENTACTION")]
     on_event_action: Vec<i32>,
+    #[daedalus(name = "POSX", element = 0)]
+    #[daedalus(name = "POSY", element = 1)]
     pos: (i32, i32),
+    #[daedalus(name = "DIMX", element = 0)]
+    #[daedalus(name = "DIMY", element = 1)]
     dim: (i32, i32),
+    #[daedalus(name = "SIZESTARTSCALE")]
     size_start_scale: f32,
+    /// Raw script bits, mods use bits without a variant in `ItemFlags`
+    #[daedalus(name = "FLAGS")]
     flags: i32,
+    #[daedalus(name = "OPENDELAYTIME")]
     open_delay_time: f32,
+    #[daedalus(name = "OPENDURATION")]
     open_duration: f32,
+    #[daedalus(name = "USERFLOAT")]
     user_float: Vec<f32>,
+    #[daedalus(name = "USERSTRING")]
     user_string: Vec<String>,
+    #[daedalus(name = "FRAMESIZEX", element = 0)]
+    #[daedalus(name = "FRAMESIZEY", element = 1)]
     frame_size: (i32, i32),
+    #[daedalus(name = "HIDEIFOPTIONSECTIONSET")]
     hide_if_option_section_set: String,
+    #[daedalus(name = "HIDEIFOPTIONSET")]
     hide_if_option_set: String,
+    #[daedalus(name = "HIDEONVALUE")]
     hide_on_value: i32,
 }
 
@@ -66,6 +101,35 @@ impl MenuItem {
     }
 }
 
+accessors!(MenuItem {
+    string font_name: String => get_font_name, set_font_name;
+    string_vec text[MAX_USERSTRINGS]: String => get_text, set_text;
+    string back_pic: String => get_back_pic, set_back_pic;
+    string alpha_mode: String => get_alpha_mode, set_alpha_mode;
+    value alpha: i32 => get_alpha, set_alpha;
+    value kind: i32 => get_kind, set_kind;
+    vec on_sel_action[MAX_SEL_ACTIONS]: i32 => get_on_sel_action, set_on_sel_action;
+    string_vec on_sel_action_s[MAX_SEL_ACTIONS]: String => get_on_sel_action_s, set_on_sel_action_s;
+    string on_chg_set_option: String => get_on_chg_set_option, set_on_chg_set_option;
+    string on_chg_set_option_section: String =>
+        get_on_chg_set_option_section, set_on_chg_set_option_section;
+    vec on_event_action[MAX_EVENTS]: i32 => get_on_event_action, set_on_event_action;
+    value pos: (i32, i32) => get_pos, set_pos;
+    value dim: (i32, i32) => get_dim, set_dim;
+    value size_start_scale: f32 => get_size_start_scale, set_size_start_scale;
+    value flags: i32 => get_raw_flags, set_raw_flags;
+    flags flags: ItemFlags => get_flags, set_flags;
+    value open_delay_time: f32 => get_open_delay_time, set_open_delay_time;
+    value open_duration: f32 => get_open_duration, set_open_duration;
+    vec user_float[MAX_USERVARS]: f32 => get_user_float, set_user_float;
+    string_vec user_string[MAX_USERVARS]: String => get_user_string, set_user_string;
+    value frame_size: (i32, i32) => get_frame_size, set_frame_size;
+    string hide_if_option_section_set: String =>
+        get_hide_if_option_section_set, set_hide_if_option_section_set;
+    string hide_if_option_set: String => get_hide_if_option_set, set_hide_if_option_set;
+    value hide_on_value: i32 => get_hide_on_value, set_hide_on_value;
+});
+
 impl Instance for MenuItem {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -74,3 +138,30 @@ impl Instance for MenuItem {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(MenuItem {
+    instance_symbol,
+    font_name,
+    text,
+    back_pic,
+    alpha_mode,
+    alpha,
+    kind,
+    on_sel_action,
+    on_sel_action_s,
+    on_chg_set_option,
+    on_chg_set_option_section,
+    on_event_action,
+    pos,
+    dim,
+    size_start_scale,
+    flags,
+    open_delay_time,
+    open_duration,
+    user_float,
+    user_string,
+    frame_size,
+    hide_if_option_section_set,
+    hide_if_option_set,
+    hide_on_value,
+});
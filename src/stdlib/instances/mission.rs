@@ -1,22 +1,39 @@
 use super::Instance;
-#[derive(Default)]
+use crate::persist_struct;
+use crate::stdlib::members::DaedalusClass;
+#[derive(Default, DaedalusClass)]
+#[daedalus(class = "C_MISSION")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mission {
     instance_symbol: usize,
     // Name des Auftraggebers
+    #[daedalus(name = "NAME")]
     name: String,
+    #[daedalus(name = "DESCRIPTION")]
     description: String,
     // Max. Dauer in Tageszeiten
+    #[daedalus(name = "DURATION")]
     duration: i32,
+    #[daedalus(name = "IMPORTANT")]
     important: i32,
 
+    #[daedalus(name = "OFFERCONDITIONS")]
     offer_conditions: u32,
+    #[daedalus(name = "OFFER")]
     offer: u32,
+    #[daedalus(name = "SUCCESSCONDITIONS")]
     success_conditions: u32,
+    #[daedalus(name = "SUCCESS")]
     success: u32,
+    #[daedalus(name = "FAILURECONDITIONS")]
     failure_conditions: u32,
+    #[daedalus(name = "FAILURE")]
     failure: u32,
+    #[daedalus(name = "OBSOLETECONDITIONS")]
     obsolete_conditions: u32,
+    #[daedalus(name = "OBSOLETE")]
     obsolete: u32,
+    #[daedalus(name = "RUNNING")]
     running: u32,
 }
 impl Mission {
@@ -24,6 +41,22 @@ impl Mission {
         Default::default()
     }
 }
+
+accessors!(Mission {
+    string name: String => get_name, set_name;
+    string description: String => get_description, set_description;
+    value duration: i32 => get_duration, set_duration;
+    value important: i32 => get_important, set_important;
+    value offer_conditions: u32 => get_offer_conditions, set_offer_conditions;
+    value offer: u32 => get_offer, set_offer;
+    value success_conditions: u32 => get_success_conditions, set_success_conditions;
+    value success: u32 => get_success, set_success;
+    value failure_conditions: u32 => get_failure_conditions, set_failure_conditions;
+    value failure: u32 => get_failure, set_failure;
+    value obsolete_conditions: u32 => get_obsolete_conditions, set_obsolete_conditions;
+    value obsolete: u32 => get_obsolete, set_obsolete;
+    value running: u32 => get_running, set_running;
+});
 impl Instance for Mission {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -32,3 +65,20 @@ impl Instance for Mission {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(Mission {
+    instance_symbol,
+    name,
+    description,
+    duration,
+    important,
+    offer_conditions,
+    offer,
+    success_conditions,
+    success,
+    failure_conditions,
+    failure,
+    obsolete_conditions,
+    obsolete,
+    running,
+});
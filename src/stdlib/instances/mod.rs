@@ -1,3 +1,111 @@
+/// Generates `get_*` and `set_*` for the listed fields.
+/// Arrays are indexed by constants like `DAM_INDEX_*` and panic on invalid indices,
+/// vectors follow script arrays and grow on assignment up to their maximum length.
+/// Flags are typed views of a raw `i32` field which keep the bits without a variant.
+macro_rules! accessors {
+    ($name:ident { $($kind:ident $field:ident $([$len:expr])?: $ty:ty => $get:ident, $set:ident;)* }) => {
+        impl $name {
+            $(accessors!(@$kind $field, $ty, $get, $set $(, $len)?);)*
+        }
+    };
+    (@value $field:ident, $ty:ty, $get:ident, $set:ident) => {
+        pub fn $get(&self) -> $ty {
+            self.$field
+        }
+        pub fn $set(&mut self, value: $ty) {
+            self.$field = value;
+        }
+    };
+    (@string $field:ident, $ty:ty, $get:ident, $set:ident) => {
+        pub fn $get(&self) -> &str {
+            &self.$field
+        }
+        pub fn $set(&mut self, value: &str) {
+            self.$field = value.to_owned();
+        }
+    };
+    (@array $field:ident, $ty:ty, $get:ident, $set:ident) => {
+        pub fn $get(&self, index: usize) -> $ty {
+            self.$field[index]
+        }
+        pub fn $set(&mut self, index: usize, value: $ty) {
+            self.$field[index] = value;
+        }
+    };
+    (@string_array $field:ident, $ty:ty, $get:ident, $set:ident) => {
+        pub fn $get(&self, index: usize) -> &str {
+            &self.$field[index]
+        }
+        pub fn $set(&mut self, index: usize, value: &str) {
+            self.$field[index] = value.to_owned();
+        }
+    };
+    (@flags $field:ident, $ty:ty, $get:ident, $set:ident) => {
+        pub fn $get(&self) -> BitFlags<$ty> {
+            super::known_flags(self.$field)
+        }
+        pub fn $set(&mut self, flags: BitFlags<$ty>) {
+            self.$field = super::with_known_flags(self.$field, flags);
+        }
+    };
+    (@vec $field:ident, $ty:ty, $get:ident, $set:ident, $len:expr) => {
+        pub fn $get(&self, index: usize) -> $ty {
+            self.$field.get(index).copied().unwrap_or_default()
+        }
+        /// Returns false if the index is past the maximum length
+        pub fn $set(&mut self, index: usize, value: $ty) -> bool {
+            if index >= $len {
+                return false;
+            }
+            if index >= self.$field.len() {
+                self.$field.resize_with(index + 1, Default::default);
+            }
+            self.$field[index] = value;
+            true
+        }
+    };
+    (@string_vec $field:ident, $ty:ty, $get:ident, $set:ident, $len:expr) => {
+        pub fn $get(&self, index: usize) -> &str {
+            self.$field.get(index).map_or("", |value| value.as_str())
+        }
+        /// Returns false if the index is past the maximum length
+        pub fn $set(&mut self, index: usize, value: &str) -> bool {
+            if index >= $len {
+                return false;
+            }
+            if index >= self.$field.len() {
+                self.$field.resize_with(index + 1, Default::default);
+            }
+            self.$field[index] = value.to_owned();
+            true
+        }
+    };
+}
+
+use enumflags2::{BitFlag, BitFlags};
+use std::convert::TryFrom;
+
+/// Bits of the raw script flags which have a variant in `T`
+fn known_flags<T>(raw: i32) -> BitFlags<T>
+where
+    T: BitFlag,
+    T::Numeric: TryFrom<u32> + Into<u64>,
+{
+    let known = raw as u32 as u64 & BitFlags::<T>::all().bits().into();
+    T::Numeric::try_from(known as u32).map_or(BitFlags::empty(), BitFlags::from_bits_truncate)
+}
+/// Replaces the bits of the raw script flags which have a variant in `T`
+fn with_known_flags<T>(raw: i32, flags: BitFlags<T>) -> i32
+where
+    T: BitFlag,
+    T::Numeric: Into<u64>,
+{
+    let all: u64 = BitFlags::<T>::all().bits().into();
+    let bits: u64 = flags.bits().into();
+    ((raw as u32 as u64 & !all) | bits) as u32 as i32
+}
+
+pub mod dynamic;
 pub mod focus;
 pub mod info;
 pub mod item;
@@ -14,17 +122,28 @@ pub mod spell;
 pub const MAX_CHAPTER: usize = 5;
 pub const MAX_MISSIONS: usize = 5;
 pub const MAX_HITCHANCE: usize = 5;
+pub const MAX_AIVAR: usize = 100;
+pub const ATR_HITPOINTS: usize = 0;
+pub const ATR_HITPOINTS_MAX: usize = 1;
+pub const ATR_MANA: usize = 2;
+pub const ATR_MANA_MAX: usize = 3;
+pub const ATR_STRENGTH: usize = 4;
+pub const ATR_DEXTERITY: usize = 5;
+pub const ATR_REGENERATEHP: usize = 6;
+pub const ATR_REGENERATEMANA: usize = 7;
 pub const ATR_INDEX_MAX: usize = 8;
 pub const ITM_TEXT_MAX: usize = 6;
 
-pub const DAM_INDEX_BARRIER: usize = 0; //				 nur der Vollstandigkeit und Transparenz wegen hier definiert ( _NICHT_ verwenden )
+// nur der Vollstandigkeit und Transparenz wegen hier definiert ( _NICHT_ verwenden )
+pub const DAM_INDEX_BARRIER: usize = 0;
 pub const DAM_INDEX_BLUNT: usize = DAM_INDEX_BARRIER + 1;
 pub const DAM_INDEX_EDGE: usize = DAM_INDEX_BLUNT + 1;
 pub const DAM_INDEX_FIRE: usize = DAM_INDEX_EDGE + 1;
 pub const DAM_INDEX_FLY: usize = DAM_INDEX_FIRE + 1;
 pub const DAM_INDEX_MAGIC: usize = DAM_INDEX_FLY + 1;
 pub const DAM_INDEX_POINT: usize = DAM_INDEX_MAGIC + 1;
-pub const DAM_INDEX_FALL: usize = DAM_INDEX_POINT + 1; //				 nur der Vollstandigkeit und Transparenz wegen hier definiert ( _NICHT_ verwenden )
+// nur der Vollstandigkeit und Transparenz wegen hier definiert ( _NICHT_ verwenden )
+pub const DAM_INDEX_FALL: usize = DAM_INDEX_POINT + 1;
 pub const DAM_INDEX_MAX: usize = DAM_INDEX_FALL + 1;
 
 pub const PROT_BARRIER: usize = DAM_INDEX_BARRIER;
@@ -37,6 +156,24 @@ pub const PROT_POINT: usize = DAM_INDEX_POINT;
 pub const PROT_FALL: usize = DAM_INDEX_FALL;
 pub const PROT_INDEX_MAX: usize = DAM_INDEX_MAX;
 
+pub const DAM_BARRIER: i32 = 1 << DAM_INDEX_BARRIER;
+pub const DAM_BLUNT: i32 = 1 << DAM_INDEX_BLUNT;
+pub const DAM_EDGE: i32 = 1 << DAM_INDEX_EDGE;
+pub const DAM_FIRE: i32 = 1 << DAM_INDEX_FIRE;
+pub const DAM_FLY: i32 = 1 << DAM_INDEX_FLY;
+pub const DAM_MAGIC: i32 = 1 << DAM_INDEX_MAGIC;
+pub const DAM_POINT: i32 = 1 << DAM_INDEX_POINT;
+pub const DAM_FALL: i32 = 1 << DAM_INDEX_FALL;
+
+/// Protection value making the npc immune against the damage type
+pub const IMMUNE: i32 = -1;
+
+pub const NPC_TALENT_UNKNOWN: usize = 0;
+pub const NPC_TALENT_1H: usize = 1;
+pub const NPC_TALENT_2H: usize = 2;
+pub const NPC_TALENT_BOW: usize = 3;
+pub const NPC_TALENT_CROSSBOW: usize = 4;
+
 pub const COND_ATR_MAX: usize = 3;
 
 pub trait Instance {
@@ -1,13 +1,24 @@
 use super::Instance;
-#[derive(Default)]
+use crate::persist_struct;
+use crate::stdlib::members::DaedalusClass;
+#[derive(Default, DaedalusClass)]
+#[daedalus(class = "C_MUSICTHEME")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MusicTheme {
     instance_symbol: usize,
+    #[daedalus(name = "FILE")]
     file: String,
+    #[daedalus(name = "VOL")]
     vol: f32,
+    #[daedalus(name = "LOOP")]
     loop_: i32,
+    #[daedalus(name = "REVERBMIX")]
     reverb_mix: f32,
+    #[daedalus(name = "REVERBTIME")]
     reverb_time: f32,
+    #[daedalus(name = "TRANSTYPE")]
     trans_type: i32,
+    #[daedalus(name = "TRANSSUBTYPE")]
     trans_sub_type: i32,
 }
 
@@ -16,6 +27,16 @@ impl MusicTheme {
         Default::default()
     }
 }
+
+accessors!(MusicTheme {
+    string file: String => get_file, set_file;
+    value vol: f32 => get_vol, set_vol;
+    value loop_: i32 => get_loop, set_loop;
+    value reverb_mix: f32 => get_reverb_mix, set_reverb_mix;
+    value reverb_time: f32 => get_reverb_time, set_reverb_time;
+    value trans_type: i32 => get_trans_type, set_trans_type;
+    value trans_sub_type: i32 => get_trans_sub_type, set_trans_sub_type;
+});
 impl Instance for MusicTheme {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -24,3 +45,14 @@ impl Instance for MusicTheme {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(MusicTheme {
+    instance_symbol,
+    file,
+    vol,
+    loop_,
+    reverb_mix,
+    reverb_time,
+    trans_type,
+    trans_sub_type,
+});
@@ -1,8 +1,12 @@
-use enumflags2::BitFlags;
+use crate::persist_struct;
+use crate::stdlib::members::{DaedalusClass, MemberField, MemberValue};
+use crate::vm::file::Kind;
+use enumflags2::{bitflags, BitFlags};
 
 use super::*;
 
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Attribute {
     hit_points: i32,
     hit_points_max: i32,
@@ -14,8 +18,45 @@ struct Attribute {
     regerenate_mana: i32,
 }
 
-#[derive(BitFlags, Copy, Clone)]
+/// Script side this is the ATTRIBUTE array indexed by ATR_*
+impl MemberField for Attribute {
+    const KINDS: &'static [Kind] = &[Kind::Int];
+    const ARRAY_SIZE: Option<u32> = Some(ATR_INDEX_MAX as u32);
+
+    fn get_value(&self, array_index: usize) -> Option<MemberValue> {
+        let value = match array_index {
+            0 => self.hit_points,
+            1 => self.hit_points_max,
+            2 => self.mana,
+            3 => self.mana_max,
+            4 => self.strength,
+            5 => self.dexterity,
+            6 => self.regernate_hp,
+            7 => self.regerenate_mana,
+            _ => return None,
+        };
+        Some(MemberValue::Int(value))
+    }
+    fn set_value(&mut self, array_index: usize, value: MemberValue) -> bool {
+        let field = match array_index {
+            0 => &mut self.hit_points,
+            1 => &mut self.hit_points_max,
+            2 => &mut self.mana,
+            3 => &mut self.mana_max,
+            4 => &mut self.strength,
+            5 => &mut self.dexterity,
+            6 => &mut self.regernate_hp,
+            7 => &mut self.regerenate_mana,
+            _ => return false,
+        };
+        field.set_value(0, value)
+    }
+}
+
+#[bitflags]
 #[repr(u16)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Flag {
     //Nil = 0,
     Friends = 1 << 0,
@@ -23,48 +64,85 @@ pub enum Flag {
     Ghost = 1 << 2,
     Protected = 1 << 10,
 }
-#[derive(Default)]
+#[derive(Default, DaedalusClass)]
+#[daedalus(class = "C_NPC")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Npc {
     instance_symbol: usize,
+    #[daedalus(name = "ID")]
     id: i32,
+    #[daedalus(name = "NAME")]
     name: [String; 5],
+    #[daedalus(name = "SLOT")]
     slot: String,
+    #[daedalus(name = "EFFECT")]
     effect: String,
+    #[daedalus(name = "NPCTYPE")]
     kind: i32,
-    flags: Option<Flag>,
+    /// Raw script bits, mods use bits without a variant in `Flag`
+    #[daedalus(name = "FLAGS")]
+    flags: i32,
+    #[daedalus(name = "ATTRIBUTE")]
     attributes: Attribute,
+    #[daedalus(name = "HITCHANCE")]
     hit_chance: [i32; MAX_HITCHANCE],
+    #[daedalus(name = "PROTECTION")]
     protection: [i32; PROT_INDEX_MAX],
+    #[daedalus(name = "DAMAGE")]
     damage: [i32; DAM_INDEX_MAX],
+    #[daedalus(name = "DAMAGETYPE")]
     damage_type: i32,
+    #[daedalus(name = "GUILD")]
     guild: i32,
+    #[daedalus(name = "LEVEL")]
     level: i32,
+    #[daedalus(name = "MISSION")]
     mission: [u32; MAX_MISSIONS],
+    #[daedalus(name = "FIGHT_TACTIC")]
     fight_tactic: i32,
+    #[daedalus(name = "WEAPON")]
     weapon: i32,
 
+    #[daedalus(name = "VOICE")]
     voice: i32,
+    #[daedalus(name = "VOICEPITCH")]
     voice_pitch: i32,
+    #[daedalus(name = "BODYMASS")]
     body_mass: i32,
+    #[daedalus(name = "DAILY_ROUTINE")]
     daily_routine: u32,
+    #[daedalus(name = "START_AISTATE")]
     start_ai_state: u32,
 
+    #[daedalus(name = "SPAWNPOINT")]
     spawn_point: String,
+    #[daedalus(name = "SPAWNDELAY")]
     spawn_delay: i32,
 
+    #[daedalus(name = "SENSES")]
     senses: i32,
+    #[daedalus(name = "SENSES_RANGE")]
     senses_range: i32,
 
+    #[daedalus(name = "AIVAR")]
     ai_var: Vec<i32>,
+    #[daedalus(name = "WP")]
     waypoint: String,
 
+    #[daedalus(name = "EXP")]
     exp: i32,
+    #[daedalus(name = "EXP_NEXT")]
     exp_next: i32,
+    #[daedalus(name = "LP")]
     lp: i32,
 
-    // If this is set to TRUE, the Npc can't be interrupted in any action (e.g. BS_FLAG_INTERRUPTABLE for anis is being ignored)
+    // If this is set to TRUE, the Npc can't be interrupted in any action
+    // (e.g. BS_FLAG_INTERRUPTABLE for anis is being ignored)
+    #[daedalus(name = "BODYSTATEINTERRUPTABLEOVERRIDE")]
     body_state_interruptable_override: i32,
-    // if "noFocus" is set to TRUE, the focus name and health bar will not be drawn of this nsc (hi, stefan!)
+    // if "noFocus" is set to TRUE, the focus name and health bar will not be drawn
+    // of this nsc (hi, stefan!)
+    #[daedalus(name = "NOFOCUS")]
     no_focus: i32,
 }
 
@@ -72,11 +150,57 @@ impl Npc {
     pub fn new() -> Self {
         Default::default()
     }
-    pub fn set_waypoint(&mut self, waypoint: &str) {
-        self.waypoint = waypoint.to_owned();
+    /// Attribute by its `ATR_*` index, panics on invalid indices
+    pub fn get_attribute(&self, index: usize) -> i32 {
+        self.attributes
+            .get_value(index)
+            .expect("Invalid attribute index")
+            .as_int()
+    }
+    pub fn set_attribute(&mut self, index: usize, value: i32) {
+        assert!(
+            self.attributes.set_value(index, MemberValue::Int(value)),
+            "Invalid attribute index"
+        );
     }
 }
 
+accessors!(Npc {
+    value id: i32 => get_id, set_id;
+    string_array name: String => get_name, set_name;
+    string slot: String => get_slot, set_slot;
+    string effect: String => get_effect, set_effect;
+    value kind: i32 => get_kind, set_kind;
+    value flags: i32 => get_raw_flags, set_raw_flags;
+    flags flags: Flag => get_flags, set_flags;
+    array hit_chance: i32 => get_hit_chance, set_hit_chance;
+    array protection: i32 => get_protection, set_protection;
+    array damage: i32 => get_damage, set_damage;
+    value damage_type: i32 => get_damage_type, set_damage_type;
+    value guild: i32 => get_guild, set_guild;
+    value level: i32 => get_level, set_level;
+    array mission: u32 => get_mission, set_mission;
+    value fight_tactic: i32 => get_fight_tactic, set_fight_tactic;
+    value weapon: i32 => get_weapon, set_weapon;
+    value voice: i32 => get_voice, set_voice;
+    value voice_pitch: i32 => get_voice_pitch, set_voice_pitch;
+    value body_mass: i32 => get_body_mass, set_body_mass;
+    value daily_routine: u32 => get_daily_routine, set_daily_routine;
+    value start_ai_state: u32 => get_start_ai_state, set_start_ai_state;
+    string spawn_point: String => get_spawn_point, set_spawn_point;
+    value spawn_delay: i32 => get_spawn_delay, set_spawn_delay;
+    value senses: i32 => get_senses, set_senses;
+    value senses_range: i32 => get_senses_range, set_senses_range;
+    vec ai_var[MAX_AIVAR]: i32 => get_ai_var, set_ai_var;
+    string waypoint: String => get_waypoint, set_waypoint;
+    value exp: i32 => get_exp, set_exp;
+    value exp_next: i32 => get_exp_next, set_exp_next;
+    value lp: i32 => get_lp, set_lp;
+    value body_state_interruptable_override: i32 =>
+        get_body_state_interruptable_override, set_body_state_interruptable_override;
+    value no_focus: i32 => get_no_focus, set_no_focus;
+});
+
 impl Instance for Npc {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -85,3 +209,50 @@ impl Instance for Npc {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(Attribute {
+    hit_points,
+    hit_points_max,
+    mana,
+    mana_max,
+    strength,
+    dexterity,
+    regernate_hp,
+    regerenate_mana,
+});
+
+persist_struct!(Npc {
+    instance_symbol,
+    id,
+    name,
+    slot,
+    effect,
+    kind,
+    flags,
+    attributes,
+    hit_chance,
+    protection,
+    damage,
+    damage_type,
+    guild,
+    level,
+    mission,
+    fight_tactic,
+    weapon,
+    voice,
+    voice_pitch,
+    body_mass,
+    daily_routine,
+    start_ai_state,
+    spawn_point,
+    spawn_delay,
+    senses,
+    senses_range,
+    ai_var,
+    waypoint,
+    exp,
+    exp_next,
+    lp,
+    body_state_interruptable_override,
+    no_focus,
+});
@@ -1,97 +1,160 @@
 use super::Instance;
-#[derive(Default)]
+use crate::persist_struct;
+use crate::stdlib::members::DaedalusClass;
+#[derive(Default, DaedalusClass)]
+#[daedalus(class = "C_PARTICLEFX")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParticleEffect {
     instance_symbol: usize,
     // 1) Emitter: zeitliches  Austoss-Verhalten, particles-per-second
+    #[daedalus(name = "PPSVALUE")]
     pps_value: f32,
+    #[daedalus(name = "PPSSCALEKEYS_S")]
     pps_scale_keys: String,
+    #[daedalus(name = "PPSISLOOPING")]
     pps_is_looping: i32,
+    #[daedalus(name = "PPSISSMOOTH")]
     pps_is_smooth: i32,
+    #[daedalus(name = "PPSFPS")]
     pps_fps: f32,
+    #[daedalus(name = "PPSCREATEEM_S")]
     pps_create_em: String,
+    #[daedalus(name = "PPSCREATEEMDELAY")]
     pps_create_em_delay: f32,
 
     // 2) Emitter: raeumliches Austoss-Verhalten
+    #[daedalus(name = "SHPTYPE_S")]
     shp_type: String, //	"point, line, box, circle, sphere, mesh"
-    shp_for: String,  //	"object,world"
+    #[daedalus(name = "SHPFOR_S")]
+    shp_for: String, //	"object,world"
+    #[daedalus(name = "SHPOFFSETVEC_S")]
     shp_offset_vec: String,
+    #[daedalus(name = "SHPDISTRIBTYPE_S")]
     shp_distrib_type: String, //	"RAND, UNIFORM, WALK"
+    #[daedalus(name = "SHPDISTRIBWALKSPEED")]
     shp_distrib_walk_speed: f32,
+    #[daedalus(name = "SHPISVOLUME")]
     shp_is_volume: i32,
+    #[daedalus(name = "SHPDIM_S")]
     shp_dim: String, //	"", "30", "10 20 30", "30", "30", "" //	line: nur 1 Dimension !=0 // shape Dimensions
+    #[daedalus(name = "SHPMESH_S")]
     shp_mesh: String, //	"cross.3ds"
+    #[daedalus(name = "SHPMESHRENDER_B")]
     shp_mesh_render: i32,
+    #[daedalus(name = "SHPSCALEKEYS_S")]
     shp_scale_keys: String, //	"[1.0] [0.8 0.9 0.2] [1.0]"
+    #[daedalus(name = "SHPSCALEISLOOPING")]
     shp_scale_is_looping: i32,
+    #[daedalus(name = "SHPSCALEISSMOOTH")]
     shp_scale_is_smooth: i32,
+    #[daedalus(name = "SHPSCALEFPS")]
     shp_scale_fps: f32,
 
     // 3) Partikel: Start Richtung/Speed:
+    #[daedalus(name = "DIRMODE_S")]
     dir_mode: String, //	"DIR, TARGET, MESH_POLY"
-    dir_for: String,  //	"OBJECT, WORLD"
+    #[daedalus(name = "DIRFOR_S")]
+    dir_for: String, //	"OBJECT, WORLD"
+    #[daedalus(name = "DIRMODETARGETFOR_S")]
     dir_mode_target_for: String,
+    #[daedalus(name = "DIRMODETARGETPOS_S")]
     dir_mode_target_pos: String, //	"30 23 67"
+    #[daedalus(name = "DIRANGLEHEAD")]
     dir_angle_head: f32,
+    #[daedalus(name = "DIRANGLEHEADVAR")]
     dir_angle_head_var: f32,
+    #[daedalus(name = "DIRANGLEELEV")]
     dir_angle_elev: f32,
+    #[daedalus(name = "DIRANGLEELEVVAR")]
     dir_angle_elev_var: f32,
+    #[daedalus(name = "VELAVG")]
     vel_avg: f32,
+    #[daedalus(name = "VELVAR")]
     vel_var: f32,
 
     // 4) Partikel: Lebensdauer
+    #[daedalus(name = "LSPPARTAVG")]
     lsp_part_avg: f32,
+    #[daedalus(name = "LSPPARTVAR")]
     lsp_part_var: f32,
 
     // 5) Partikel: Flugverhalten (gravity, nicht-linear?, mesh-selfRot?,..)
     // grav: a) nur Y, b) XYZ, c) auf Ziel zu steuern
     //  flyMode_S;								//	"LINEAR, LIN_SINUS,.."
     // flyMeshSelfRotSpeedMin, flyMeshSelfRotSpeedMax
+    #[daedalus(name = "FLYGRAVITY_S")]
     fly_gravity: String,
+    #[daedalus(name = "FLYCOLLDET_B")]
     fly_coll_det: i32,
 
     // 6) Partikel: Visualisierung
-    vis_name: String,          //	"NAME_V0_A0.TGA/.3DS"	(Variation, Animation)
-    vis_orientation: String,   //	"NONE, VELO"
+    #[daedalus(name = "VISNAME_S")]
+    vis_name: String, //	"NAME_V0_A0.TGA/.3DS"	(Variation, Animation)
+    #[daedalus(name = "VISORIENTATION_S")]
+    vis_orientation: String, //	"NONE, VELO"
+    #[daedalus(name = "VISTEXISQUADPOLY")]
     vis_tex_is_quad_poly: i32, //	0=triMesh, 1=quadMesh
+    #[daedalus(name = "VISTEXANIFPS")]
     vis_tex_ani_fps: f32,
+    #[daedalus(name = "VISTEXANIISLOOPING")]
     vis_tex_ani_is_looping: i32, //	0=oneShot, 1=looping
     // color		(nur Tex, lifeSpan-Sync)
+    #[daedalus(name = "VISTEXCOLORSTART_S")]
     vis_tex_color_start: String,
+    #[daedalus(name = "VISTEXCOLOREND_S")]
     vis_tex_color_end: String,
     // size-ani		(nur Tex, lifeSpan-Sync)
+    #[daedalus(name = "VISSIZESTART_S")]
     vis_size_start: String,
+    #[daedalus(name = "VISSIZEENDSCALE")]
     vis_size_end_scale: f32,
     // alpha		(lifeSpan-Sync)
+    #[daedalus(name = "VISALPHAFUNC_S")]
     vis_alpha_func: String,
+    #[daedalus(name = "VISALPHASTART")]
     vis_alpha_start: f32,
+    #[daedalus(name = "VISALPHAEND")]
     vis_alpha_end: f32,
 
     // 7) misc effects
 
     // trail
+    #[daedalus(name = "TRLFADESPEED")]
     trl_fade_speed: f32,
+    #[daedalus(name = "TRLTEXTURE_S")]
     trl_texture: String,
+    #[daedalus(name = "TRLWIDTH")]
     trl_width: f32,
 
     // marks
+    #[daedalus(name = "MRKFADESPEED")]
     mrk_fade_speed: f32,
+    #[daedalus(name = "MRKTEXTURE_S")]
     mrk_texture: String,
+    #[daedalus(name = "MRKSIZE")]
     mrk_size: f32,
 
     // flocking
+    #[daedalus(name = "FLOCKMODE")]
     flock_mode: String,
+    #[daedalus(name = "FLOCKSTRENGTH")]
     flock_strength: f32,
 
     // local frame of reference override
     // calculates the position of the particles each frame relative to the emitters pos/rot
     // can be expensive
     // WARNING: in comb with flyCollDet_B this can be a performance-hog deluxe
+    #[daedalus(name = "USEEMITTERSFOR")]
     use_emitters_for: i32,
 
-    // optional you can set a valid timeperiod in which this pfx should be rendered (e.g. "8 22": should be rendererd from 8 to 22 o clock")
+    // optional you can set a valid timeperiod in which this pfx should be rendered
+    // (e.g. "8 22": should be rendererd from 8 to 22 o clock")
+    #[daedalus(name = "TIMESTARTEND_S")]
     time_start_end: String,
 
-    // with the next setting you can define weather this pfx is an ambient pfx, thus can be disabled in the gothic.ini with the value [ENGINE]/noAmbientPFX
+    // with the next setting you can define weather this pfx is an ambient pfx,
+    // thus can be disabled in the gothic.ini with the value [ENGINE]/noAmbientPFX
+    #[daedalus(name = "M_BISAMBIENTPFX")]
     b_is_ambient_pfx: i32,
 }
 
@@ -99,8 +162,87 @@ impl ParticleEffect {
     pub fn new() -> Self {
         Default::default()
     }
+    /// Hours of `time_start_end`, e.g. `"8 22"`, `None` if the effect is not timed
+    pub fn get_time_range(&self) -> Option<(f32, f32)> {
+        let mut hours = self
+            .time_start_end
+            .split_whitespace()
+            .map(|hour| hour.parse::<f32>());
+        match (hours.next(), hours.next()) {
+            (Some(Ok(start)), Some(Ok(end))) => Some((start, end)),
+            _ => None,
+        }
+    }
+    /// Whether a timed effect is visible at the hour, untimed effects always are
+    pub fn is_active_at(&self, hour: f32) -> bool {
+        match self.get_time_range() {
+            Some((start, end)) if start <= end => start <= hour && hour < end,
+            Some((start, end)) => hour >= start || hour < end,
+            None => true,
+        }
+    }
 }
 
+accessors!(ParticleEffect {
+    value pps_value: f32 => get_pps_value, set_pps_value;
+    string pps_scale_keys: String => get_pps_scale_keys, set_pps_scale_keys;
+    value pps_is_looping: i32 => get_pps_is_looping, set_pps_is_looping;
+    value pps_is_smooth: i32 => get_pps_is_smooth, set_pps_is_smooth;
+    value pps_fps: f32 => get_pps_fps, set_pps_fps;
+    string pps_create_em: String => get_pps_create_em, set_pps_create_em;
+    value pps_create_em_delay: f32 => get_pps_create_em_delay, set_pps_create_em_delay;
+    string shp_type: String => get_shp_type, set_shp_type;
+    string shp_for: String => get_shp_for, set_shp_for;
+    string shp_offset_vec: String => get_shp_offset_vec, set_shp_offset_vec;
+    string shp_distrib_type: String => get_shp_distrib_type, set_shp_distrib_type;
+    value shp_distrib_walk_speed: f32 => get_shp_distrib_walk_speed, set_shp_distrib_walk_speed;
+    value shp_is_volume: i32 => get_shp_is_volume, set_shp_is_volume;
+    string shp_dim: String => get_shp_dim, set_shp_dim;
+    string shp_mesh: String => get_shp_mesh, set_shp_mesh;
+    value shp_mesh_render: i32 => get_shp_mesh_render, set_shp_mesh_render;
+    string shp_scale_keys: String => get_shp_scale_keys, set_shp_scale_keys;
+    value shp_scale_is_looping: i32 => get_shp_scale_is_looping, set_shp_scale_is_looping;
+    value shp_scale_is_smooth: i32 => get_shp_scale_is_smooth, set_shp_scale_is_smooth;
+    value shp_scale_fps: f32 => get_shp_scale_fps, set_shp_scale_fps;
+    string dir_mode: String => get_dir_mode, set_dir_mode;
+    string dir_for: String => get_dir_for, set_dir_for;
+    string dir_mode_target_for: String => get_dir_mode_target_for, set_dir_mode_target_for;
+    string dir_mode_target_pos: String => get_dir_mode_target_pos, set_dir_mode_target_pos;
+    value dir_angle_head: f32 => get_dir_angle_head, set_dir_angle_head;
+    value dir_angle_head_var: f32 => get_dir_angle_head_var, set_dir_angle_head_var;
+    value dir_angle_elev: f32 => get_dir_angle_elev, set_dir_angle_elev;
+    value dir_angle_elev_var: f32 => get_dir_angle_elev_var, set_dir_angle_elev_var;
+    value vel_avg: f32 => get_vel_avg, set_vel_avg;
+    value vel_var: f32 => get_vel_var, set_vel_var;
+    value lsp_part_avg: f32 => get_lsp_part_avg, set_lsp_part_avg;
+    value lsp_part_var: f32 => get_lsp_part_var, set_lsp_part_var;
+    string fly_gravity: String => get_fly_gravity, set_fly_gravity;
+    value fly_coll_det: i32 => get_fly_coll_det, set_fly_coll_det;
+    string vis_name: String => get_vis_name, set_vis_name;
+    string vis_orientation: String => get_vis_orientation, set_vis_orientation;
+    value vis_tex_is_quad_poly: i32 => get_vis_tex_is_quad_poly, set_vis_tex_is_quad_poly;
+    value vis_tex_ani_fps: f32 => get_vis_tex_ani_fps, set_vis_tex_ani_fps;
+    value vis_tex_ani_is_looping: i32 => get_vis_tex_ani_is_looping, set_vis_tex_ani_is_looping;
+    string vis_tex_color_start: String => get_vis_tex_color_start, set_vis_tex_color_start;
+    string vis_tex_color_end: String => get_vis_tex_color_end, set_vis_tex_color_end;
+    string vis_size_start: String => get_vis_size_start, set_vis_size_start;
+    value vis_size_end_scale: f32 => get_vis_size_end_scale, set_vis_size_end_scale;
+    string vis_alpha_func: String => get_vis_alpha_func, set_vis_alpha_func;
+    value vis_alpha_start: f32 => get_vis_alpha_start, set_vis_alpha_start;
+    value vis_alpha_end: f32 => get_vis_alpha_end, set_vis_alpha_end;
+    value trl_fade_speed: f32 => get_trl_fade_speed, set_trl_fade_speed;
+    string trl_texture: String => get_trl_texture, set_trl_texture;
+    value trl_width: f32 => get_trl_width, set_trl_width;
+    value mrk_fade_speed: f32 => get_mrk_fade_speed, set_mrk_fade_speed;
+    string mrk_texture: String => get_mrk_texture, set_mrk_texture;
+    value mrk_size: f32 => get_mrk_size, set_mrk_size;
+    string flock_mode: String => get_flock_mode, set_flock_mode;
+    value flock_strength: f32 => get_flock_strength, set_flock_strength;
+    value use_emitters_for: i32 => get_use_emitters_for, set_use_emitters_for;
+    string time_start_end: String => get_time_start_end, set_time_start_end;
+    value b_is_ambient_pfx: i32 => get_b_is_ambient_pfx, set_b_is_ambient_pfx;
+});
+
 impl Instance for ParticleEffect {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -109,3 +251,64 @@ impl Instance for ParticleEffect {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(ParticleEffect {
+    instance_symbol,
+    pps_value,
+    pps_scale_keys,
+    pps_is_looping,
+    pps_is_smooth,
+    pps_fps,
+    pps_create_em,
+    pps_create_em_delay,
+    shp_type,
+    shp_for,
+    shp_offset_vec,
+    shp_distrib_type,
+    shp_distrib_walk_speed,
+    shp_is_volume,
+    shp_dim,
+    shp_mesh,
+    shp_mesh_render,
+    shp_scale_keys,
+    shp_scale_is_looping,
+    shp_scale_is_smooth,
+    shp_scale_fps,
+    dir_mode,
+    dir_for,
+    dir_mode_target_for,
+    dir_mode_target_pos,
+    dir_angle_head,
+    dir_angle_head_var,
+    dir_angle_elev,
+    dir_angle_elev_var,
+    vel_avg,
+    vel_var,
+    lsp_part_avg,
+    lsp_part_var,
+    fly_gravity,
+    fly_coll_det,
+    vis_name,
+    vis_orientation,
+    vis_tex_is_quad_poly,
+    vis_tex_ani_fps,
+    vis_tex_ani_is_looping,
+    vis_tex_color_start,
+    vis_tex_color_end,
+    vis_size_start,
+    vis_size_end_scale,
+    vis_alpha_func,
+    vis_alpha_start,
+    vis_alpha_end,
+    trl_fade_speed,
+    trl_texture,
+    trl_width,
+    mrk_fade_speed,
+    mrk_texture,
+    mrk_size,
+    flock_mode,
+    flock_strength,
+    use_emitters_for,
+    time_start_end,
+    b_is_ambient_pfx,
+});
@@ -1,16 +1,29 @@
 use super::Instance;
+use crate::persist_struct;
+use crate::stdlib::members::DaedalusClass;
 use std::num::NonZeroI32;
-#[derive(Default)]
+#[derive(Default, DaedalusClass)]
+#[daedalus(class = "C_SFX")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SoundEffect {
     instance_symbol: usize,
+    #[daedalus(name = "FILE")]
     file: String,
+    #[daedalus(name = "PITCHOFF")]
     pitch_off: Option<NonZeroI32>,
+    #[daedalus(name = "PITCHVAR")]
     pitch_var: Option<NonZeroI32>,
+    #[daedalus(name = "VOL")]
     vol: i32,
+    #[daedalus(name = "LOOP")]
     loop_: Option<NonZeroI32>,
+    #[daedalus(name = "LOOPSTARTOFFSET")]
     loop_start_offset: Option<NonZeroI32>,
-    loop_end_offseT: Option<NonZeroI32>,
+    #[daedalus(name = "LOOPENDOFFSET")]
+    loop_end_offset: Option<NonZeroI32>,
+    #[daedalus(name = "REVERBLEVEL")]
     reverb_level: f32,
+    #[daedalus(name = "PFXNAME")]
     pfx_name: String,
 }
 impl SoundEffect {
@@ -18,6 +31,18 @@ impl SoundEffect {
         Default::default()
     }
 }
+
+accessors!(SoundEffect {
+    string file: String => get_file, set_file;
+    value pitch_off: Option<NonZeroI32> => get_pitch_off, set_pitch_off;
+    value pitch_var: Option<NonZeroI32> => get_pitch_var, set_pitch_var;
+    value vol: i32 => get_vol, set_vol;
+    value loop_: Option<NonZeroI32> => get_loop, set_loop;
+    value loop_start_offset: Option<NonZeroI32> => get_loop_start_offset, set_loop_start_offset;
+    value loop_end_offset: Option<NonZeroI32> => get_loop_end_offset, set_loop_end_offset;
+    value reverb_level: f32 => get_reverb_level, set_reverb_level;
+    string pfx_name: String => get_pfx_name, set_pfx_name;
+});
 impl Instance for SoundEffect {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -26,3 +51,16 @@ impl Instance for SoundEffect {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(SoundEffect {
+    instance_symbol,
+    file,
+    pitch_off,
+    pitch_var,
+    vol,
+    loop_,
+    loop_start_offset,
+    loop_end_offset,
+    reverb_level,
+    pfx_name,
+});
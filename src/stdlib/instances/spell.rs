@@ -1,5 +1,7 @@
 use super::Instance;
+use crate::persist_struct;
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Spell {
     instance_symbol: usize,
     time_per_mana: f32,
@@ -22,6 +24,22 @@ impl Spell {
     }
 }
 
+accessors!(Spell {
+    value time_per_mana: f32 => get_time_per_mana, set_time_per_mana;
+    value damage_per_level: i32 => get_damage_per_level, set_damage_per_level;
+    value damage_type: i32 => get_damage_type, set_damage_type;
+    value spell_type: i32 => get_spell_type, set_spell_type;
+    value can_turn_during_invest: i32 => get_can_turn_during_invest, set_can_turn_during_invest;
+    value can_change_target_during_invest: i32 =>
+        get_can_change_target_during_invest, set_can_change_target_during_invest;
+    value is_multi_effect: i32 => get_is_multi_effect, set_is_multi_effect;
+    value target_collect_algo: i32 => get_target_collect_algo, set_target_collect_algo;
+    value target_collect_type: i32 => get_target_collect_type, set_target_collect_type;
+    value target_collect_range: i32 => get_target_collect_range, set_target_collect_range;
+    value target_collect_azi: i32 => get_target_collect_azi, set_target_collect_azi;
+    value target_collect_elev: i32 => get_target_collect_elev, set_target_collect_elev;
+});
+
 impl Instance for Spell {
     fn get_instance_symbol(&self) -> usize {
         self.instance_symbol
@@ -30,3 +48,19 @@ impl Instance for Spell {
         self.instance_symbol = instance_symbol;
     }
 }
+
+persist_struct!(Spell {
+    instance_symbol,
+    time_per_mana,
+    damage_per_level,
+    damage_type,
+    spell_type,
+    can_turn_during_invest,
+    can_change_target_during_invest,
+    is_multi_effect,
+    target_collect_algo,
+    target_collect_type,
+    target_collect_range,
+    target_collect_azi,
+    target_collect_elev,
+});
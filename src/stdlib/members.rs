@@ -0,0 +1,258 @@
+use crate::vm::file::Kind;
+use enumflags2::{BitFlag, BitFlags};
+use std::convert::TryFrom;
+use std::num::{NonZeroI32, NonZeroU32};
+
+pub use daedalus_derive::DaedalusClass;
+
+/// Value of a class member as seen by the scripts
+#[derive(Clone, Debug, PartialEq)]
+pub enum MemberValue {
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+impl MemberValue {
+    /// Floats are returned as their bits, like on the data stack
+    pub fn as_int(&self) -> i32 {
+        match self {
+            MemberValue::Int(value) => *value,
+            MemberValue::Float(value) => value.to_bits() as i32,
+            MemberValue::String(_) => 0,
+        }
+    }
+    pub fn as_float(&self) -> f32 {
+        match self {
+            MemberValue::Int(value) => f32::from_bits(*value as u32),
+            MemberValue::Float(value) => *value,
+            MemberValue::String(_) => 0.0,
+        }
+    }
+    pub fn as_string(&self) -> String {
+        match self {
+            MemberValue::String(value) => value.clone(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Field of a native struct which is bound to a class member
+pub trait MemberField {
+    /// Kinds of class members the field can hold
+    const KINDS: &'static [Kind];
+    /// Number of elements, `None` if the field grows with the script array
+    const ARRAY_SIZE: Option<u32> = Some(1);
+
+    fn get_value(&self, array_index: usize) -> Option<MemberValue>;
+    /// Returns false if the value does not fit the field
+    fn set_value(&mut self, array_index: usize, value: MemberValue) -> bool;
+}
+
+impl MemberField for i32 {
+    const KINDS: &'static [Kind] = &[Kind::Int];
+
+    fn get_value(&self, array_index: usize) -> Option<MemberValue> {
+        match array_index {
+            0 => Some(MemberValue::Int(*self)),
+            _ => None,
+        }
+    }
+    fn set_value(&mut self, array_index: usize, value: MemberValue) -> bool {
+        match (array_index, value) {
+            (0, MemberValue::Int(value)) => *self = value,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Function and instance references
+impl MemberField for u32 {
+    const KINDS: &'static [Kind] = &[Kind::Int, Kind::Func, Kind::Instance];
+
+    fn get_value(&self, array_index: usize) -> Option<MemberValue> {
+        (*self as i32).get_value(array_index)
+    }
+    fn set_value(&mut self, array_index: usize, value: MemberValue) -> bool {
+        match (array_index, value) {
+            (0, MemberValue::Int(value)) => *self = value as u32,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl MemberField for f32 {
+    const KINDS: &'static [Kind] = &[Kind::Float];
+
+    fn get_value(&self, array_index: usize) -> Option<MemberValue> {
+        match array_index {
+            0 => Some(MemberValue::Float(*self)),
+            _ => None,
+        }
+    }
+    fn set_value(&mut self, array_index: usize, value: MemberValue) -> bool {
+        match (array_index, value) {
+            (0, MemberValue::Float(value)) => *self = value,
+            // floats are assigned as raw bits by the scripts
+            (0, MemberValue::Int(value)) => *self = f32::from_bits(value as u32),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl MemberField for String {
+    const KINDS: &'static [Kind] = &[Kind::CharString];
+
+    fn get_value(&self, array_index: usize) -> Option<MemberValue> {
+        match array_index {
+            0 => Some(MemberValue::String(self.clone())),
+            _ => None,
+        }
+    }
+    fn set_value(&mut self, array_index: usize, value: MemberValue) -> bool {
+        match (array_index, value) {
+            (0, MemberValue::String(value)) => *self = value,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl MemberField for Option<NonZeroI32> {
+    const KINDS: &'static [Kind] = &[Kind::Int];
+
+    fn get_value(&self, array_index: usize) -> Option<MemberValue> {
+        self.map_or(0, |value| value.get()).get_value(array_index)
+    }
+    fn set_value(&mut self, array_index: usize, value: MemberValue) -> bool {
+        match (array_index, value) {
+            (0, MemberValue::Int(value)) => *self = NonZeroI32::new(value),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl MemberField for Option<NonZeroU32> {
+    const KINDS: &'static [Kind] = &[Kind::Int, Kind::Func, Kind::Instance];
+
+    fn get_value(&self, array_index: usize) -> Option<MemberValue> {
+        self.map_or(0, |value| value.get()).get_value(array_index)
+    }
+    fn set_value(&mut self, array_index: usize, value: MemberValue) -> bool {
+        match (array_index, value) {
+            (0, MemberValue::Int(value)) => *self = NonZeroU32::new(value as u32),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl<T> MemberField for BitFlags<T>
+where
+    T: BitFlag,
+    T::Numeric: TryFrom<u32> + Into<u64>,
+{
+    const KINDS: &'static [Kind] = &[Kind::Int];
+
+    fn get_value(&self, array_index: usize) -> Option<MemberValue> {
+        let bits: u64 = self.bits().into();
+        (bits as i32).get_value(array_index)
+    }
+    fn set_value(&mut self, array_index: usize, value: MemberValue) -> bool {
+        match (array_index, value) {
+            // bits without a variant are rejected instead of dropped
+            (0, MemberValue::Int(value)) => match T::Numeric::try_from(value as u32)
+                .ok()
+                .and_then(|bits| BitFlags::from_bits(bits).ok())
+            {
+                Some(flags) => *self = flags,
+                None => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl<T: MemberField, const N: usize> MemberField for [T; N] {
+    const KINDS: &'static [Kind] = T::KINDS;
+    const ARRAY_SIZE: Option<u32> = Some(N as u32);
+
+    fn get_value(&self, array_index: usize) -> Option<MemberValue> {
+        self.get(array_index)?.get_value(0)
+    }
+    fn set_value(&mut self, array_index: usize, value: MemberValue) -> bool {
+        match self.get_mut(array_index) {
+            Some(field) => field.set_value(0, value),
+            None => false,
+        }
+    }
+}
+
+/// Script arrays without a fixed size on the rust side, grown on assignment.
+/// The VM only writes indices within the array size of the member in the DAT.
+impl<T: MemberField + Default> MemberField for Vec<T> {
+    const KINDS: &'static [Kind] = T::KINDS;
+    const ARRAY_SIZE: Option<u32> = None;
+
+    fn get_value(&self, array_index: usize) -> Option<MemberValue> {
+        match self.get(array_index) {
+            Some(field) => field.get_value(0),
+            None => T::default().get_value(0),
+        }
+    }
+    fn set_value(&mut self, array_index: usize, value: MemberValue) -> bool {
+        if array_index >= self.len() {
+            self.resize_with(array_index + 1, Default::default);
+        }
+        self[array_index].set_value(0, value)
+    }
+}
+
+/// Accessor of one class member on the native struct `T`
+pub struct Member<T> {
+    pub name: &'static str,
+    pub get: fn(&T, usize) -> Option<MemberValue>,
+    pub set: fn(&mut T, usize, MemberValue) -> bool,
+}
+
+/// Shape of a bound member, checked against the class in the DAT
+#[derive(Clone, Debug)]
+pub struct MemberLayout {
+    pub name: &'static str,
+    pub kinds: &'static [Kind],
+    pub array_size: Option<u32>,
+}
+
+impl MemberLayout {
+    /// Layout of the field returned by `field`, `array_size` overrides the size of the field type
+    pub fn of<S, T: MemberField>(
+        name: &'static str,
+        array_size: Option<u32>,
+        _field: fn(&S) -> &T,
+    ) -> MemberLayout {
+        MemberLayout {
+            name,
+            kinds: T::KINDS,
+            array_size: array_size.or(T::ARRAY_SIZE),
+        }
+    }
+}
+
+/// Native struct backing a daedalus class, usually implemented with `#[derive(DaedalusClass)]`
+pub trait ClassMembers: Sized + 'static {
+    /// Name of the daedalus class, e.g. `C_NPC`
+    const CLASS_NAME: &'static str;
+    const MEMBERS: &'static [Member<Self>];
+
+    fn get_layout() -> Vec<MemberLayout>;
+
+    /// Position of the member in `MEMBERS`
+    fn get_member_slot(name: &str) -> Option<usize> {
+        Self::MEMBERS.iter().position(|member| member.name == name)
+    }
+}
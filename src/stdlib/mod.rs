@@ -1,6 +1,10 @@
+use crate::snapshot::{Persist, SnapshotReader, SnapshotWriter};
+
 pub mod instances;
+pub mod members;
 pub mod prelude;
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum GameType {
     Gothic1,
     Gothic2,
@@ -21,7 +25,8 @@ pub enum SubTransitionType {
     Measure,
 }
 
-#[derive(Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InstanceClass {
     Npc,
     Mission,
@@ -34,4 +39,55 @@ pub enum InstanceClass {
     Sfx,
     Pfx,
     MusicTheme,
+    /// Any class without a native struct, see `DynamicInstance`
+    Dynamic,
+}
+
+impl InstanceClass {
+    pub const ALL: [InstanceClass; 12] = [
+        InstanceClass::Npc,
+        InstanceClass::Mission,
+        InstanceClass::Info,
+        InstanceClass::Item,
+        InstanceClass::ItemReact,
+        InstanceClass::Focus,
+        InstanceClass::Menu,
+        InstanceClass::MenuItem,
+        InstanceClass::Sfx,
+        InstanceClass::Pfx,
+        InstanceClass::MusicTheme,
+        InstanceClass::Dynamic,
+    ];
+
+    /// Instance class backing the daedalus class of the given name
+    pub fn from_class_name(class_name: &str) -> Option<InstanceClass> {
+        let instance_class = match class_name.to_uppercase().as_str() {
+            "C_NPC" => InstanceClass::Npc,
+            "C_MISSION" => InstanceClass::Mission,
+            "C_INFO" => InstanceClass::Info,
+            "C_ITEM" => InstanceClass::Item,
+            "C_ITEMREACT" => InstanceClass::ItemReact,
+            "C_FOCUS" => InstanceClass::Focus,
+            "C_MENU" => InstanceClass::Menu,
+            "C_MENU_ITEM" => InstanceClass::MenuItem,
+            "C_SFX" => InstanceClass::Sfx,
+            "C_PARTICLEFX" => InstanceClass::Pfx,
+            "C_MUSICTHEME" => InstanceClass::MusicTheme,
+            _ => return None,
+        };
+        Some(instance_class)
+    }
+}
+
+impl Persist for InstanceClass {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(*self as u8));
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        let value = reader.read::<u8>()?;
+        match InstanceClass::ALL.get(value as usize) {
+            Some(instance_class) => Ok(*instance_class),
+            None => Err(format!("Unknown instance class {}", value)),
+        }
+    }
 }
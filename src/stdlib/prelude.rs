@@ -1,3 +1,4 @@
+pub use super::instances::dynamic::DynamicInstance;
 pub use super::instances::focus::Focus;
 pub use super::instances::info::Info;
 pub use super::instances::item::Item;
@@ -0,0 +1,153 @@
+use super::file::symbol::Data;
+use super::file::{Flag, Kind};
+use super::VirtualMachine;
+
+impl VirtualMachine {
+    /// Index of the named symbol after checking its kind and the array index
+    fn resolve_symbol(&self, name: &str, array_index: usize, kind: Kind) -> Result<usize, String> {
+        let sym_table = self.file.get_sym_table();
+        let index = match sym_table.get_symbol_index_by_name(name) {
+            Some(index) => index,
+            None => return Err(format!("Symbol {} not found", name)),
+        };
+        let properties = &sym_table.get_symbol_by_index(index)?.properties;
+        if properties.get_kind() != kind {
+            return Err(format!(
+                "Symbol {} is of kind {:?}, expected {:?}",
+                name,
+                properties.get_kind(),
+                kind
+            ));
+        }
+        let count = properties.get_count().max(1) as usize;
+        if array_index >= count {
+            return Err(format!(
+                "Index {} out of bound for symbol {} with {} elements",
+                array_index, name, count
+            ));
+        }
+        Ok(index)
+    }
+    fn resolve_mut_symbol(
+        &self,
+        name: &str,
+        array_index: usize,
+        kind: Kind,
+    ) -> Result<usize, String> {
+        let index = self.resolve_symbol(name, array_index, kind)?;
+        let symbol = self.file.get_sym_table().get_symbol_by_index(index)?;
+        match symbol.properties.has_flag(Flag::Const) {
+            true => Err(format!("Symbol {} is constant", name)),
+            false => Ok(index),
+        }
+    }
+    fn get_value_data(&self, name: &str, index: usize) -> Result<&Data, String> {
+        self.symbol_values
+            .get_data(self.file.get_sym_table(), index)
+            .ok_or_else(|| format!("Symbol {} holds no value", name))
+    }
+
+    pub fn get_int(&self, name: &str) -> Result<i32, String> {
+        self.get_int_at(name, 0)
+    }
+    pub fn get_int_at(&self, name: &str, array_index: usize) -> Result<i32, String> {
+        let index = self.resolve_symbol(name, array_index, Kind::Int)?;
+        match self.get_value_data(name, index)? {
+            Data::IntSequence(vec) => Ok(vec.get(array_index).copied().unwrap_or(0)),
+            _ => Err(format!("Symbol {} does not hold integers", name)),
+        }
+    }
+    pub fn set_int(&mut self, name: &str, value: i32) -> Result<(), String> {
+        self.set_int_at(name, 0, value)
+    }
+    pub fn set_int_at(&mut self, name: &str, array_index: usize, value: i32) -> Result<(), String> {
+        let index = self.resolve_mut_symbol(name, array_index, Kind::Int)?;
+        self.set_int_value(index, array_index, value);
+        Ok(())
+    }
+    pub fn get_float(&self, name: &str) -> Result<f32, String> {
+        self.get_float_at(name, 0)
+    }
+    pub fn get_float_at(&self, name: &str, array_index: usize) -> Result<f32, String> {
+        let index = self.resolve_symbol(name, array_index, Kind::Float)?;
+        match self.get_value_data(name, index)? {
+            Data::FloatSequence(vec) => Ok(vec.get(array_index).copied().unwrap_or(0.0)),
+            _ => Err(format!("Symbol {} does not hold floats", name)),
+        }
+    }
+    pub fn set_float(&mut self, name: &str, value: f32) -> Result<(), String> {
+        self.set_float_at(name, 0, value)
+    }
+    pub fn set_float_at(
+        &mut self,
+        name: &str,
+        array_index: usize,
+        value: f32,
+    ) -> Result<(), String> {
+        let index = self.resolve_mut_symbol(name, array_index, Kind::Float)?;
+        self.set_float_value(index, array_index, value);
+        Ok(())
+    }
+    pub fn get_string(&self, name: &str) -> Result<String, String> {
+        self.get_string_at(name, 0)
+    }
+    pub fn get_string_at(&self, name: &str, array_index: usize) -> Result<String, String> {
+        let index = self.resolve_symbol(name, array_index, Kind::CharString)?;
+        match self.get_value_data(name, index)? {
+            Data::StringSequence(vec) => Ok(vec.get(array_index).cloned().unwrap_or_default()),
+            _ => Err(format!("Symbol {} does not hold strings", name)),
+        }
+    }
+    pub fn set_string(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.set_string_at(name, 0, value)
+    }
+    pub fn set_string_at(
+        &mut self,
+        name: &str,
+        array_index: usize,
+        value: &str,
+    ) -> Result<(), String> {
+        let index = self.resolve_mut_symbol(name, array_index, Kind::CharString)?;
+        self.set_string_value(index, array_index, value);
+        Ok(())
+    }
+    /// Symbol index of the function, function variables resolve to the function they point to
+    pub fn get_func(&self, name: &str) -> Result<usize, String> {
+        let index = self.resolve_symbol(name, 0, Kind::Func)?;
+        let symbol = self.file.get_sym_table().get_symbol_by_index(index)?;
+        if symbol.properties.has_flag(Flag::Const) {
+            return Ok(index);
+        }
+        match self
+            .symbol_values
+            .get_data(self.file.get_sym_table(), index)
+        {
+            Some(Data::IntSequence(vec)) if vec.first().is_some_and(|func| *func > 0) => {
+                Ok(vec[0] as usize)
+            }
+            _ => Err(format!("Function variable {} is not set", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::file::test_file::{op, TestFile};
+    use crate::vm::file::Operator;
+    use enumflags2::BitFlags;
+
+    #[test]
+    fn function_variables_hold_symbol_indices() {
+        let mut test_file = TestFile::new();
+        let target = test_file.func("TARGET", BitFlags::empty(), &[], &[op(Operator::Ret)]);
+        let handler = test_file.func_var("HANDLER");
+        let mut virtual_machine = VirtualMachine::from_file(test_file.build());
+        assert_eq!(virtual_machine.get_func("TARGET"), Ok(target));
+        assert!(virtual_machine.get_func("HANDLER").is_err());
+        assert_eq!(virtual_machine.get_int_value(handler, 0), 0);
+
+        virtual_machine.set_int_value(handler, 0, target as i32);
+        assert_eq!(virtual_machine.get_func("HANDLER"), Ok(target));
+    }
+}
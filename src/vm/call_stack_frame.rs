@@ -1,4 +1,5 @@
 use super::VirtualMachine;
+use crate::snapshot::{Persist, SnapshotReader, SnapshotWriter};
 pub enum CallStackFrame {
     Address(usize),
     SymbolIndex(usize),
@@ -15,6 +16,27 @@ impl CallStackFrame {
         }
     }
 }
+impl Persist for CallStackFrame {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        match self {
+            CallStackFrame::Address(val) => {
+                writer.write(&0u8);
+                writer.write(val);
+            }
+            CallStackFrame::SymbolIndex(val) => {
+                writer.write(&1u8);
+                writer.write(val);
+            }
+        }
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(CallStackFrame::Address(reader.read()?)),
+            1 => Ok(CallStackFrame::SymbolIndex(reader.read()?)),
+            tag => Err(format!("Unknown call stack frame tag {}", tag)),
+        }
+    }
+}
 // struct CallStackFrame {
 //     address_type: AddressType,
 // }
@@ -25,3 +47,18 @@ impl CallStackFrame {
 //         call_stack
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_tag_fails() {
+        let mut writer = SnapshotWriter::new();
+        writer.write(&2u8);
+        writer.write(&5usize);
+        let bytes = writer.into_bytes();
+        let mut reader = SnapshotReader::new(&bytes);
+        assert!(reader.read::<CallStackFrame>().is_err());
+    }
+}
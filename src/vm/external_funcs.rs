@@ -1,5 +1,269 @@
 use super::VirtualMachine;
+use crate::game_state::{GameState, RoutineEntry, TopicSection, TopicStatus};
+use crate::stdlib::instances::info::SubChoice;
+use crate::stdlib::instances::Instance;
+use crate::stdlib::InstanceClass;
+use zen_memory::Handle;
 
-pub fn insert_item(virtual_machine: &VirtualMachine) {
-    let spawn_point = virtual_machine.pop_string();
+/// Wld_InsertNpc(var int npcInstance, var string spawnPoint)
+pub fn insert_npc(virtual_machine: &mut VirtualMachine) {
+    let spawn_point = virtual_machine.pop_string().unwrap_or_default();
+    let instance = virtual_machine.pop_int().unwrap_or(0) as usize;
+    if let Err(error) = GameState::insert_npc(virtual_machine, instance, &spawn_point) {
+        log::warn!("Wld_InsertNpc: {}", error);
+    }
+}
+
+/// Wld_InsertItem(var int itemInstance, var string spawnPoint)
+pub fn insert_item(virtual_machine: &mut VirtualMachine) {
+    let _spawn_point = virtual_machine.pop_string();
+    let instance = virtual_machine.pop_int().unwrap_or(0) as usize;
+    if let Err(error) = GameState::insert_item(virtual_machine, instance) {
+        log::warn!("Wld_InsertItem: {}", error);
+    }
+}
+
+/// Info_AddChoice(var C_INFO dialog, var string description, var func function)
+pub fn info_add_choice(virtual_machine: &mut VirtualMachine) {
+    let func_sym = virtual_machine.pop_int().unwrap_or(0) as u32;
+    let text = virtual_machine.pop_string().unwrap_or_default();
+    let info = match virtual_machine.pop_instance() {
+        Some(instance) if instance.class == InstanceClass::Info => instance.handle,
+        _ => return log::warn!("Info_AddChoice: Instance is not an info"),
+    };
+    if let Some(info) = virtual_machine.get_mut_game_state().get_mut_info(&info) {
+        let mut choice = SubChoice::new();
+        choice.set_text(&text);
+        choice.set_func_sym(func_sym);
+        info.add_choice(choice);
+    }
+}
+
+/// Info_ClearChoices(var C_INFO dialog)
+pub fn info_clear_choices(virtual_machine: &mut VirtualMachine) {
+    let info = match virtual_machine.pop_instance() {
+        Some(instance) if instance.class == InstanceClass::Info => instance.handle,
+        _ => return log::warn!("Info_ClearChoices: Instance is not an info"),
+    };
+    if let Some(info) = virtual_machine.get_mut_game_state().get_mut_info(&info) {
+        info.clear_choices();
+    }
+}
+
+/// Npc_KnowsInfo(var C_NPC self, var int infoInstance)
+pub fn npc_knows_info(virtual_machine: &mut VirtualMachine) {
+    let info = virtual_machine.pop_int().unwrap_or(0) as usize;
+    let knows = match virtual_machine.pop_instance() {
+        Some(instance) if instance.class == InstanceClass::Npc => virtual_machine
+            .get_game_state()
+            .knows_info(&instance.handle, info),
+        _ => false,
+    };
+    virtual_machine.push_int(knows as i32);
+}
+
+/// Log_CreateTopic(var string name, var int section)
+pub fn log_create_topic(virtual_machine: &mut VirtualMachine) {
+    let section = virtual_machine.pop_int().unwrap_or(0);
+    let name = virtual_machine.pop_string().unwrap_or_default();
+    match TopicSection::from_int(section) {
+        Some(section) => virtual_machine
+            .get_mut_game_state()
+            .create_log_topic(&name, section),
+        None => log::warn!("Log_CreateTopic: Invalid section {} for {}", section, name),
+    }
+}
+
+/// Log_SetTopicStatus(var string name, var int status)
+pub fn log_set_topic_status(virtual_machine: &mut VirtualMachine) {
+    let status = virtual_machine.pop_int().unwrap_or(0);
+    let name = virtual_machine.pop_string().unwrap_or_default();
+    let status = match TopicStatus::from_int(status) {
+        Some(status) => status,
+        None => return log::warn!("Log_SetTopicStatus: Invalid status {} for {}", status, name),
+    };
+    if !virtual_machine
+        .get_mut_game_state()
+        .set_log_topic_status(&name, status)
+    {
+        log::warn!("Log_SetTopicStatus: Topic {} not found", name);
+    }
+}
+
+/// Log_AddEntry(var string topic, var string entry)
+pub fn log_add_entry(virtual_machine: &mut VirtualMachine) {
+    let entry = virtual_machine.pop_string().unwrap_or_default();
+    let name = virtual_machine.pop_string().unwrap_or_default();
+    if !virtual_machine
+        .get_mut_game_state()
+        .add_log_entry(&name, &entry)
+    {
+        log::warn!("Log_AddEntry: Topic {} not found", name);
+    }
+}
+
+fn pop_npc(virtual_machine: &mut VirtualMachine) -> Option<Handle> {
+    match virtual_machine.pop_instance() {
+        Some(instance) if instance.class == InstanceClass::Npc => Some(instance.handle),
+        _ => None,
+    }
+}
+
+/// Npc_HasItems(var C_NPC npc, var int itemInstance)
+pub fn npc_has_items(virtual_machine: &mut VirtualMachine) {
+    let item = virtual_machine.pop_int().unwrap_or(0) as usize;
+    let count = match pop_npc(virtual_machine) {
+        Some(npc) => virtual_machine
+            .get_game_state()
+            .get_inv_item_count(&npc, item),
+        None => 0,
+    };
+    virtual_machine.push_int(count as i32);
+}
+
+/// Npc_RemoveInvItems(var C_NPC npc, var int itemInstance, var int amount)
+pub fn npc_remove_inv_items(virtual_machine: &mut VirtualMachine) {
+    let amount = virtual_machine.pop_int().unwrap_or(0).max(0) as u32;
+    let item = virtual_machine.pop_int().unwrap_or(0) as usize;
+    let removed = match pop_npc(virtual_machine) {
+        Some(npc) => GameState::remove_inv_items(virtual_machine, npc, item, amount),
+        None => false,
+    };
+    virtual_machine.push_int(removed as i32);
+}
+
+/// CreateInvItems(var C_NPC npc, var int itemInstance, var int amount)
+pub fn create_inv_items(virtual_machine: &mut VirtualMachine) {
+    let amount = virtual_machine.pop_int().unwrap_or(0).max(0) as u32;
+    let item = virtual_machine.pop_int().unwrap_or(0) as usize;
+    let npc = match pop_npc(virtual_machine) {
+        Some(npc) => npc,
+        None => return log::warn!("CreateInvItems: Instance is not a npc"),
+    };
+    if let Err(error) = GameState::create_inv_items(virtual_machine, npc, item, amount) {
+        log::warn!("CreateInvItems: {}", error);
+    }
+}
+
+/// EquipItem(var C_NPC npc, var int itemInstance), creates the item if the npc does not carry it
+pub fn equip_item(virtual_machine: &mut VirtualMachine) {
+    let item_symbol = virtual_machine.pop_int().unwrap_or(0) as usize;
+    let npc = match pop_npc(virtual_machine) {
+        Some(npc) => npc,
+        None => return log::warn!("EquipItem: Instance is not a npc"),
+    };
+    let game_state = virtual_machine.get_game_state();
+    let carried = game_state.get_inv_of(&npc).and_then(|inventory| {
+        inventory.get_items().iter().copied().find(|item| {
+            !inventory.is_equipped(item)
+                && game_state
+                    .get_item(item)
+                    .is_some_and(|item| item.get_instance_symbol() == item_symbol)
+        })
+    });
+    let result = match carried {
+        Some(item) => Ok(item),
+        None => GameState::create_inv_items(virtual_machine, npc, item_symbol, 1)
+            .and_then(|item| item.ok_or_else(|| "No item was created".to_owned())),
+    }
+    .and_then(|item| GameState::equip_item(virtual_machine, npc, item));
+    if let Err(error) = result {
+        log::warn!("EquipItem: {}", error);
+    }
+}
+
+/// Wld_GetDay()
+pub fn wld_get_day(virtual_machine: &mut VirtualMachine) {
+    let day = virtual_machine.get_mut_game_state().get_current_day();
+    virtual_machine.push_int(day);
+}
+
+/// Wld_SetTime(var int hour, var int min)
+pub fn wld_set_time(virtual_machine: &mut VirtualMachine) {
+    let minute = virtual_machine.pop_int().unwrap_or(0);
+    let hour = virtual_machine.pop_int().unwrap_or(0);
+    virtual_machine.get_mut_game_state().set_time(hour, minute);
+}
+
+/// Wld_IsTime(var int hour1, var int min1, var int hour2, var int min2)
+pub fn wld_is_time(virtual_machine: &mut VirtualMachine) {
+    let end_minute = virtual_machine.pop_int().unwrap_or(0);
+    let end_hour = virtual_machine.pop_int().unwrap_or(0);
+    let start_minute = virtual_machine.pop_int().unwrap_or(0);
+    let start_hour = virtual_machine.pop_int().unwrap_or(0);
+    let is_time = virtual_machine.get_game_state().get_world_clock().is_time(
+        start_hour,
+        start_minute,
+        end_hour,
+        end_minute,
+    );
+    virtual_machine.push_int(is_time as i32);
+}
+
+/// Npc_GetStateTime(var C_NPC self)
+pub fn npc_get_state_time(virtual_machine: &mut VirtualMachine) {
+    let seconds = match pop_npc(virtual_machine) {
+        Some(npc) => virtual_machine.get_game_state().get_npc_state_time(&npc),
+        None => 0.0,
+    };
+    virtual_machine.push_int(seconds as i32);
+}
+
+/// Npc_SetStateTime(var C_NPC self, var int seconds)
+pub fn npc_set_state_time(virtual_machine: &mut VirtualMachine) {
+    let seconds = virtual_machine.pop_int().unwrap_or(0);
+    if let Some(npc) = pop_npc(virtual_machine) {
+        virtual_machine
+            .get_mut_game_state()
+            .set_npc_state_time(&npc, seconds as f32);
+    }
+}
+
+/// TA(var C_NPC self, var int start_h, var int stop_h, var func state, var string waypoint)
+pub fn ta(virtual_machine: &mut VirtualMachine) {
+    let waypoint = virtual_machine.pop_string().unwrap_or_default();
+    let state = virtual_machine.pop_int().unwrap_or(0) as usize;
+    let end_hour = virtual_machine.pop_int().unwrap_or(0);
+    let start_hour = virtual_machine.pop_int().unwrap_or(0);
+    if let Some(npc) = pop_npc(virtual_machine) {
+        let entry = RoutineEntry::new(start_hour, 0, end_hour, 0, state, &waypoint);
+        virtual_machine
+            .get_mut_game_state()
+            .add_routine_entry(&npc, entry);
+    }
+}
+
+/// TA_Min(var C_NPC self, var int start_h, var int start_m, var int stop_h, var int stop_m, var func state, var string waypoint)
+pub fn ta_min(virtual_machine: &mut VirtualMachine) {
+    let waypoint = virtual_machine.pop_string().unwrap_or_default();
+    let state = virtual_machine.pop_int().unwrap_or(0) as usize;
+    let end_minute = virtual_machine.pop_int().unwrap_or(0);
+    let end_hour = virtual_machine.pop_int().unwrap_or(0);
+    let start_minute = virtual_machine.pop_int().unwrap_or(0);
+    let start_hour = virtual_machine.pop_int().unwrap_or(0);
+    if let Some(npc) = pop_npc(virtual_machine) {
+        let entry = RoutineEntry::new(
+            start_hour,
+            start_minute,
+            end_hour,
+            end_minute,
+            state,
+            &waypoint,
+        );
+        virtual_machine
+            .get_mut_game_state()
+            .add_routine_entry(&npc, entry);
+    }
+}
+
+/// Npc_ExchangeRoutine(var C_NPC self, var string routineName)
+pub fn npc_exchange_routine(virtual_machine: &mut VirtualMachine) {
+    let name = virtual_machine.pop_string().unwrap_or_default();
+    let npc = match pop_npc(virtual_machine) {
+        Some(npc) => npc,
+        None => return log::warn!("Npc_ExchangeRoutine: Instance is not a npc"),
+    };
+    if let Err(error) = GameState::exchange_routine(virtual_machine, npc, &name) {
+        log::warn!("Npc_ExchangeRoutine: {}", error);
+    }
 }
@@ -2,13 +2,14 @@ use super::stack::{Stack, StackOpCode};
 use super::sym_table::SymTable;
 use super::symbol::{Data, Properties, SymbolBuilder};
 use super::{Flag, Kind, Operator};
+use std::convert::TryFrom;
 use std::mem;
 use zen_parser::ZenParser;
+
+/// Parsed dat file, never changed after loading
+/// so that it can be shared between virtual machines
 pub struct File {
-    parser: ZenParser,
-    pub sym_table: SymTable,
-    sort_table: Vec<u32>,
-    // offset, size
+    sym_table: SymTable,
     stack: Stack,
 }
 
@@ -21,22 +22,11 @@ impl File {
         let count = parser.read_binary::<u32>().unwrap();
         let mut sym_table = SymTable::with_capacity(count as usize);
         let sort_table = parser.read_binary_as_vec::<u32>(count as usize).unwrap();
+        sym_table.write_sort_table(&sort_table);
         for index in 0..count {
             let name = match parser.read_binary::<u32>() {
-                Ok(_) => {
-                    let mut inner = String::new();
-                    while let Ok(ch) = parser.read_binary::<char>() {
-                        if ch == 0x0a as char {
-                            break;
-                        }
-                        if ch != 0xff as char {
-                            // FIXME: if Bedinung eigentlich nicht notwendig
-                            inner.push(ch);
-                        }
-                    }
-                    inner
-                }
-                Err(_) => "".to_owned(),
+                Ok(named) if named != 0 => read_line(&parser),
+                _ => "".to_owned(),
             };
             let mut symbol_builder = SymbolBuilder::new(name.as_str());
 
@@ -52,39 +42,25 @@ impl File {
             );
 
             if properties.is_not_flag(Flag::ClassVar) {
+                let count = properties.get_count() as usize;
                 match properties.get_kind() {
                     Kind::Float => {
-                        symbol_builder.with_data(
-                            parser
-                                .read_binary_as_vec::<Data>(
-                                    mem::size_of::<f32>() * properties.get_count() as usize,
-                                )
-                                .unwrap(),
-                        );
+                        symbol_builder.with_data(Data::FloatSequence(
+                            parser.read_binary_as_vec::<f32>(count).unwrap(),
+                        ));
                     }
                     Kind::Int => {
-                        symbol_builder.with_data(
-                            parser
-                                .read_binary_as_vec::<Data>(
-                                    mem::size_of::<u32>() * properties.get_count() as usize,
-                                )
-                                .unwrap(),
-                        );
+                        symbol_builder.with_data(Data::IntSequence(
+                            parser.read_binary_as_vec::<i32>(count).unwrap(),
+                        ));
                     }
                     Kind::CharString => {
-                        let mut inner = vec![];
-                        for _ in 0..properties.get_count() {
-                            while let Ok(ch) = parser.read_binary::<char>() {
-                                if ch == 0x0a as char {
-                                    break;
-                                }
-                                if ch != 0xff as char {
-                                    inner.push(Data::Char(ch));
-                                }
-                            }
+                        let mut inner = Vec::with_capacity(count);
+                        for _ in 0..count {
                             // TODO Replace \\n with \n
+                            inner.push(read_line(&parser));
                         }
-                        symbol_builder.with_data(inner);
+                        symbol_builder.with_data(Data::StringSequence(inner));
                     }
                     Kind::Class => {
                         symbol_builder.with_class_offset(parser.read_binary::<i32>().unwrap());
@@ -99,99 +75,93 @@ impl File {
                 .with_properties(properties)
                 .with_parent(parser.read_binary::<u32>().unwrap());
 
-            sym_table.insert(index as usize, symbol_builder.build().unwrap());
+            sym_table.insert(index as usize, symbol_builder.build()?);
         }
         let size = parser.read_binary::<i32>().unwrap() as usize;
         let offset = parser.get_seek();
-
-        let file = File {
-            parser,
-            sym_table,
-            sort_table,
-            stack: Stack { offset, size },
-        };
+        let mut stack = Stack::new(offset, size);
 
         println!("Reading Stack...");
 
-        while file.parser.get_seek() < file.parser.get_file_size() {
-            file.get_stack_op_code(file.parser.get_seek());
+        while parser.get_seek() < parser.get_file_size() {
+            let address = parser.get_seek() - offset;
+            stack.insert(address, read_stack_op_code(&parser)?);
         }
 
-        Ok(file)
+        Ok(File::new(sym_table, stack))
     }
-    pub fn get_stack(&self) -> Stack {
-        self.stack
+    /// Creates a file from an already decoded symbol table and code section
+    pub fn new(mut sym_table: SymTable, stack: Stack) -> File {
+        sym_table.index_instances();
+        File { sym_table, stack }
     }
-    pub fn get_stack_op_code(&self, proc_counter: usize) -> StackOpCode {
-        self.parser.set_seek(proc_counter);
-        let operator = self.parser.read_binary::<Operator>().unwrap();
-        //let operator: Operator = unsafe { mem::transmute(operator_num) };
-        let stack_op_code = match operator {
-            Operator::Call => {
-                let mut op_code =
-                    StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
-                op_code.with_address(self.parser.read_binary::<i32>().unwrap());
-                op_code
-            }
-            Operator::CallExternal => {
-                let mut op_code =
-                    StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
-                op_code.with_symbol(self.parser.read_binary::<i32>().unwrap());
-                op_code
-            }
-            Operator::PushInt => {
-                let mut op_code =
-                    StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
-                op_code.with_value(self.parser.read_binary::<i32>().unwrap());
-                op_code
-            }
-            Operator::PushVar => {
-                let mut op_code =
-                    StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
-                op_code.with_symbol(self.parser.read_binary::<i32>().unwrap());
-                op_code
-            }
-            Operator::PushInstance => {
-                let mut op_code =
-                    StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
-                op_code.with_symbol(self.parser.read_binary::<i32>().unwrap());
-                op_code
-            }
-            Operator::Jump => {
-                let mut op_code = StackOpCode::new(operator, mem::size_of::<i32>());
-                op_code.with_address(self.parser.read_binary::<i32>().unwrap());
-                op_code
-            }
-            Operator::JumpIf => {
-                let mut op_code =
-                    StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
-                op_code.with_address(self.parser.read_binary::<i32>().unwrap());
-                op_code
-            }
-            Operator::SetInstance => {
-                let mut op_code =
-                    StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
-                op_code.with_symbol(self.parser.read_binary::<i32>().unwrap());
-                op_code
-            }
-            Operator::PushArrayVar => {
-                let mut op_code =
-                    StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
-                op_code
-                    .with_symbol(self.parser.read_binary::<i32>().unwrap())
-                    .with_index(self.parser.read_binary::<u8>().unwrap());
-                op_code
-            }
-            _ => {
-                let op_code = StackOpCode::new(operator, mem::size_of::<u8>());
-                op_code
-            }
-        };
-        stack_op_code
+    pub fn get_sym_table(&self) -> &SymTable {
+        &self.sym_table
     }
-    // pub fn add_symbol(&mut self) -> usize {
-    //     let builder = SymbolBuilder::new("").with_properties(Default::default());
-    //     let symbol = builder.build().unwrap();
-    //     self.sym_table.push(symbol)
-    // }
+    pub fn get_stack(&self) -> &Stack {
+        &self.stack
+    }
+    pub fn get_stack_op_code(&self, program_counter: usize) -> Result<StackOpCode, String> {
+        match self.stack.get_op_code(program_counter) {
+            Some(op_code) => Ok(*op_code),
+            None => Err(format!("No operator at address {}", program_counter)),
+        }
+    }
+}
+
+fn read_line(parser: &ZenParser) -> String {
+    let mut inner = String::new();
+    while let Ok(ch) = parser.read_binary::<u8>() {
+        if ch == 0x0a {
+            break;
+        }
+        if ch != 0xff {
+            // FIXME: if Bedinung eigentlich nicht notwendig
+            inner.push(ch as char);
+        }
+    }
+    inner
+}
+
+fn read_stack_op_code(parser: &ZenParser) -> Result<StackOpCode, String> {
+    let value = parser.read_binary::<u8>().unwrap();
+    let operator = match Operator::try_from(value) {
+        Ok(operator) => operator,
+        Err(_) => return Err(format!("Unknown operator {}", value)),
+    };
+    let stack_op_code = match operator {
+        Operator::Call | Operator::Jump | Operator::JumpIf => {
+            let mut op_code =
+                StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
+            op_code.with_address(parser.read_binary::<i32>().unwrap());
+            op_code
+        }
+        Operator::CallExternal
+        | Operator::PushVar
+        | Operator::PushInstance
+        | Operator::SetInstance => {
+            let mut op_code =
+                StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
+            op_code.with_symbol(parser.read_binary::<i32>().unwrap());
+            op_code
+        }
+        Operator::PushInt => {
+            let mut op_code =
+                StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
+            op_code.with_value(parser.read_binary::<i32>().unwrap());
+            op_code
+        }
+        Operator::PushArrayVar => {
+            let mut op_code = StackOpCode::new(
+                operator,
+                mem::size_of::<u8>() + mem::size_of::<i32>() + mem::size_of::<u8>(),
+            );
+            op_code
+                .with_symbol(parser.read_binary::<i32>().unwrap())
+                .with_index(parser.read_binary::<u8>().unwrap());
+            op_code
+        }
+        _ => StackOpCode::new(operator, mem::size_of::<u8>()),
+    };
+    Ok(stack_op_code)
 }
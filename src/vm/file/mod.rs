@@ -1,12 +1,15 @@
-use enumflags2::BitFlags;
+use enumflags2::bitflags;
 use std::convert::TryFrom;
 
 pub mod file;
 pub mod stack;
 pub mod sym_table;
 pub mod symbol;
+#[cfg(test)]
+pub(crate) mod test_file;
 
 #[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Operator {
     Add = 0,             // a + b
     Subract = 1,         // a - b
@@ -148,8 +151,9 @@ impl TryFrom<u8> for Operator {
     }
 }
 
+#[bitflags]
 #[repr(u8)]
-#[derive(BitFlags, Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Flag {
     Const = 0b00001,
     Return = 0b00010,
@@ -158,7 +162,7 @@ pub enum Flag {
     Merged = 0b10000,
 }
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Kind {
     Void = 0,
     Float = 1,
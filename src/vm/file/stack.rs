@@ -1,6 +1,8 @@
 use super::Operator;
+use std::collections::HashMap;
 use std::num::{NonZeroI32, NonZeroU8};
 
+#[derive(Copy, Clone)]
 pub struct StackOpCode {
     operator: Operator,
     address: Option<NonZeroI32>,
@@ -42,9 +44,46 @@ impl StackOpCode {
     pub fn get_operator_size(&self) -> usize {
         self.operator_size
     }
+    pub fn get_address(&self) -> usize {
+        self.address.map_or(0, |address| address.get() as usize)
+    }
+    pub fn get_symbol(&self) -> usize {
+        self.symbol.map_or(0, |symbol| symbol.get() as usize)
+    }
+    pub fn get_value(&self) -> i32 {
+        self.value.map_or(0, |value| value.get())
+    }
+    pub fn get_index(&self) -> usize {
+        self.index.map_or(0, |index| index.get() as usize)
+    }
 }
-#[derive(Copy, Clone, Default)]
+
+/// Decoded code section of a dat file, op codes are indexed by their address
+#[derive(Default)]
 pub struct Stack {
     offset: usize,
     size: usize,
+    op_codes: HashMap<usize, StackOpCode>,
+}
+
+impl Stack {
+    pub fn new(offset: usize, size: usize) -> Stack {
+        Stack {
+            offset,
+            size,
+            op_codes: HashMap::new(),
+        }
+    }
+    pub fn insert(&mut self, address: usize, op_code: StackOpCode) {
+        self.op_codes.insert(address, op_code);
+    }
+    pub fn get_op_code(&self, address: usize) -> Option<&StackOpCode> {
+        self.op_codes.get(&address)
+    }
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
 }
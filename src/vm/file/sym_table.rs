@@ -1,8 +1,6 @@
 use super::symbol::Symbol;
 use super::{Flag, Kind};
-use crate::stdlib::instances::Instance;
 use std::collections::HashMap;
-use std::mem;
 
 #[derive(Default)]
 pub struct SymTable {
@@ -10,6 +8,8 @@ pub struct SymTable {
     symbols: Vec<Symbol>,
     pub symbols_by_name: HashMap<String, usize>,
     pub functions_by_address: HashMap<usize, usize>,
+    // instance symbols by the index of their class
+    instances_by_class: HashMap<usize, Vec<usize>>,
 }
 
 impl SymTable {
@@ -25,18 +25,25 @@ impl SymTable {
             symbols,
             symbols_by_name,
             functions_by_address: HashMap::new(),
+            instances_by_class: HashMap::new(),
         }
     }
     pub fn write_sort_table(&mut self, table: &[u32]) {
         self.sort_table = Vec::from(table);
     }
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
     pub fn get_symbol_by_name(&self, sym_name: &str) -> Result<&Symbol, String> {
         match self.symbols_by_name.get(sym_name) {
             Some(index) => Ok(self.symbols.get(*index).unwrap()),
             None => Err(format!("Symbol {} not found", sym_name)),
         }
     }
-    pub fn get_mut_symbol_by_name(&self, sym_name: &str) -> Result<&mut Symbol, String> {
+    pub fn get_mut_symbol_by_name(&mut self, sym_name: &str) -> Result<&mut Symbol, String> {
         match self.symbols_by_name.get(sym_name) {
             Some(index) => Ok(self.symbols.get_mut(*index).unwrap()),
             None => Err(format!("Symbol {} not found", sym_name)),
@@ -86,38 +93,45 @@ impl SymTable {
         self.symbols.push(symbol);
         index + 1
     }
-    pub fn iterate_symbols_of_class(&self, class_name: &str, callback: &dyn Fn(usize, &Symbol)) {
-        let base = self.get_symbol_index_by_name(class_name).unwrap();
-        self.symbols.iter().enumerate().for_each(|(index, symbol)| {
-            if symbol.properties.get_kind() as u8 != Kind::Instance as u8 {
-                return;
+    /// Class of an instance or prototype, instances are resolved through their prototype
+    pub fn get_base_class(&self, index: usize) -> Option<usize> {
+        let mut parent = self.symbols.get(index)?.get_parent()?.get() as usize;
+        let mut parent_symbol = self.symbols.get(parent)?;
+        if parent_symbol.properties.get_kind() == Kind::Prototype {
+            parent = parent_symbol.get_parent()?.get() as usize;
+            parent_symbol = self.symbols.get(parent)?;
+        }
+        match parent_symbol.properties.get_kind() {
+            Kind::Class => Some(parent),
+            _ => None,
+        }
+    }
+    /// Groups all instance definitions by their class, called once all symbols are loaded
+    pub fn index_instances(&mut self) {
+        let mut instances_by_class: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            // instance variables like self have no code
+            if symbol.properties.get_kind() != Kind::Instance || symbol.get_address().is_none() {
+                continue;
             }
-            let parent_address = match symbol.get_parent() {
-                Some(address) => address.get(),
-                None => return,
-            };
-            let parent = self.get_symbol_by_index(parent_address as usize).unwrap();
-
-            let parent_base = if parent.properties.get_kind() as u8 == Kind::Prototype as u8 {
-                match parent.get_parent() {
-                    Some(address) => address.get(),
-                    None => parent_address,
-                }
-            } else {
-                parent_address
-            };
-            if base == parent_base as usize {
-                callback(index, symbol);
+            if let Some(class) = self.get_base_class(index) {
+                instances_by_class.entry(class).or_default().push(index);
             }
-        });
+        }
+        self.instances_by_class = instances_by_class;
     }
-    pub fn register<I: Instance, M>(&self, sym_name: &str, instance: I, member: M) {
-        match self.get_mut_symbol_by_name(sym_name) {
-            Ok(symbol) => {
-                let offset = &member as *const u32 - &instance as *const u32;
-                symbol.set_class_member(offset, mem::size_of_val(member));
-            }
-            Err(_) => (),
+    pub fn get_instances_of_class(&self, class_name: &str) -> &[usize] {
+        self.get_symbol_index_by_name(class_name)
+            .and_then(|class| self.instances_by_class.get(&class))
+            .map_or(&[], |instances| instances.as_slice())
+    }
+    /// Class symbols which have instances
+    pub fn get_instantiated_classes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.instances_by_class.keys().copied()
+    }
+    pub fn iterate_symbols_of_class(&self, class_name: &str, callback: &dyn Fn(usize, &Symbol)) {
+        for index in self.get_instances_of_class(class_name) {
+            callback(*index, &self.symbols[*index]);
         }
     }
 }
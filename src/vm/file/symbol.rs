@@ -1,19 +1,17 @@
-use super::{Flag, Kind, Operator};
-use crate::stdlib::InstanceClass;
-use crate::vm::VirtualMachine;
+use super::{Flag, Kind};
+use crate::snapshot::{Persist, SnapshotReader, SnapshotWriter};
 use bitfield::{bitfield, BitRange};
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryInto;
 use std::num::{NonZeroI32, NonZeroU32};
-use zen_memory::Handle;
 
 bitfield! {
     #[derive(Default)]
     struct Element(u32);
-    u32, get_count, set_count: 12, 0;
-    Kind, get_kind, set_kind: 16, 12;
-    u8, get_flags, set_flags: 22, 16;
-    u32, get_space, set_space: 23, 22;
-    u32, get_reserved, set_reserved: 32, 23;
+    u32, get_count, set_count: 11, 0;
+    Kind, get_kind, set_kind: 15, 12;
+    u8, get_flags, set_flags: 21, 16;
+    u32, get_space, set_space: 22, 22;
+    u32, get_reserved, set_reserved: 31, 23;
 }
 impl BitRange<Kind> for Element {
     fn bit_range(&self, msb: usize, lsb: usize) -> Kind {
@@ -85,15 +83,17 @@ impl Properties {
         self.element.get_kind()
     }
 }
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Data {
-    IntSequence(Vec<u32>),
+    IntSequence(Vec<i32>),
     FloatSequence(Vec<f32>),
-    String(String),
+    StringSequence(Vec<String>),
 }
 
-impl TryInto<Vec<u32>> for Data {
+impl TryInto<Vec<i32>> for Data {
     type Error = ();
-    fn try_into(self) -> Result<Vec<u32>, Self::Error> {
+    fn try_into(self) -> Result<Vec<i32>, Self::Error> {
         match self {
             Data::IntSequence(val) => Ok(val),
             _ => Err(()),
@@ -111,16 +111,43 @@ impl TryInto<Vec<f32>> for Data {
     }
 }
 
-impl TryInto<String> for Data {
+impl TryInto<Vec<String>> for Data {
     type Error = ();
-    fn try_into(self) -> Result<String, Self::Error> {
+    fn try_into(self) -> Result<Vec<String>, Self::Error> {
         match self {
-            Data::String(val) => Ok(val),
+            Data::StringSequence(val) => Ok(val),
             _ => Err(()),
         }
     }
 }
 
+impl Persist for Data {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        match self {
+            Data::IntSequence(vec) => {
+                writer.write(&0u8);
+                writer.write(vec);
+            }
+            Data::FloatSequence(vec) => {
+                writer.write(&1u8);
+                writer.write(vec);
+            }
+            Data::StringSequence(vec) => {
+                writer.write(&2u8);
+                writer.write(vec);
+            }
+        }
+    }
+    fn read(reader: &mut SnapshotReader) -> Result<Self, String> {
+        match reader.read::<u8>()? {
+            0 => Ok(Data::IntSequence(reader.read()?)),
+            1 => Ok(Data::FloatSequence(reader.read()?)),
+            2 => Ok(Data::StringSequence(reader.read()?)),
+            value => Err(format!("Unknown data kind {}", value)),
+        }
+    }
+}
+
 pub struct SymbolBuilder {
     name: String,
     properties: Option<Properties>,
@@ -130,8 +157,6 @@ pub struct SymbolBuilder {
     // Valid for Classes that write directly to the engine
     // Store array size of the Class member var
     class_member_array_size: Option<NonZeroI32>,
-    instance_data_handle: Option<Handle>,
-    instance_data_class: Option<InstanceClass>,
     parent: Option<NonZeroU32>,
     address: Option<NonZeroU32>,
     data: Option<Data>,
@@ -144,8 +169,6 @@ impl SymbolBuilder {
             properties: None,
             class_member_offset: None,
             class_member_array_size: None,
-            instance_data_handle: None,
-            instance_data_class: None,
             parent: None,
             address: None,
             data: None,
@@ -175,11 +198,6 @@ impl SymbolBuilder {
         self.class_member_array_size = NonZeroI32::new(array_size);
         self
     }
-    pub fn with_instance_data(&mut self, handle: Handle, kind: InstanceClass) -> &mut Self {
-        self.instance_data_handle = Some(handle);
-        self.instance_data_class = Some(kind);
-        self
-    }
     pub fn with_parent(&mut self, parent: u32) -> &mut Self {
         self.parent = NonZeroU32::new(parent);
         self
@@ -206,17 +224,11 @@ impl SymbolBuilder {
             }
             _ => (),
         }
-        let instance_data_handle = match self.instance_data_handle {
-            Some(handle) => handle,
-            None => Handle::new(),
-        };
         Ok(Symbol {
             name: self.name,
             properties,
             class_member_offset: self.class_member_offset,
             class_member_array_size: self.class_member_array_size,
-            instance_data_handle,
-            instance_data_class: self.instance_data_class,
             parent: self.parent,
             address: self.address,
             data: self.data,
@@ -231,8 +243,6 @@ pub struct Symbol {
     // Valid for Classes that write directly to the engine
     // Store array size of the Class member var
     class_member_array_size: Option<NonZeroI32>,
-    instance_data_handle: Handle,
-    instance_data_class: Option<InstanceClass>,
     parent: Option<NonZeroU32>,
     address: Option<NonZeroU32>,
     data: Option<Data>,
@@ -253,9 +263,6 @@ impl Symbol {
     pub fn get_address(&self) -> Option<NonZeroU32> {
         self.address
     }
-    pub fn set_address(&mut self, address: u32) {
-        self.address = NonZeroU32::new(address);
-    }
     // pub fn get_data_at(&self, index: usize) -> Result<Data, &str> {
     //     match self.data {
     //         Some(data) => {
@@ -267,40 +274,20 @@ impl Symbol {
     //         None => Err("Data not specified"),
     //     }
     // }
+    /// Initial value of the symbol as stored in the dat file
     pub fn get_data(&self) -> Option<&Data> {
-        match self.data {
-            Some(val) => Some(&val),
-            None => None,
-        }
-    }
-    pub fn get_mut_data(&mut self) -> Option<&mut Data> {
-        match self.data {
-            Some(val) => Some(&mut val),
-            None => None,
-        }
+        self.data.as_ref()
     }
     pub fn get_string(&self) -> Option<&String> {
-        let string_result: Result<String, _> = match self.data {
-            Some(data) => data.try_into(),
-            None => return None,
-        };
-        match string_result {
-            Ok(string) => Some(&string),
-            Err(_) => None,
+        match &self.data {
+            Some(Data::StringSequence(vec)) => vec.first(),
+            _ => None,
         }
     }
-    pub fn get_mut_string(&self) -> Option<&mut String> {
-        let string_result: Result<String, _> = match self.data {
-            Some(data) => data.try_into(),
-            None => return None,
-        };
-        match string_result {
-            Ok(string) => Some(&mut string),
-            Err(_) => None,
-        }
+    pub fn get_class_member_offset(&self) -> Option<NonZeroI32> {
+        self.class_member_offset
     }
-    pub fn set_class_member(&mut self, offset: i32, array_size: i32) {
-        self.class_member_offset = NonZeroI32::new(offset);
-        self.class_member_array_size = NonZeroI32::new(array_size);
+    pub fn get_class_member_array_size(&self) -> Option<NonZeroI32> {
+        self.class_member_array_size
     }
 }
@@ -0,0 +1,163 @@
+//! Small dat files built in memory for the tests of the virtual machine
+use super::file::File;
+use super::stack::{Stack, StackOpCode};
+use super::sym_table::SymTable;
+use super::symbol::{Data, Properties, SymbolBuilder};
+use super::{Flag, Kind, Operator};
+use enumflags2::BitFlags;
+use std::mem;
+use std::sync::Arc;
+
+/// Symbols are appended in the order of the calls.
+/// Index 0 and address 0 stay unused as op codes cannot refer to them.
+pub struct TestFile {
+    sym_table: SymTable,
+    stack: Stack,
+    next_address: usize,
+}
+
+impl TestFile {
+    pub fn new() -> Self {
+        let mut test_file = TestFile {
+            sym_table: SymTable::new(),
+            stack: Stack::new(0, 0),
+            next_address: 1,
+        };
+        test_file.push("", Kind::Void, 0, BitFlags::empty(), 0, None, None);
+        test_file
+    }
+    #[allow(clippy::too_many_arguments)]
+    fn push(
+        &mut self,
+        name: &str,
+        kind: Kind,
+        count: u32,
+        flags: BitFlags<Flag>,
+        parent: usize,
+        address: Option<usize>,
+        data: Option<Data>,
+    ) -> usize {
+        let element = count | (kind as u32) << 12 | (flags.bits() as u32) << 16;
+        let mut builder = SymbolBuilder::new(name);
+        builder
+            .with_properties(Properties::new(0, element, 0, 0, 0, 0, 0))
+            .with_parent(parent as u32);
+        if let Some(address) = address {
+            builder.with_address(address as u32);
+        }
+        if let Some(data) = data {
+            builder.with_data(data);
+        }
+        let index = self.sym_table.len();
+        self.sym_table.push(builder.build().unwrap());
+        index
+    }
+    /// Writes the code behind the previous code, `None` for no code
+    fn write_code(&mut self, code: &[StackOpCode]) -> Option<usize> {
+        if code.is_empty() {
+            return None;
+        }
+        let address = self.next_address;
+        for op_code in code {
+            self.stack.insert(self.next_address, *op_code);
+            self.next_address += op_code.get_operator_size();
+        }
+        Some(address)
+    }
+    /// Global variable, ints, floats and strings start zeroed
+    pub fn var(&mut self, name: &str, kind: Kind, count: u32) -> usize {
+        let data = zeroed(kind, count);
+        self.push(name, kind, count, BitFlags::empty(), 0, None, data)
+    }
+    /// Class followed by its members as `(name, kind, array size)`
+    pub fn class(&mut self, name: &str, members: &[(&str, Kind, u32)]) -> usize {
+        let flags = BitFlags::empty();
+        let class = self.push(
+            name,
+            Kind::Class,
+            members.len() as u32,
+            flags,
+            0,
+            None,
+            None,
+        );
+        for (member, kind, count) in members {
+            let member = format!("{}.{}", name, member);
+            let flags = BitFlags::from_flag(Flag::ClassVar);
+            let data = zeroed(*kind, *count);
+            self.push(&member, *kind, *count, flags, class, None, data);
+        }
+        class
+    }
+    /// Script function followed by its parameters, `Flag::Const` is always set
+    pub fn func(
+        &mut self,
+        name: &str,
+        flags: BitFlags<Flag>,
+        params: &[(&str, Kind)],
+        code: &[StackOpCode],
+    ) -> usize {
+        let address = self.write_code(code);
+        let flags = flags | Flag::Const;
+        let func = self.push(
+            name,
+            Kind::Func,
+            params.len() as u32,
+            flags,
+            0,
+            address,
+            None,
+        );
+        for (param, kind) in params {
+            let param = format!("{}.{}", name, param);
+            let data = zeroed(*kind, 1);
+            self.push(&param, *kind, 1, BitFlags::empty(), 0, None, data);
+        }
+        func
+    }
+    /// Variable holding a function, it has no initial value
+    pub fn func_var(&mut self, name: &str) -> usize {
+        self.push(name, Kind::Func, 1, BitFlags::empty(), 0, None, None)
+    }
+    pub fn get_address(&self, name: &str) -> usize {
+        let symbol = self.sym_table.get_symbol_by_name(name).unwrap();
+        symbol.get_address().unwrap().get() as usize
+    }
+    pub fn build(self) -> Arc<File> {
+        Arc::new(File::new(self.sym_table, self.stack))
+    }
+}
+
+fn zeroed(kind: Kind, count: u32) -> Option<Data> {
+    let count = count.max(1) as usize;
+    match kind {
+        Kind::Int => Some(Data::IntSequence(vec![0; count])),
+        Kind::Float => Some(Data::FloatSequence(vec![0.0; count])),
+        Kind::CharString => Some(Data::StringSequence(vec![String::new(); count])),
+        _ => None,
+    }
+}
+
+fn with_operand(
+    operator: Operator,
+    operand: fn(&mut StackOpCode, i32) -> &mut StackOpCode,
+    value: i32,
+) -> StackOpCode {
+    let mut op_code = StackOpCode::new(operator, mem::size_of::<u8>() + mem::size_of::<i32>());
+    operand(&mut op_code, value);
+    op_code
+}
+
+/// Op code without operand, e.g. `Operator::Add` or `Operator::Ret`
+pub fn op(operator: Operator) -> StackOpCode {
+    StackOpCode::new(operator, mem::size_of::<u8>())
+}
+pub fn push_int(value: i32) -> StackOpCode {
+    with_operand(Operator::PushInt, StackOpCode::with_value, value)
+}
+pub fn push_var(symbol: usize) -> StackOpCode {
+    with_operand(Operator::PushVar, StackOpCode::with_symbol, symbol as i32)
+}
+pub fn call(address: usize) -> StackOpCode {
+    with_operand(Operator::Call, StackOpCode::with_address, address as i32)
+}
@@ -0,0 +1,315 @@
+use super::call_stack_frame::CallStackFrame;
+use super::file::{Flag, Kind};
+use super::VirtualMachine;
+
+/// When a hook runs relative to the script body of the function
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HookMode {
+    /// Runs before the body, setting a return value skips the body
+    Before,
+    /// Runs instead of the body, `VirtualMachine::call_original` still runs it
+    Instead,
+    /// Runs after the body and sees its return value
+    After,
+}
+
+/// Argument of a hooked function, typed by the parameter symbol
+#[derive(Clone, Debug, PartialEq)]
+pub enum Argument {
+    Int(i32),
+    Float(f32),
+    String(String),
+    /// Symbol index of the passed instance
+    Instance(usize),
+}
+
+pub struct HookContext {
+    function: usize,
+    arguments: Vec<Argument>,
+    return_value: Option<i32>,
+}
+
+impl HookContext {
+    /// Symbol index of the hooked function
+    pub fn get_function(&self) -> usize {
+        self.function
+    }
+    pub fn get_arguments(&self) -> &[Argument] {
+        &self.arguments
+    }
+    /// Changed arguments are passed on to the script body
+    pub fn get_mut_arguments(&mut self) -> &mut Vec<Argument> {
+        &mut self.arguments
+    }
+    pub fn get_return_value(&self) -> Option<i32> {
+        self.return_value
+    }
+    pub fn set_return_value(&mut self, value: i32) {
+        self.return_value = Some(value);
+    }
+}
+
+pub type HookFunc = Box<dyn FnMut(&mut VirtualMachine, &mut HookContext) + Send>;
+
+pub(crate) struct Hook {
+    mode: HookMode,
+    func: HookFunc,
+}
+
+impl VirtualMachine {
+    /// Registers a rust function which runs whenever the script function is called,
+    /// by a `Call` instruction as well as by the host
+    pub fn hook_func<F>(&mut self, sym_name: &str, mode: HookMode, func: F) -> Result<(), String>
+    where
+        F: FnMut(&mut VirtualMachine, &mut HookContext) + Send + 'static,
+    {
+        let sym_table = self.file.get_sym_table();
+        let index = match sym_table.get_symbol_index_by_name(sym_name) {
+            Some(index) => index,
+            None => return Err(format!("Symbol {} not found", sym_name)),
+        };
+        let symbol = sym_table.get_symbol_by_index(index)?;
+        if symbol.properties.get_kind() != Kind::Func || symbol.properties.has_flag(Flag::External)
+        {
+            return Err(format!("Symbol {} is not a script function", sym_name));
+        }
+        self.hooks_by_index.insert(
+            index,
+            Hook {
+                mode,
+                func: Box::new(func),
+            },
+        );
+        Ok(())
+    }
+    pub fn unhook_func(&mut self, sym_name: &str) {
+        if let Some(index) = self.file.get_sym_table().get_symbol_index_by_name(sym_name) {
+            self.hooks_by_index.remove(&index);
+        }
+    }
+    pub(crate) fn is_hooked(&self, sym_index: usize) -> bool {
+        self.hooks_by_index.contains_key(&sym_index)
+    }
+    /// Runs the script body of a hooked function with the arguments of the context
+    /// and returns its return value
+    pub fn call_original(&mut self, context: &HookContext) -> i32 {
+        let symbol = match self
+            .file
+            .get_sym_table()
+            .get_symbol_by_index(context.function)
+        {
+            Ok(symbol) => symbol,
+            Err(_) => return 0,
+        };
+        let address = symbol
+            .get_address()
+            .map_or(0, |address| address.get() as usize);
+        let has_return = symbol.properties.has_flag(Flag::Return);
+        for argument in context.arguments.iter() {
+            match argument {
+                Argument::Int(value) => self.push_int(*value),
+                Argument::Float(value) => self.push_float(*value),
+                Argument::String(value) => self.push_string(value),
+                Argument::Instance(index) => self.push_var(*index, 0),
+            }
+        }
+        if let Err(error) = self.run_function_at(address) {
+            log::error!("{}", error);
+        }
+        match has_return && !self.stack.is_empty() {
+            true => self.pop_int().unwrap_or(0),
+            false => 0,
+        }
+    }
+    /// Runs the function body at the address and restores the program counter afterwards,
+    /// a script error stops the function and is returned
+    pub(crate) fn run_function_at(&mut self, address: usize) -> Result<(), String> {
+        let return_address = self.program_counter;
+        CallStackFrame::Address(address).insert_in_vm(self);
+        self.set_program_counter(address);
+        let result = loop {
+            match self.execute_instruction() {
+                Ok(true) => (),
+                Ok(false) => break Ok(()),
+                Err(error) => break Err(error),
+            }
+        };
+        self.call_stack.pop();
+        self.set_program_counter(return_address);
+        result
+    }
+    /// Pops the arguments of the function, leaves the return value on the stack like the script body would
+    pub(crate) fn call_hooked_function(&mut self, sym_index: usize) {
+        let mut hook = match self.hooks_by_index.remove(&sym_index) {
+            Some(hook) => hook,
+            None => return,
+        };
+        let mut context = HookContext {
+            function: sym_index,
+            arguments: self.pop_arguments(sym_index),
+            return_value: None,
+        };
+        match hook.mode {
+            HookMode::Before => {
+                (hook.func)(self, &mut context);
+                if context.return_value.is_none() {
+                    context.return_value = Some(self.call_original(&context));
+                }
+            }
+            HookMode::Instead => (hook.func)(self, &mut context),
+            HookMode::After => {
+                context.return_value = Some(self.call_original(&context));
+                (hook.func)(self, &mut context);
+            }
+        }
+        self.hooks_by_index.entry(sym_index).or_insert(hook);
+
+        let has_return = self
+            .file
+            .get_sym_table()
+            .get_symbol_by_index(sym_index)
+            .is_ok_and(|symbol| symbol.properties.has_flag(Flag::Return));
+        if has_return {
+            self.push_int(context.return_value.unwrap_or(0));
+        }
+    }
+    /// Parameters are the symbols following the function symbol
+    fn pop_arguments(&mut self, sym_index: usize) -> Vec<Argument> {
+        let sym_table = self.file.clone();
+        let sym_table = sym_table.get_sym_table();
+        let count = sym_table
+            .get_symbol_by_index(sym_index)
+            .map_or(0, |symbol| symbol.properties.get_count() as usize);
+        let mut arguments = Vec::with_capacity(count);
+        for param in (sym_index + 1..=sym_index + count).rev() {
+            let kind = sym_table
+                .get_symbol_by_index(param)
+                .map_or(Kind::Int, |symbol| symbol.properties.get_kind());
+            let argument = match kind {
+                Kind::Float => Argument::Float(self.pop_float().unwrap_or(0.0)),
+                Kind::CharString => Argument::String(self.pop_string().unwrap_or_default()),
+                Kind::Instance => Argument::Instance(self.pop_var().0),
+                _ => Argument::Int(self.pop_int().unwrap_or(0)),
+            };
+            arguments.push(argument);
+        }
+        arguments.reverse();
+        arguments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::file::test_file::{call, op, push_int, push_var, TestFile};
+    use crate::vm::file::Operator;
+    use enumflags2::BitFlags;
+
+    /// `func int DOUBLE(var int x) { return x * 2; }` and `func int CALLER() { return DOUBLE(5); }`
+    fn setup() -> (VirtualMachine, usize, usize) {
+        let mut test_file = TestFile::new();
+        let returns = BitFlags::from_flag(Flag::Return);
+        let double = 1;
+        let param = double + 1;
+        let code = [
+            push_var(param),
+            op(Operator::Assign),
+            push_int(2),
+            push_var(param),
+            op(Operator::Multiply),
+            op(Operator::Ret),
+        ];
+        assert_eq!(
+            test_file.func("DOUBLE", returns, &[("X", Kind::Int)], &code),
+            double
+        );
+        let code = [
+            push_int(5),
+            call(test_file.get_address("DOUBLE")),
+            op(Operator::Ret),
+        ];
+        let caller = test_file.func("CALLER", returns, &[], &code);
+        (VirtualMachine::from_file(test_file.build()), double, caller)
+    }
+
+    fn run(virtual_machine: &mut VirtualMachine, func: usize, arguments: &[i32]) -> i32 {
+        virtual_machine.prepare_run_func();
+        for argument in arguments {
+            virtual_machine.push_int(*argument);
+        }
+        virtual_machine.run_func_by_sym_index(func, false).unwrap()
+    }
+
+    #[test]
+    fn before_rewrites_arguments() {
+        let (mut virtual_machine, double, caller) = setup();
+        virtual_machine
+            .hook_func("DOUBLE", HookMode::Before, |_, context| {
+                assert_eq!(context.get_function(), 1);
+                context.get_mut_arguments()[0] = Argument::Int(10);
+            })
+            .unwrap();
+        assert_eq!(run(&mut virtual_machine, double, &[21]), 20);
+        assert_eq!(run(&mut virtual_machine, caller, &[]), 20);
+    }
+
+    #[test]
+    fn before_return_value_skips_the_body() {
+        let (mut virtual_machine, double, _) = setup();
+        virtual_machine
+            .hook_func("DOUBLE", HookMode::Before, |_, context| {
+                context.set_return_value(7)
+            })
+            .unwrap();
+        assert_eq!(run(&mut virtual_machine, double, &[21]), 7);
+        assert_eq!(virtual_machine.get_int_value(double + 1, 0), 0);
+    }
+
+    #[test]
+    fn instead_replaces_the_body() {
+        let (mut virtual_machine, double, caller) = setup();
+        virtual_machine
+            .hook_func("DOUBLE", HookMode::Instead, |_, context| {
+                assert_eq!(context.get_arguments(), &[Argument::Int(5)]);
+                context.set_return_value(99);
+            })
+            .unwrap();
+        assert_eq!(run(&mut virtual_machine, caller, &[]), 99);
+        assert_eq!(virtual_machine.get_int_value(double + 1, 0), 0);
+
+        virtual_machine
+            .hook_func("DOUBLE", HookMode::Instead, |virtual_machine, context| {
+                let original = virtual_machine.call_original(context);
+                context.set_return_value(original + 1);
+            })
+            .unwrap();
+        assert_eq!(run(&mut virtual_machine, caller, &[]), 11);
+    }
+
+    #[test]
+    fn after_overrides_the_return_value() {
+        let (mut virtual_machine, double, caller) = setup();
+        virtual_machine
+            .hook_func("DOUBLE", HookMode::After, |_, context| {
+                let value = context.get_return_value().unwrap();
+                context.set_return_value(-value);
+            })
+            .unwrap();
+        assert_eq!(run(&mut virtual_machine, double, &[21]), -42);
+        assert_eq!(run(&mut virtual_machine, caller, &[]), -10);
+
+        virtual_machine.unhook_func("DOUBLE");
+        assert_eq!(run(&mut virtual_machine, caller, &[]), 10);
+    }
+
+    #[test]
+    fn only_script_functions_are_hooked() {
+        let (mut virtual_machine, _, _) = setup();
+        assert!(virtual_machine
+            .hook_func("DOUBLE.X", HookMode::Before, |_, _| ())
+            .is_err());
+        assert!(virtual_machine
+            .hook_func("MISSING", HookMode::Before, |_, _| ())
+            .is_err());
+    }
+}
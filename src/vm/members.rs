@@ -0,0 +1,434 @@
+use super::file::symbol::Data;
+use super::file::{Flag, Kind};
+use super::VirtualMachine;
+use crate::game_state::GameState;
+use crate::stdlib::members::{ClassMembers, MemberValue};
+use crate::stdlib::prelude::*;
+use std::collections::HashSet;
+use zen_memory::Handle;
+
+/// Difference between a native struct and its class in the DAT
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClassMismatch {
+    /// The struct binds a member the class does not have
+    MissingInClass { class: String, member: String },
+    /// The class has a member the struct does not bind
+    MissingInStruct { class: String, member: String },
+    KindMismatch {
+        class: String,
+        member: String,
+        kind: Kind,
+    },
+    ArraySizeMismatch {
+        class: String,
+        member: String,
+        expected: u32,
+        found: u32,
+    },
+}
+
+/// Accessor a class member symbol is routed to
+#[derive(Copy, Clone)]
+pub(crate) struct MemberBinding {
+    class: InstanceClass,
+    slot: usize,
+    class_symbol: usize,
+}
+
+impl VirtualMachine {
+    /// Binds every class member symbol, e.g. `C_NPC.ATTRIBUTE`, to the accessor on its native struct.
+    /// Members of classes without a native struct are bound to `DynamicInstance` by their position,
+    /// members without a native field keep their value in the symbol.
+    pub(crate) fn bind_class_members(&mut self) {
+        self.member_bindings.clear();
+        let sym_table = self.file.get_sym_table();
+        for index in 0..sym_table.len() {
+            let symbol = sym_table.get_symbol_by_index(index).unwrap();
+            if !symbol.properties.has_flag(Flag::ClassVar) {
+                continue;
+            }
+            let (class_name, member_name) =
+                match symbol.get_name().and_then(|name| name.split_once('.')) {
+                    Some(names) => names,
+                    None => continue,
+                };
+            let class_symbol = match sym_table.get_symbol_index_by_name(class_name) {
+                Some(class_symbol) if class_symbol < index => class_symbol,
+                _ => continue,
+            };
+            let binding = match InstanceClass::from_class_name(class_name) {
+                Some(class) => {
+                    GameState::get_member_slot(class, member_name).map(|slot| MemberBinding {
+                        class,
+                        slot,
+                        class_symbol,
+                    })
+                }
+                None => Some(MemberBinding {
+                    class: InstanceClass::Dynamic,
+                    slot: index - class_symbol - 1,
+                    class_symbol,
+                }),
+            };
+            if let Some(binding) = binding {
+                self.member_bindings.insert(index, binding);
+            }
+        }
+    }
+    /// Whether the current instance is an object of the class the member belongs to
+    fn is_current_instance_of(&self, binding: &MemberBinding) -> bool {
+        if self.current_instance_class != Some(binding.class) {
+            return false;
+        }
+        match binding.class {
+            InstanceClass::Dynamic => self
+                .game_state
+                .get_dynamic_instance(&self.current_instance_handle)
+                .is_some_and(|object| object.get_class_symbol() == binding.class_symbol),
+            _ => true,
+        }
+    }
+    /// Compares the members bound by `T` with the class symbol of the same name.
+    /// Classes missing in the DAT are not checked.
+    pub fn check_class_layout<T: ClassMembers>(&self) -> Vec<ClassMismatch> {
+        let sym_table = self.file.get_sym_table();
+        let mut mismatches = vec![];
+        if sym_table.get_symbol_index_by_name(T::CLASS_NAME).is_none() {
+            return mismatches;
+        }
+        let class = T::CLASS_NAME.to_owned();
+        let prefix = format!("{}.", T::CLASS_NAME);
+        let layout = T::get_layout();
+        let mut found = HashSet::new();
+        for index in 0..sym_table.len() {
+            let symbol = sym_table.get_symbol_by_index(index).unwrap();
+            let member = match symbol.get_name() {
+                Some(name) if name.starts_with(&prefix) => &name[prefix.len()..],
+                _ => continue,
+            };
+            if !symbol.properties.has_flag(Flag::ClassVar) {
+                continue;
+            }
+            let member_layout = match layout.iter().find(|layout| layout.name == member) {
+                Some(member_layout) => member_layout,
+                None => {
+                    mismatches.push(ClassMismatch::MissingInStruct {
+                        class: class.clone(),
+                        member: member.to_owned(),
+                    });
+                    continue;
+                }
+            };
+            found.insert(member_layout.name);
+            let kind = symbol.properties.get_kind();
+            if !member_layout.kinds.contains(&kind) {
+                mismatches.push(ClassMismatch::KindMismatch {
+                    class: class.clone(),
+                    member: member.to_owned(),
+                    kind,
+                });
+            }
+            let count = symbol.properties.get_count().max(1);
+            match member_layout.array_size {
+                Some(expected) if expected != count => {
+                    mismatches.push(ClassMismatch::ArraySizeMismatch {
+                        class: class.clone(),
+                        member: member.to_owned(),
+                        expected,
+                        found: count,
+                    })
+                }
+                _ => (),
+            }
+        }
+        for member_layout in layout.iter().filter(|layout| !found.contains(layout.name)) {
+            mismatches.push(ClassMismatch::MissingInClass {
+                class: class.clone(),
+                member: member_layout.name.to_owned(),
+            });
+        }
+        mismatches
+    }
+    /// Checks all native instance structs against the DAT
+    pub fn check_class_layouts(&self) -> Vec<ClassMismatch> {
+        let mut mismatches = self.check_class_layout::<Npc>();
+        mismatches.append(&mut self.check_class_layout::<Mission>());
+        mismatches.append(&mut self.check_class_layout::<Info>());
+        mismatches.append(&mut self.check_class_layout::<Item>());
+        mismatches.append(&mut self.check_class_layout::<ItemReact>());
+        mismatches.append(&mut self.check_class_layout::<Focus>());
+        mismatches.append(&mut self.check_class_layout::<Menu>());
+        mismatches.append(&mut self.check_class_layout::<MenuItem>());
+        mismatches.append(&mut self.check_class_layout::<SoundEffect>());
+        mismatches.append(&mut self.check_class_layout::<ParticleEffect>());
+        mismatches.append(&mut self.check_class_layout::<MusicTheme>());
+        mismatches
+    }
+    /// Checks the native structs against the current file and logs every mismatch
+    pub(crate) fn update_class_mismatches(&mut self) {
+        self.class_mismatches = self.check_class_layouts();
+        for mismatch in self.class_mismatches.iter() {
+            log::warn!("Class layout mismatch: {:?}", mismatch);
+        }
+    }
+    /// Mismatches found when the file was loaded or reloaded
+    pub fn get_class_mismatches(&self) -> &[ClassMismatch] {
+        &self.class_mismatches
+    }
+    /// Member of a dynamic instance by its name without the class prefix
+    pub fn get_dynamic_member(&self, handle: &Handle, name: &str) -> Option<&Data> {
+        self.game_state
+            .get_dynamic_instance(handle)?
+            .get_member_by_name(self.file.get_sym_table(), name)
+    }
+    pub(crate) fn is_bound_member(&self, index: usize) -> bool {
+        self.member_bindings.contains_key(&index)
+    }
+    /// Whether the index is within the array size of the member in the DAT
+    fn is_in_member_bounds(&self, index: usize, array_index: usize) -> bool {
+        self.file
+            .get_sym_table()
+            .get_symbol_by_index(index)
+            .is_ok_and(|symbol| array_index < symbol.properties.get_count().max(1) as usize)
+    }
+    /// Reads the member of the current instance
+    pub(crate) fn get_member_value(&self, index: usize, array_index: usize) -> Option<MemberValue> {
+        let binding = self.member_bindings.get(&index)?;
+        if !self.is_current_instance_of(binding) {
+            log::warn!(
+                "Member {} accessed without a {:?} instance",
+                index,
+                binding.class
+            );
+            return None;
+        }
+        if !self.is_in_member_bounds(index, array_index) {
+            log::warn!("Index {} out of bound for member {}", array_index, index);
+            return None;
+        }
+        let value = self.game_state.get_member(
+            binding.class,
+            &self.current_instance_handle,
+            binding.slot,
+            array_index,
+        );
+        if value.is_none() {
+            log::warn!("Index {} out of bound for member {}", array_index, index);
+        }
+        value
+    }
+    /// Writes the member of the current instance
+    pub(crate) fn set_member_value(
+        &mut self,
+        index: usize,
+        array_index: usize,
+        value: MemberValue,
+    ) {
+        let binding = match self.member_bindings.get(&index) {
+            Some(binding) => *binding,
+            None => return,
+        };
+        if !self.is_current_instance_of(&binding) {
+            log::warn!(
+                "Member {} accessed without a {:?} instance",
+                index,
+                binding.class
+            );
+            return;
+        }
+        if !self.is_in_member_bounds(index, array_index) {
+            return log::warn!("Index {} out of bound for member {}", array_index, index);
+        }
+        let handle = self.current_instance_handle;
+        if !self
+            .game_state
+            .set_member(binding.class, &handle, binding.slot, array_index, value)
+        {
+            log::warn!("Could not assign index {} of member {}", array_index, index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::members::DaedalusClass;
+    use crate::vm::file::test_file::TestFile;
+
+    #[derive(Default, DaedalusClass)]
+    #[daedalus(class = "C_TEST")]
+    struct Test {
+        #[daedalus(name = "ID")]
+        id: i32,
+        #[daedalus(name = "NAME")]
+        name: String,
+        #[daedalus(name = "VALUES")]
+        values: [i32; 4],
+        #[daedalus(name = "RANGE1", element = 0)]
+        #[daedalus(name = "RANGE2", element = 1)]
+        range: (f32, f32),
+        #[daedalus(name = "MISSING")]
+        missing: i32,
+    }
+
+    fn test_class() -> VirtualMachine {
+        let mut test_file = TestFile::new();
+        test_file.class(
+            "C_TEST",
+            &[
+                ("ID", Kind::Int, 1),
+                ("NAME", Kind::Int, 1),
+                ("VALUES", Kind::Int, 2),
+                ("RANGE1", Kind::Float, 1),
+                ("RANGE2", Kind::Float, 1),
+                ("EXTRA", Kind::Float, 1),
+            ],
+        );
+        VirtualMachine::from_file(test_file.build())
+    }
+
+    #[test]
+    fn derive_binds_members() {
+        let mut test = Test::default();
+        let slot = Test::get_member_slot("RANGE2").unwrap();
+        assert!((Test::MEMBERS[slot].set)(
+            &mut test,
+            0,
+            MemberValue::Float(2.5)
+        ));
+        assert_eq!(test.range, (0.0, 2.5));
+        let slot = Test::get_member_slot("VALUES").unwrap();
+        assert!((Test::MEMBERS[slot].set)(&mut test, 3, MemberValue::Int(7)));
+        assert!(!(Test::MEMBERS[slot].set)(
+            &mut test,
+            4,
+            MemberValue::Int(7)
+        ));
+        assert_eq!(
+            (Test::MEMBERS[slot].get)(&test, 3),
+            Some(MemberValue::Int(7))
+        );
+        assert!(Test::get_member_slot("EXTRA").is_none());
+        assert_eq!(Test::get_layout().len(), 6);
+    }
+
+    #[test]
+    fn flags_keep_bits_without_variant() {
+        use crate::stdlib::instances::npc::{Flag, Npc};
+        let mut npc = Npc::default();
+        let slot = Npc::get_member_slot("FLAGS").unwrap();
+        let bits = Flag::Immortal as i32 | 1 << 20;
+        assert!((Npc::MEMBERS[slot].set)(
+            &mut npc,
+            0,
+            MemberValue::Int(bits)
+        ));
+        assert_eq!(npc.get_flags(), Flag::Immortal);
+        npc.set_flags(Flag::Ghost.into());
+        assert_eq!(npc.get_raw_flags(), Flag::Ghost as i32 | 1 << 20);
+        assert_eq!(
+            (Npc::MEMBERS[slot].get)(&npc, 0),
+            Some(MemberValue::Int(Flag::Ghost as i32 | 1 << 20))
+        );
+    }
+
+    #[test]
+    fn script_arrays_are_bound_by_the_class() {
+        let mut test_file = TestFile::new();
+        test_file.class("C_NPC", &[("AIVAR", Kind::Int, 2)]);
+        let mut virtual_machine = VirtualMachine::from_file(test_file.build());
+        let member = virtual_machine
+            .file
+            .get_sym_table()
+            .get_symbol_index_by_name("C_NPC.AIVAR")
+            .unwrap();
+        let npc = virtual_machine
+            .get_mut_game_state()
+            .create_object(InstanceClass::Npc)
+            .unwrap();
+        virtual_machine.current_instance_handle = npc;
+        virtual_machine.current_instance_class = Some(InstanceClass::Npc);
+        virtual_machine.set_member_value(member, 1, MemberValue::Int(5));
+        virtual_machine.set_member_value(member, 2, MemberValue::Int(7));
+        assert_eq!(virtual_machine.get_member_value(member, 2), None);
+        let npc = virtual_machine.get_game_state().get_npc(&npc).unwrap();
+        assert_eq!(npc.get_ai_var(1), 5);
+        assert_eq!(npc.get_ai_var(2), 0);
+    }
+
+    #[test]
+    fn vector_accessors_stop_at_the_maximum_length() {
+        use crate::stdlib::instances::menu::MAX_USERVARS;
+        use crate::stdlib::instances::menu_item::MenuItem;
+        let mut menu_item = MenuItem::default();
+        assert!(menu_item.set_user_float(MAX_USERVARS - 1, 1.5));
+        assert!(!menu_item.set_user_float(MAX_USERVARS, 2.5));
+        assert_eq!(menu_item.get_user_float(MAX_USERVARS - 1), 1.5);
+        assert_eq!(menu_item.get_user_float(MAX_USERVARS), 0.0);
+    }
+
+    #[test]
+    fn class_layout_mismatches() {
+        let virtual_machine = test_class();
+        let class = "C_TEST".to_owned();
+        let mismatches = virtual_machine.check_class_layout::<Test>();
+        assert_eq!(
+            mismatches,
+            vec![
+                ClassMismatch::KindMismatch {
+                    class: class.clone(),
+                    member: "NAME".to_owned(),
+                    kind: Kind::Int,
+                },
+                ClassMismatch::ArraySizeMismatch {
+                    class: class.clone(),
+                    member: "VALUES".to_owned(),
+                    expected: 4,
+                    found: 2,
+                },
+                ClassMismatch::MissingInStruct {
+                    class: class.clone(),
+                    member: "EXTRA".to_owned(),
+                },
+                ClassMismatch::MissingInClass {
+                    class,
+                    member: "MISSING".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn classes_missing_in_the_file_are_not_checked() {
+        let virtual_machine = VirtualMachine::from_file(TestFile::new().build());
+        assert!(virtual_machine.check_class_layout::<Test>().is_empty());
+        assert!(virtual_machine.get_class_mismatches().is_empty());
+    }
+
+    #[test]
+    fn native_classes_are_checked_on_load() {
+        let mut test_file = TestFile::new();
+        test_file.class(
+            "C_FOCUS",
+            &[("NPC_LONGRANGE", Kind::Int, 1), ("NPC_PRIO", Kind::Int, 3)],
+        );
+        let virtual_machine = VirtualMachine::from_file(test_file.build());
+        let mismatches = virtual_machine.get_class_mismatches();
+        let class = "C_FOCUS".to_owned();
+        assert!(mismatches.contains(&ClassMismatch::KindMismatch {
+            class: class.clone(),
+            member: "NPC_LONGRANGE".to_owned(),
+            kind: Kind::Int,
+        }));
+        assert!(mismatches.contains(&ClassMismatch::ArraySizeMismatch {
+            class: class.clone(),
+            member: "NPC_PRIO".to_owned(),
+            expected: 1,
+            found: 3,
+        }));
+        assert!(mismatches.contains(&ClassMismatch::MissingInClass {
+            class,
+            member: "NPC_AZI".to_owned(),
+        }));
+    }
+}
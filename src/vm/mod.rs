@@ -1,17 +1,32 @@
 use crate::game_state::{GameExternals, GameState};
+use crate::stdlib::members::MemberValue;
 use crate::stdlib::InstanceClass;
 use call_stack_frame::CallStackFrame;
-use file::file::File;
 use file::stack::StackOpCode;
-use file::symbol::{Data, Symbol, SymbolBuilder};
+use file::symbol::Data;
 use file::{Flag, Kind, Operator};
 use std::collections::{HashMap, VecDeque};
 use std::convert::TryInto;
+use std::sync::Arc;
+use symbol_values::{InstanceData, SymbolValues};
 use zen_memory::Handle;
 
+pub use file::file::File;
+pub use hook::{Argument, HookContext, HookFunc, HookMode};
+pub use members::ClassMismatch;
+pub use preload::{InstanceRegistry, PreloadFailure, PreloadReport, STATIC_CLASSES};
+pub use reload::ReloadConflict;
+
+mod accessors;
 mod call_stack_frame;
 mod external_funcs;
-mod file;
+pub mod file;
+mod hook;
+mod members;
+mod preload;
+mod reload;
+mod snapshot;
+pub mod symbol_values;
 
 const NUM_FAKE_STRING_SYMBOLS: u8 = 5;
 struct StackValue(u32);
@@ -36,37 +51,55 @@ impl From<u32> for StackValue {
     }
 }
 struct VirtualMachineState {
+    current_instance: usize,
     current_instance_handle: Handle,
-    current_instance_class: InstanceClass,
+    current_instance_class: Option<InstanceClass>,
     program_counter: usize,
     stack: Vec<StackValue>,
-    call_stack: Vec<usize>,
-    symbol: Symbol,
 }
-pub struct VirtualMachine<'a> {
-    file: File,
+/// Function called when a script calls the external with the registered name
+pub type ExternalFunc = Box<dyn FnMut(&mut VirtualMachine) + Send>;
+
+pub struct VirtualMachine {
+    file: Arc<File>,
+    symbol_values: SymbolValues,
     program_counter: usize,
     stack: Vec<StackValue>,
     call_stack: Vec<CallStackFrame>,
-    externals_by_index: HashMap<usize, &'a dyn Fn(&VirtualMachine)>,
+    externals_by_index: HashMap<usize, ExternalFunc>,
+    hooks_by_index: HashMap<usize, hook::Hook>,
+    member_bindings: HashMap<usize, members::MemberBinding>,
+    class_mismatches: Vec<ClassMismatch>,
+    on_external_called: Option<Box<dyn FnMut(usize) + Send>>,
     current_instance: usize,
     current_instance_handle: Handle,
     current_instance_class: Option<InstanceClass>,
     registered_instances: HashMap<InstanceClass, Vec<usize>>,
-    game_state: GameState<'a>,
+    game_state: GameState,
     state_stack: Vec<VirtualMachineState>,
     fake_string_symbols: VecDeque<usize>,
 }
 
-impl<'a> VirtualMachine<'a> {
-    pub fn new(file: String) -> VirtualMachine<'a> {
+impl VirtualMachine {
+    pub fn new(file: String) -> VirtualMachine {
         let file = File::open(file).unwrap();
-        let virtual_machine = VirtualMachine {
+        Self::from_file(Arc::new(file))
+    }
+    /// Creates a virtual machine on top of an already loaded file.
+    /// All values start as they are stored in the file,
+    /// the native structs are checked against the classes of the file.
+    pub fn from_file(file: Arc<File>) -> VirtualMachine {
+        let mut virtual_machine = VirtualMachine {
             file,
+            symbol_values: SymbolValues::new(),
             program_counter: 0,
             stack: vec![],
             call_stack: vec![],
             externals_by_index: HashMap::new(),
+            hooks_by_index: HashMap::new(),
+            member_bindings: HashMap::new(),
+            class_mismatches: vec![],
+            on_external_called: None,
             current_instance: 0,
             current_instance_handle: Handle::new(),
             current_instance_class: None,
@@ -75,255 +108,817 @@ impl<'a> VirtualMachine<'a> {
             state_stack: vec![],
             fake_string_symbols: VecDeque::new(),
         };
+
+        virtual_machine.create_fake_string_symbols();
+        virtual_machine.bind_class_members();
+        virtual_machine.update_class_mismatches();
         // Register functions
+        virtual_machine.register_external_func("Wld_InsertNpc", external_funcs::insert_npc);
+        virtual_machine.register_external_func("Wld_InsertItem", external_funcs::insert_item);
+        virtual_machine.register_external_func("Info_AddChoice", external_funcs::info_add_choice);
         virtual_machine
-            .register_external_func("insert_item", |virtual_machine: &VirtualMachine| {});
+            .register_external_func("Info_ClearChoices", external_funcs::info_clear_choices);
+        virtual_machine.register_external_func("Npc_KnowsInfo", external_funcs::npc_knows_info);
+        virtual_machine.register_external_func("Log_CreateTopic", external_funcs::log_create_topic);
+        virtual_machine
+            .register_external_func("Log_SetTopicStatus", external_funcs::log_set_topic_status);
+        virtual_machine.register_external_func("Log_AddEntry", external_funcs::log_add_entry);
+        virtual_machine.register_external_func("Npc_HasItems", external_funcs::npc_has_items);
+        virtual_machine
+            .register_external_func("Npc_RemoveInvItems", external_funcs::npc_remove_inv_items);
+        virtual_machine.register_external_func("CreateInvItems", external_funcs::create_inv_items);
+        virtual_machine.register_external_func("EquipItem", external_funcs::equip_item);
+        virtual_machine.register_external_func("Wld_GetDay", external_funcs::wld_get_day);
+        virtual_machine.register_external_func("Wld_SetTime", external_funcs::wld_set_time);
+        virtual_machine.register_external_func("Wld_IsTime", external_funcs::wld_is_time);
+        virtual_machine
+            .register_external_func("Npc_GetStateTime", external_funcs::npc_get_state_time);
+        virtual_machine
+            .register_external_func("Npc_SetStateTime", external_funcs::npc_set_state_time);
+        virtual_machine.register_external_func("TA", external_funcs::ta);
+        virtual_machine.register_external_func("TA_Min", external_funcs::ta_min);
+        virtual_machine
+            .register_external_func("Npc_ExchangeRoutine", external_funcs::npc_exchange_routine);
 
-        for index in 0..NUM_FAKE_STRING_SYMBOLS {
-            let builder = SymbolBuilder::new("")
-                .with_properties(Default::default())
-                .set_kind(Kind::CharString);
-            let index = file.sym_table.push(builder.build());
-            virtual_machine.fake_string_symbols.push(index);
-        }
         virtual_machine.current_instance_handle.invalidate();
+        virtual_machine
+    }
+
+    /// Fake string symbols live behind the end of the symbol table
+    fn create_fake_string_symbols(&mut self) {
+        let sym_count = self.file.get_sym_table().len();
+        for index in sym_count..sym_count + NUM_FAKE_STRING_SYMBOLS as usize {
+            self.symbol_values
+                .insert_data(index, Data::StringSequence(vec![String::new()]));
+            self.fake_string_symbols.push_back(index);
+        }
     }
 
-    pub fn get_current_instruction(&mut self) -> StackOpCode {
-        let operator = self.file.get_stack_op_code(self.program_counter);
+    pub fn get_current_instruction(&mut self) -> Result<StackOpCode, String> {
+        let operator = self.file.get_stack_op_code(self.program_counter)?;
         self.program_counter += operator.get_operator_size();
-        operator
+        Ok(operator)
+    }
+    /// Saves the current state, arguments for the function have to be pushed afterwards
+    pub fn prepare_run_func(&mut self) {
+        self.push_state();
     }
-    //pub fn prepare_run_func(&self) {}
+    /// Runs a script function and returns its result, zero for functions without return.
+    /// Fails if the symbol is no script function or the script runs into an error.
     pub fn run_func_by_sym_index(
         &mut self,
         sym_index: usize,
         clear_data_stack: bool,
-    ) -> Option<u32> {
+    ) -> Result<i32, String> {
         if clear_data_stack {
             self.stack = vec![];
         }
+        let address = match self.get_func_address(sym_index) {
+            Some(address) => address,
+            None => {
+                // restore the state saved by `prepare_run_func`
+                self.pop_state();
+                return Err(format!("Symbol {} is not a script function", sym_index));
+            }
+        };
         CallStackFrame::SymbolIndex(sym_index).insert_in_vm(self);
-        let func_sym = self.file.sym_table.get_symbol_by_index(sym_index).unwrap();
-        let address = match func_sym.get_address() {
-            Some(val) => val.get(),
-            None => return None,
+        let run = match self.is_hooked(sym_index) {
+            true => {
+                self.call_hooked_function(sym_index);
+                Ok(())
+            }
+            false => {
+                self.set_program_counter(address);
+                loop {
+                    match self.execute_instruction() {
+                        Ok(true) => (),
+                        Ok(false) => break Ok(()),
+                        Err(error) => break Err(error),
+                    }
+                }
+            }
         };
-        self.set_program_counter(address);
-        while self.do_stack() {}
-        let result = match func_sym.properties.has_flag(Flag::Return) && !self.stack.is_empty() {
-            true => self.pop_int().unwrap(),
+        self.call_stack.pop();
+        if let Err(error) = run {
+            self.pop_state();
+            return Err(error);
+        }
+
+        let has_return = self
+            .file
+            .get_sym_table()
+            .get_symbol_by_index(sym_index)
+            .unwrap()
+            .properties
+            .has_flag(Flag::Return);
+        let result = match has_return && !self.stack.is_empty() {
+            true => self.pop_int().unwrap_or(0),
             false => 0,
         };
         self.pop_state();
-        Some(result)
+        Ok(result)
     }
-    pub fn set_program_counter(&mut self, target: u32) {
-        self.program_counter = target as usize;
+    /// Address of a script function, `None` for externals and other symbols
+    fn get_func_address(&self, sym_index: usize) -> Option<usize> {
+        let symbol = self
+            .file
+            .get_sym_table()
+            .get_symbol_by_index(sym_index)
+            .ok()?;
+        if symbol.properties.get_kind() != Kind::Func || symbol.properties.has_flag(Flag::External)
+        {
+            return None;
+        }
+        symbol.get_address().map(|address| address.get() as usize)
     }
-    pub fn register_external_func(&mut self, sym_name: &str, func: &'a dyn Fn(&VirtualMachine)) {
-        match self.file.sym_table.get_symbol_index_by_name(sym_name) {
-            Some(index) => {
-                self.externals_by_index.insert(index, func);
-            }
-            None => (),
+    pub fn run_func_by_name(&mut self, sym_name: &str) -> Result<i32, String> {
+        let index = self
+            .file
+            .get_sym_table()
+            .get_symbol_index_by_name(sym_name)
+            .ok_or_else(|| format!("Function {} not found", sym_name))?;
+        self.run_func_by_sym_index(index, false)
+    }
+    pub fn set_program_counter(&mut self, target: usize) {
+        self.program_counter = target;
+    }
+    pub fn register_external_func<F>(&mut self, sym_name: &str, func: F)
+    where
+        F: FnMut(&mut VirtualMachine) + Send + 'static,
+    {
+        if let Some(index) = self.file.get_sym_table().get_symbol_index_by_name(sym_name) {
+            self.externals_by_index.insert(index, Box::new(func));
         }
     }
 
-    pub fn push_int(&mut self, value: u32) {
-        self.stack.push(StackValue::from(value));
+    pub fn push_int(&mut self, value: i32) {
+        self.stack.push(StackValue::from(value as u32));
+        self.stack.push(StackValue::from(Operator::PushInt));
+    }
+    pub fn push_float(&mut self, value: f32) {
+        self.stack.push(StackValue::from(value.to_bits()));
         self.stack.push(StackValue::from(Operator::PushInt));
     }
-    pub fn push_string(&mut self, string: String) {
-        let sym_index = self.fake_string_symbols.front().unwrap();
-        let symbol = self.file.sym_table.get_symbol_by_index(*sym_index).unwrap();
-        self.fake_string_symbols
-            .push_back(self.fake_string_symbols.pop_front().unwrap());
-        let data_string = symbol.get_mut_string().unwrap();
-        data_string.clear();
-        data_string.push_str(string.as_str());
-        self.push_var(*sym_index, 0);
-    }
-    pub fn push_var(&mut self, index: usize, array_index: u32) {
-        self.stack.push(StackValue::from(array_index));
+    pub fn push_string(&mut self, string: &str) {
+        let sym_index = self.fake_string_symbols.pop_front().unwrap();
+        self.fake_string_symbols.push_back(sym_index);
+        self.set_string_value(sym_index, 0, string);
+        self.push_var(sym_index, 0);
+    }
+    pub fn push_var(&mut self, index: usize, array_index: usize) {
+        self.stack.push(StackValue::from(array_index as u32));
         self.stack.push(StackValue::from(index as u32));
         self.stack.push(StackValue::from(Operator::PushVar));
     }
     pub fn push_var_by_name(&mut self, sym_name: &str) {
         let index = self
             .file
-            .sym_table
+            .get_sym_table()
             .get_symbol_index_by_name(sym_name)
             .unwrap();
         self.push_var(index, 0);
     }
-    pub fn push_state(&self) {}
+    pub fn push_state(&mut self) {
+        self.state_stack.push(VirtualMachineState {
+            current_instance: self.current_instance,
+            current_instance_handle: self.current_instance_handle,
+            current_instance_class: self.current_instance_class,
+            program_counter: self.program_counter,
+            stack: std::mem::take(&mut self.stack),
+        });
+    }
+    pub fn pop_state(&mut self) {
+        if let Some(state) = self.state_stack.pop() {
+            self.current_instance = state.current_instance;
+            self.current_instance_handle = state.current_instance_handle;
+            self.current_instance_class = state.current_instance_class;
+            self.program_counter = state.program_counter;
+            self.stack = state.stack;
+        }
+    }
 
-    pub fn set_return<T>(&self, v: T) {}
-    pub fn pop_state(&self) {}
     /// Returns value
-    pub fn pop_int(&mut self) -> Option<u32> {
-        if self.stack.is_empty() {
-            return None;
-        }
-        let token = self.stack.pop().unwrap().get_operator().unwrap();
-        let value = self.stack.pop().unwrap().get();
+    pub fn pop_int(&mut self) -> Option<i32> {
+        let token = self.stack.pop()?.get_operator().ok()?;
         match token {
-            Operator::PushInt => Some(value),
+            Operator::PushInt => Some(self.stack.pop()?.get() as i32),
             Operator::PushVar => {
-                let index = self.stack.pop().unwrap().get();
-
-                match self
-                    .file
-                    .sym_table
-                    .get_symbol_by_index(value as usize)
-                    .unwrap()
-                    .get_data()
-                    .unwrap()
-                {
-                    Data::IntSequence(vec) => match vec.get(index as usize) {
-                        Some(val) => Some(*val),
-                        None => None,
-                    },
-                    _ => None,
-                }
+                let index = self.stack.pop()?.get() as usize;
+                let array_index = self.stack.pop()?.get() as usize;
+                Some(self.get_int_value(index, array_index))
             }
             _ => None,
         }
     }
     pub fn pop_float(&mut self) -> Option<f32> {
-        if self.stack.is_empty() {
-            return None;
-        }
-        let token = self.stack.pop().unwrap().get_operator().unwrap();
-        let value = self.stack.pop().unwrap().get();
+        let token = self.stack.pop()?.get_operator().ok()?;
         match token {
-            Operator::PushInt => Some(value as f32),
+            Operator::PushInt => Some(f32::from_bits(self.stack.pop()?.get())),
             Operator::PushVar => {
-                let index = self.stack.pop().unwrap().get();
-
-                match self
-                    .file
-                    .sym_table
-                    .get_symbol_by_index(value as usize)
-                    .unwrap()
-                    .get_data()
-                    .unwrap()
-                {
-                    Data::FloatSequence(vec) => match vec.get(index as usize) {
-                        Some(val) => Some(*val),
-                        None => None,
-                    },
-                    _ => None,
-                }
+                let index = self.stack.pop()?.get() as usize;
+                let array_index = self.stack.pop()?.get() as usize;
+                Some(self.get_float_value(index, array_index))
             }
             _ => None,
         }
     }
     pub fn pop_string(&mut self) -> Option<String> {
-        let (value, index) = self.pop_var();
+        let (index, array_index) = self.pop_var();
+        if self.is_bound_member(index) {
+            return self
+                .get_member_value(index, array_index)
+                .map(|value| value.as_string());
+        }
         match self
-            .file
-            .sym_table
-            .get_symbol_by_index(index as usize)
-            .unwrap()
-            .get_data()
-            .unwrap()
+            .symbol_values
+            .get_data(self.file.get_sym_table(), index)?
         {
-            Data::String(string) => Some(*string),
+            Data::StringSequence(vec) => vec.get(array_index).cloned(),
             _ => None,
         }
     }
-    /// Returns (value, array_index)
-    pub fn pop_var(&mut self) -> (u32, u32) {
-        let mut index = 0;
-        if self.stack.is_empty() {
-            return (0, index);
-        }
-        let token = self.stack.pop().unwrap().get_operator().unwrap();
-        let value = self.stack.pop().unwrap().get();
+    /// Returns (symbol index, array_index)
+    pub fn pop_var(&mut self) -> (usize, usize) {
+        let token = match self.stack.pop().map(|value| value.get_operator()) {
+            Some(Ok(token)) => token,
+            _ => return (0, 0),
+        };
+        let value = self.stack.pop().map_or(0, |value| value.get()) as usize;
         match token {
-            Operator::PushInt => {
-                self.stack.pop();
-                (value, index)
-            }
+            Operator::PushInt => (value, 0),
             Operator::PushVar => {
-                self.stack.pop();
-                index = self.stack.pop().unwrap().get();
-                self.stack.pop();
-                (value, index)
+                let array_index = self.stack.pop().map_or(0, |value| value.get()) as usize;
+                (value, array_index)
             }
             _ => (0xffffffff, 0),
         }
     }
 
-    pub fn set_instance(&self, inst_symbol: &str, handle: Handle, instance_class: InstanceClass) {}
-    pub fn set_current_instance(&self, sym_index: usize) {}
+    /// Object bound to the instance symbol on top of the stack
+    pub fn pop_instance(&mut self) -> Option<InstanceData> {
+        let (index, _) = self.pop_var();
+        self.symbol_values.get_instance(index)
+    }
+
+    pub(crate) fn get_int_value(&self, index: usize, array_index: usize) -> i32 {
+        if self.is_bound_member(index) {
+            return self
+                .get_member_value(index, array_index)
+                .map_or(0, |value| value.as_int());
+        }
+        match self
+            .symbol_values
+            .get_data(self.file.get_sym_table(), index)
+        {
+            Some(Data::IntSequence(vec)) => vec.get(array_index).copied().unwrap_or(0),
+            Some(Data::FloatSequence(vec)) => vec
+                .get(array_index)
+                .map_or(0, |value| value.to_bits() as i32),
+            _ => 0,
+        }
+    }
+    pub(crate) fn set_int_value(&mut self, index: usize, array_index: usize, value: i32) {
+        if self.is_bound_member(index) {
+            return self.set_member_value(index, array_index, MemberValue::Int(value));
+        }
+        let sym_table = self.file.get_sym_table();
+        match self.symbol_values.get_mut_data(sym_table, index) {
+            Some(Data::IntSequence(vec)) => match vec.get_mut(array_index) {
+                Some(val) => *val = value,
+                None => log::warn!("Index {} out of bound for symbol {}", array_index, index),
+            },
+            _ => log::warn!("Symbol {} does not hold integers", index),
+        }
+    }
+    pub(crate) fn get_float_value(&self, index: usize, array_index: usize) -> f32 {
+        if self.is_bound_member(index) {
+            return self
+                .get_member_value(index, array_index)
+                .map_or(0.0, |value| value.as_float());
+        }
+        match self
+            .symbol_values
+            .get_data(self.file.get_sym_table(), index)
+        {
+            Some(Data::FloatSequence(vec)) => vec.get(array_index).copied().unwrap_or(0.0),
+            _ => 0.0,
+        }
+    }
+    pub(crate) fn set_float_value(&mut self, index: usize, array_index: usize, value: f32) {
+        if self.is_bound_member(index) {
+            return self.set_member_value(index, array_index, MemberValue::Float(value));
+        }
+        let sym_table = self.file.get_sym_table();
+        match self.symbol_values.get_mut_data(sym_table, index) {
+            Some(Data::FloatSequence(vec)) => match vec.get_mut(array_index) {
+                Some(val) => *val = value,
+                None => log::warn!("Index {} out of bound for symbol {}", array_index, index),
+            },
+            _ => log::warn!("Symbol {} does not hold floats", index),
+        }
+    }
+    pub(crate) fn get_string_value(&self, index: usize, array_index: usize) -> String {
+        if self.is_bound_member(index) {
+            return self
+                .get_member_value(index, array_index)
+                .map_or_else(String::new, |value| value.as_string());
+        }
+        match self
+            .symbol_values
+            .get_data(self.file.get_sym_table(), index)
+        {
+            Some(Data::StringSequence(vec)) => vec.get(array_index).cloned().unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+    pub(crate) fn set_string_value(&mut self, index: usize, array_index: usize, value: &str) {
+        if self.is_bound_member(index) {
+            let value = MemberValue::String(value.to_owned());
+            return self.set_member_value(index, array_index, value);
+        }
+        let sym_table = self.file.get_sym_table();
+        match self.symbol_values.get_mut_data(sym_table, index) {
+            Some(Data::StringSequence(vec)) => match vec.get_mut(array_index) {
+                Some(val) => {
+                    val.clear();
+                    val.push_str(value);
+                }
+                None => log::warn!("Index {} out of bound for symbol {}", array_index, index),
+            },
+            _ => log::warn!("Symbol {} does not hold strings", index),
+        }
+    }
+
+    /// Binds the instance symbol to an existing object
+    pub fn set_instance(
+        &mut self,
+        inst_symbol: &str,
+        handle: Handle,
+        instance_class: InstanceClass,
+    ) {
+        match self
+            .file
+            .get_sym_table()
+            .get_symbol_index_by_name(inst_symbol)
+        {
+            Some(index) => self
+                .symbol_values
+                .set_instance(index, handle, instance_class),
+            None => log::warn!("Instance {} not found", inst_symbol),
+        }
+    }
+    /// Binds the instance symbol, `None` unbinds it. Returns the previous binding
+    pub fn replace_instance(
+        &mut self,
+        inst_symbol: &str,
+        instance: Option<InstanceData>,
+    ) -> Option<InstanceData> {
+        let index = self
+            .file
+            .get_sym_table()
+            .get_symbol_index_by_name(inst_symbol)?;
+        let previous = self.symbol_values.remove_instance(index);
+        if let Some(instance) = instance {
+            self.symbol_values
+                .set_instance(index, instance.handle, instance.class);
+        }
+        previous
+    }
+    pub fn set_current_instance(&mut self, sym_index: usize) {
+        self.current_instance = sym_index;
+        match self.symbol_values.get_instance(sym_index) {
+            Some(instance) => {
+                self.current_instance_handle = instance.handle;
+                self.current_instance_class = Some(instance.class);
+            }
+            None => {
+                self.current_instance_handle.invalidate();
+                self.current_instance_class = None;
+            }
+        }
+    }
+    /// Native class of the instance, `Dynamic` for classes without a native struct
+    pub fn get_instance_class(&self, sym_index: usize) -> Option<InstanceClass> {
+        let sym_table = self.file.get_sym_table();
+        let class = sym_table.get_base_class(sym_index)?;
+        let class_name = sym_table.get_symbol_by_index(class).ok()?.get_name()?;
+        Some(InstanceClass::from_class_name(class_name).unwrap_or(InstanceClass::Dynamic))
+    }
+    /// All instance definitions of the class, `Dynamic` covers every class without a native struct
+    pub fn get_instances_of(&self, instance_class: InstanceClass) -> Vec<usize> {
+        let sym_table = self.file.get_sym_table();
+        let mut instances = vec![];
+        for class in sym_table.get_instantiated_classes() {
+            let class_name = sym_table
+                .get_symbol_by_index(class)
+                .ok()
+                .and_then(|symbol| symbol.get_name())
+                .unwrap_or("");
+            let class_of =
+                InstanceClass::from_class_name(class_name).unwrap_or(InstanceClass::Dynamic);
+            if class_of == instance_class {
+                instances.extend_from_slice(sym_table.get_instances_of_class(class_name));
+            }
+        }
+        instances.sort_unstable();
+        instances
+    }
+    /// Allocates an object of the detected class for the instance symbol and initialises it
+    pub fn create_instance(&mut self, sym_index: usize) -> Result<Handle, String> {
+        let instance_class = match self.get_instance_class(sym_index) {
+            Some(instance_class) => instance_class,
+            None => {
+                return Err(format!(
+                    "Symbol {} is not an instance of a class",
+                    sym_index
+                ))
+            }
+        };
+        let handle = match instance_class {
+            InstanceClass::Dynamic => {
+                let sym_table = self.file.get_sym_table();
+                let class_symbol = sym_table.get_base_class(sym_index).unwrap();
+                self.game_state
+                    .create_dynamic_instance(sym_table, class_symbol)?
+            }
+            _ => self.game_state.create_object(instance_class)?,
+        };
+        self.initialise_instance(handle, sym_index, instance_class)?;
+        Ok(handle)
+    }
+    /// Like `create_instance` but fails if the instance is not of the expected class
+    pub fn create_instance_of(
+        &mut self,
+        sym_index: usize,
+        instance_class: InstanceClass,
+    ) -> Result<Handle, String> {
+        match self.get_instance_class(sym_index) {
+            Some(class) if class == instance_class => self.create_instance(sym_index),
+            class => Err(format!(
+                "Symbol {} is of class {:?}, expected {:?}",
+                sym_index, class, instance_class
+            )),
+        }
+    }
+    /// Binds the symbol to the already allocated object and runs the code of the
+    /// prototype followed by the code of the instance with it as current instance.
+    /// Fails with the first script error, the instance is not registered then.
     pub fn initialise_instance(
-        &self,
+        &mut self,
         handle: Handle,
         sym_index: usize,
         instance_class: InstanceClass,
-    ) {
+    ) -> Result<(), String> {
+        let already_registered = self
+            .symbol_values
+            .get_instance(sym_index)
+            .is_some_and(|instance| instance.class == instance_class);
+        self.game_state
+            .set_instance_symbol(instance_class, &handle, sym_index);
+        self.symbol_values
+            .set_instance(sym_index, handle, instance_class);
+
+        self.push_state();
+        self.set_current_instance(sym_index);
+        let sym_table = self.file.clone();
+        let sym_table = sym_table.get_sym_table();
+        let mut chain = self.get_parent_chain(sym_index);
+        chain.insert(0, sym_index);
+        // outermost parent first, the class itself has no code
+        for index in chain.into_iter().rev() {
+            let symbol = match sym_table.get_symbol_by_index(index) {
+                Ok(symbol) => symbol,
+                Err(_) => continue,
+            };
+            if symbol.properties.get_kind() == Kind::Class {
+                continue;
+            }
+            if let Some(address) = symbol.get_address() {
+                if let Err(error) = self.run_function_at(address.get() as usize) {
+                    self.pop_state();
+                    return Err(format!(
+                        "Initialising {} failed: {}",
+                        symbol.get_name().unwrap_or("Symbol without name"),
+                        error
+                    ));
+                }
+            }
+        }
+        self.pop_state();
+
+        if !already_registered {
+            self.registered_instances
+                .entry(instance_class)
+                .or_default()
+                .push(sym_index);
+        }
+        Ok(())
+    }
+    /// Parents of the symbol up to its class, e.g. the prototype followed by the class
+    pub fn get_parent_chain(&self, sym_index: usize) -> Vec<usize> {
+        let sym_table = self.file.get_sym_table();
+        let mut chain = vec![];
+        let mut index = sym_index;
+        while let Some(parent) = sym_table
+            .get_symbol_by_index(index)
+            .ok()
+            .and_then(|symbol| symbol.get_parent())
+        {
+            let parent = parent.get() as usize;
+            // guards against broken files referencing themselves
+            if chain.contains(&parent) || chain.len() > 8 {
+                break;
+            }
+            chain.push(parent);
+            index = parent;
+        }
+        chain
+    }
+    /// Instance symbols initialised as objects of the class
+    pub fn get_registered_instances_of(&self, instance_class: InstanceClass) -> &[usize] {
+        self.registered_instances
+            .get(&instance_class)
+            .map_or(&[], |instances| instances.as_slice())
+    }
+    pub fn get_current_instance(&self) -> usize {
+        self.current_instance
+    }
+    pub fn get_current_instance_data(&self) -> Option<InstanceData> {
+        self.symbol_values.get_instance(self.current_instance)
+    }
+    pub fn get_current_instance_class(&self) -> Option<InstanceClass> {
+        self.current_instance_class
+    }
+    pub fn get_current_instance_handle(&self) -> Handle {
+        self.current_instance_handle
     }
-    pub fn get_registered_instances_of(&self, instance_class: InstanceClass) -> Vec<usize> {}
-    pub fn get_current_instance_data(&self) -> Box<Data> {}
-    pub fn get_current_instance_class(&self) -> InstanceClass {}
-    pub fn get_current_instance_handle(&self) -> Handle {}
 
-    pub fn get_file(&self) -> File {
-        self.file
+    pub fn get_file(&self) -> &Arc<File> {
+        &self.file
     }
-    pub fn get_game_state(&self) -> GameState {
-        self.game_state
+    pub fn get_symbol_values(&self) -> &SymbolValues {
+        &self.symbol_values
+    }
+    pub fn get_game_state(&self) -> &GameState {
+        &self.game_state
+    }
+    pub fn get_mut_game_state(&mut self) -> &mut GameState {
+        &mut self.game_state
+    }
+    pub fn set_game_externals(&mut self, game_externals: GameExternals) {
+        self.game_state.set_game_externals(game_externals);
     }
 
-    pub fn is_stack_empty(&self) -> bool {}
+    pub fn is_stack_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
     pub fn get_call_stack(&self) -> Vec<String> {
+        let sym_table = self.file.get_sym_table();
         self.call_stack
+            .iter()
+            .map(|frame| {
+                let index = match frame {
+                    CallStackFrame::SymbolIndex(index) => Some(*index),
+                    CallStackFrame::Address(address) => {
+                        sym_table.get_function_index_by_address(*address).ok()
+                    }
+                };
+                match index.and_then(|index| sym_table.get_symbol_by_index(index).ok()) {
+                    Some(symbol) => symbol.get_name().unwrap_or("").to_owned(),
+                    None => format!("<address {}>", frame.get()),
+                }
+            })
+            .collect()
+    }
+    pub fn clear_call_stack(&mut self) {
+        self.call_stack.clear();
+    }
+    /// Executes a single instruction, returns false when the current function returned.
+    /// Errors are logged and end the function like a return.
+    pub fn do_stack(&mut self) -> bool {
+        match self.execute_instruction() {
+            Ok(running) => running,
+            Err(error) => {
+                log::error!("{}", error);
+                false
+            }
+        }
     }
-    pub fn clear_call_stack(&self) {}
-    pub fn do_stack(&self) -> bool {
-        let old_program_counter = self.program_counter;
-        let operator = self.get_current_instruction();
+    /// Executes a single instruction, returns false when the current function returned
+    pub(crate) fn execute_instruction(&mut self) -> Result<bool, String> {
+        let operator = self.get_current_instruction()?;
 
         match operator.get_operator() {
-            Operator::Add => self.push::<i32>(self.pop::<i32>() + self.pop::<i32>()),
-            Operator::Subract => self.push::<i32>(self.pop::<i32>() - self.pop::<i32>()),
-            Operator::Multiply => self.push::<i32>(self.pop::<i32>() * self.pop::<i32>()),
-            Operator::Divide => self.push::<i32>(self.pop::<i32>() / self.pop::<i32>()),
-            Operator::Mod => self.push::<i32>(self.pop::<i32>() % self.pop::<i32>()),
-            Operator::BinOr => self.push::<i32>(self.pop::<i32>() | self.pop::<i32>()),
-            Operator::BinAnd => self.push::<i32>(self.pop::<i32>() & self.pop::<i32>()),
-            Operator::Less => self.push::<i32>(self.pop::<i32>() < self.pop::<i32>() as i32),
-            Operator::Greater => self.push::<i32>(self.pop::<i32>() > self.pop::<i32>() as i32),
-            Operator::AssignFunc | Operator::Assign => {
-                let (prev_addr, next_addr) = (self.pop_var().0, self.pop::<i32>());
-                self.file
-                    .sym_table
-                    .get_symbol_by_index(prev_addr)
-                    .unwrap()
-                    .set_address(next_addr);
+            Operator::Add => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int(a.wrapping_add(b));
+            }
+            Operator::Subract => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int(a.wrapping_sub(b));
+            }
+            Operator::Multiply => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int(a.wrapping_mul(b));
+            }
+            Operator::Divide => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int(a.checked_div(b).unwrap_or(0));
+            }
+            Operator::Mod => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int(a.checked_rem(b).unwrap_or(0));
+            }
+            Operator::BinOr => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int(a | b);
+            }
+            Operator::BinAnd => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int(a & b);
+            }
+            Operator::Less => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int((a < b) as i32);
+            }
+            Operator::Greater => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int((a > b) as i32);
             }
             Operator::LogOr => {
-                let val = (self.pop::<i32>(), self.pop::<i32>());
-                self.push::<i32>(val.0 || val.1 as i32);
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int((a != 0 || b != 0) as i32);
             }
             Operator::LogAnd => {
-                let val = (self.pop::<i32>(), self.pop::<i32>());
-                self.push::<i32>(val.0 && val.1 as i32);
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int((a != 0 && b != 0) as i32);
+            }
+            Operator::ShiftLeft => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int(a.wrapping_shl(b as u32));
+            }
+            Operator::ShiftRight => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int(a.wrapping_shr(b as u32));
+            }
+            Operator::LessOrEqual => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int((a <= b) as i32);
             }
-            Operator::ShiftLeft => self.push::<i32>(self.pop::<i32>() << self.pop::<i32>()),
-            Operator::ShiftRight => self.push::<i32>(self.pop::<i32>() << self.pop::<i32>()),
-            Operator::LessOrEqual => self.push::<i32>(self.pop::<i32>() <= self.pop::<i32>()),
-            Operator::Equal => self.push::<i32>(self.pop::<i32>() == self.pop::<i32>()),
-            Operator::NotEqual => self.push::<i32>(self.pop::<i32>() != self.pop::<i32>()),
-            Operator::GreaterOrEqual => self.push::<i32>(self.pop::<i32>() >= self.pop::<i32>()),
-            Operator::AssignAdd => {}
-            _ => (),
+            Operator::Equal => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int((a == b) as i32);
+            }
+            Operator::NotEqual => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int((a != b) as i32);
+            }
+            Operator::GreaterOrEqual => {
+                let (a, b) = (self.pop_data_value(), self.pop_data_value());
+                self.push_int((a >= b) as i32);
+            }
+            Operator::Assign | Operator::AssignFunc => {
+                let (index, array_index) = self.pop_var();
+                let value = self.pop_data_value();
+                self.set_int_value(index, array_index, value);
+            }
+            Operator::AssignAdd
+            | Operator::AssignSubtract
+            | Operator::AssignMultiply
+            | Operator::AssignDivide => {
+                let (index, array_index) = self.pop_var();
+                let value = self.pop_data_value();
+                let old = self.get_int_value(index, array_index);
+                let new = match operator.get_operator() {
+                    Operator::AssignAdd => old.wrapping_add(value),
+                    Operator::AssignSubtract => old.wrapping_sub(value),
+                    Operator::AssignMultiply => old.wrapping_mul(value),
+                    _ => old.checked_div(value).unwrap_or(0),
+                };
+                self.set_int_value(index, array_index, new);
+            }
+            Operator::AssignString => {
+                let (index, array_index) = self.pop_var();
+                let (source, source_array_index) = self.pop_var();
+                let value = self.get_string_value(source, source_array_index);
+                self.set_string_value(index, array_index, value.as_str());
+            }
+            Operator::AssignStringRef => {
+                log::warn!("AssignStringRef is not supported");
+            }
+            Operator::AssignFloat => {
+                let (index, array_index) = self.pop_var();
+                let value = self.pop_float().unwrap_or(0.0);
+                self.set_float_value(index, array_index, value);
+            }
+            Operator::AssignInstance => {
+                let (index, _) = self.pop_var();
+                let (source, _) = self.pop_var();
+                match self.symbol_values.get_instance(source) {
+                    Some(instance) => {
+                        self.symbol_values
+                            .set_instance(index, instance.handle, instance.class)
+                    }
+                    None => {
+                        self.symbol_values.remove_instance(index);
+                    }
+                }
+            }
+            Operator::Plus => {
+                let a = self.pop_data_value();
+                self.push_int(a);
+            }
+            Operator::Minus => {
+                let a = self.pop_data_value();
+                self.push_int(a.wrapping_neg());
+            }
+            Operator::Not => {
+                let a = self.pop_data_value();
+                self.push_int((a == 0) as i32);
+            }
+            Operator::Negate => {
+                let a = self.pop_data_value();
+                self.push_int(!a);
+            }
+            Operator::Ret => return Ok(false),
+            Operator::Call => {
+                // instance and prototype code relies on the current instance surviving calls
+                let (current_instance, handle, class) = (
+                    self.current_instance,
+                    self.current_instance_handle,
+                    self.current_instance_class,
+                );
+                let address = operator.get_address();
+                let hooked = match self.hooks_by_index.is_empty() {
+                    true => None,
+                    false => self
+                        .file
+                        .get_sym_table()
+                        .get_function_index_by_address(address)
+                        .ok()
+                        .filter(|index| self.is_hooked(*index)),
+                };
+                let result = match hooked {
+                    Some(index) => {
+                        let return_address = self.program_counter;
+                        CallStackFrame::SymbolIndex(index).insert_in_vm(self);
+                        self.call_hooked_function(index);
+                        self.call_stack.pop();
+                        self.set_program_counter(return_address);
+                        Ok(())
+                    }
+                    None => self.run_function_at(address),
+                };
+                self.current_instance = current_instance;
+                self.current_instance_handle = handle;
+                self.current_instance_class = class;
+                result?;
+            }
+            Operator::CallExternal => {
+                let index = operator.get_symbol();
+                if let Some(callback) = &mut self.on_external_called {
+                    callback(index);
+                }
+                // The external is taken out while running, so it can borrow the vm mutably
+                match self.externals_by_index.remove(&index) {
+                    Some(mut func) => {
+                        func(self);
+                        self.externals_by_index.entry(index).or_insert(func);
+                    }
+                    None => {
+                        let name = self
+                            .file
+                            .get_sym_table()
+                            .get_symbol_by_index(index)
+                            .ok()
+                            .and_then(|symbol| symbol.get_name())
+                            .unwrap_or("");
+                        log::warn!("External {} is not registered", name);
+                    }
+                }
+            }
+            Operator::PushInt => self.push_int(operator.get_value()),
+            Operator::PushVar | Operator::PushInstance => self.push_var(operator.get_symbol(), 0),
+            Operator::PushArrayVar => self.push_var(operator.get_symbol(), operator.get_index()),
+            Operator::Jump => self.set_program_counter(operator.get_address()),
+            Operator::JumpIf => {
+                if self.pop_data_value() == 0 {
+                    self.set_program_counter(operator.get_address());
+                }
+            }
+            Operator::SetInstance => self.set_current_instance(operator.get_symbol()),
         }
+        Ok(true)
+    }
+    fn pop_data_value(&mut self) -> i32 {
+        self.pop_int().unwrap_or(0)
     }
 
     //pub fn set_on_symbol_value_changed_callback(&self, func: &dyn Fn(u32, Operator)) {}
-    pub fn set_on_external_called_callback(&self, func: Fn(u32)) {}
+    pub fn set_on_external_called_callback<F>(&mut self, func: F)
+    where
+        F: FnMut(usize) + Send + 'static,
+    {
+        self.on_external_called = Some(Box::new(func));
+    }
 }
@@ -0,0 +1,127 @@
+use super::file::Kind;
+use super::VirtualMachine;
+use crate::stdlib::InstanceClass;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use zen_memory::Handle;
+
+/// Classes the engine materialises at startup
+pub const STATIC_CLASSES: [InstanceClass; 8] = [
+    InstanceClass::Info,
+    InstanceClass::ItemReact,
+    InstanceClass::Focus,
+    InstanceClass::Sfx,
+    InstanceClass::Pfx,
+    InstanceClass::MusicTheme,
+    InstanceClass::Menu,
+    InstanceClass::MenuItem,
+];
+
+/// Objects of one class by the name of their instance symbol
+#[derive(Default)]
+pub struct InstanceRegistry {
+    handles_by_name: HashMap<String, Handle>,
+}
+
+impl InstanceRegistry {
+    pub fn get(&self, name: &str) -> Option<Handle> {
+        self.handles_by_name.get(name).copied()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Handle)> {
+        self.handles_by_name
+            .iter()
+            .map(|(name, handle)| (name.as_str(), *handle))
+    }
+    pub fn len(&self) -> usize {
+        self.handles_by_name.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.handles_by_name.is_empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PreloadFailure {
+    pub instance: String,
+    pub error: String,
+}
+
+pub struct PreloadReport {
+    pub registries: HashMap<InstanceClass, InstanceRegistry>,
+    pub failures: Vec<PreloadFailure>,
+    pub duration: Duration,
+}
+
+impl PreloadReport {
+    pub fn get_registry(&self, instance_class: InstanceClass) -> Option<&InstanceRegistry> {
+        self.registries.get(&instance_class)
+    }
+}
+
+impl VirtualMachine {
+    /// Initialises every instance of the classes, failures are collected instead of aborting
+    pub fn preload_instances(&mut self, classes: &[InstanceClass]) -> PreloadReport {
+        let start = Instant::now();
+        let mut registries = HashMap::new();
+        let mut failures = vec![];
+        for instance_class in classes.iter() {
+            let mut registry = InstanceRegistry::default();
+            for index in self.get_instances_of(*instance_class) {
+                let name = self
+                    .file
+                    .get_sym_table()
+                    .get_symbol_by_index(index)
+                    .ok()
+                    .and_then(|symbol| symbol.get_name())
+                    .unwrap_or("")
+                    .to_owned();
+                if let Err(error) = self.check_instance_code(index) {
+                    failures.push(PreloadFailure {
+                        instance: name,
+                        error,
+                    });
+                    continue;
+                }
+                match self.create_instance(index) {
+                    Ok(handle) => {
+                        registry.handles_by_name.insert(name, handle);
+                    }
+                    Err(error) => failures.push(PreloadFailure {
+                        instance: name,
+                        error,
+                    }),
+                }
+            }
+            registries.insert(*instance_class, registry);
+        }
+        PreloadReport {
+            registries,
+            failures,
+            duration: start.elapsed(),
+        }
+    }
+    /// Fails if the instance or one of its prototypes has no code to initialise it
+    fn check_instance_code(&self, sym_index: usize) -> Result<(), String> {
+        let sym_table = self.file.get_sym_table();
+        let mut chain = self.get_parent_chain(sym_index);
+        chain.insert(0, sym_index);
+        for index in chain {
+            let symbol = sym_table.get_symbol_by_index(index)?;
+            let kind = symbol.properties.get_kind();
+            if kind != Kind::Instance && kind != Kind::Prototype {
+                continue;
+            }
+            if symbol.get_address().is_none() {
+                return Err(format!(
+                    "{} has no code",
+                    symbol.get_name().unwrap_or("Symbol without name")
+                ));
+            }
+        }
+        Ok(())
+    }
+    /// Initialises all instances of `STATIC_CLASSES`
+    pub fn preload_static_instances(&mut self) -> PreloadReport {
+        self.preload_instances(&STATIC_CLASSES)
+    }
+}
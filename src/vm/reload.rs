@@ -0,0 +1,214 @@
+use super::file::symbol::Data;
+use super::file::{Flag, Kind};
+use super::{File, VirtualMachine};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Value which could not be carried over by a reload
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReloadConflict {
+    SymbolRemoved { name: String },
+    KindChanged { name: String, old: Kind, new: Kind },
+    ArraySizeChanged { name: String, old: u32, new: u32 },
+    ExternalRemoved { name: String },
+}
+
+impl VirtualMachine {
+    /// Swaps in a recompiled file. Changed globals are carried over by name,
+    /// instance bindings, externals and hooks are moved to the new symbol indices.
+    /// Fails if a script is running.
+    pub fn reload_file(&mut self, file: Arc<File>) -> Result<Vec<ReloadConflict>, String> {
+        if !self.call_stack.is_empty() || !self.state_stack.is_empty() {
+            return Err("Cannot reload while a script is running".to_owned());
+        }
+        let old_file = std::mem::replace(&mut self.file, file);
+        let new_file = self.file.clone();
+        let (old_table, new_table) = (old_file.get_sym_table(), new_file.get_sym_table());
+        let name_of = |index: usize| {
+            old_table
+                .get_symbol_by_index(index)
+                .ok()
+                .and_then(|symbol| symbol.get_name())
+        };
+        let remap =
+            |index: usize| name_of(index).and_then(|name| new_table.get_symbol_index_by_name(name));
+        let mut conflicts = vec![];
+
+        let old_values = self.symbol_values.take();
+        for (index, data) in old_values.iter_data() {
+            // fake string symbols are created again below
+            if *index >= old_table.len() {
+                continue;
+            }
+            let name = name_of(*index).unwrap_or("").to_owned();
+            let new_index = match remap(*index) {
+                Some(new_index) => new_index,
+                None => {
+                    conflicts.push(ReloadConflict::SymbolRemoved { name });
+                    continue;
+                }
+            };
+            let old_properties = &old_table.get_symbol_by_index(*index).unwrap().properties;
+            let new_properties = &new_table.get_symbol_by_index(new_index).unwrap().properties;
+            if new_properties.has_flag(Flag::Const) {
+                continue;
+            }
+            if old_properties.get_kind() != new_properties.get_kind() {
+                conflicts.push(ReloadConflict::KindChanged {
+                    name,
+                    old: old_properties.get_kind(),
+                    new: new_properties.get_kind(),
+                });
+                continue;
+            }
+            if old_properties.get_count() != new_properties.get_count() {
+                conflicts.push(ReloadConflict::ArraySizeChanged {
+                    name,
+                    old: old_properties.get_count(),
+                    new: new_properties.get_count(),
+                });
+                continue;
+            }
+            let data = match (new_properties.get_kind(), data) {
+                // function variables point to function symbols
+                (Kind::Func, Data::IntSequence(vec)) => Data::IntSequence(
+                    vec.iter()
+                        .map(|value| remap(*value as usize).unwrap_or(0) as i32)
+                        .collect(),
+                ),
+                _ => data.clone(),
+            };
+            self.symbol_values.insert_data(new_index, data);
+        }
+        for (index, instance) in old_values.iter_instances() {
+            if let Some(new_index) = remap(*index) {
+                self.symbol_values
+                    .set_instance(new_index, instance.handle, instance.class);
+            }
+        }
+
+        self.fake_string_symbols.clear();
+        self.create_fake_string_symbols();
+        self.bind_class_members();
+        self.update_class_mismatches();
+
+        let registered_instances = std::mem::take(&mut self.registered_instances);
+        for (instance_class, instances) in registered_instances {
+            let instances = instances.into_iter().filter_map(&remap).collect();
+            self.registered_instances.insert(instance_class, instances);
+        }
+        self.current_instance = remap(self.current_instance).unwrap_or(0);
+        self.game_state.remap_instance_symbols(remap);
+
+        let externals = std::mem::take(&mut self.externals_by_index);
+        let mut externals_by_index = HashMap::with_capacity(externals.len());
+        for (index, func) in externals {
+            match remap(index) {
+                Some(new_index) => {
+                    externals_by_index.insert(new_index, func);
+                }
+                None => conflicts.push(ReloadConflict::ExternalRemoved {
+                    name: name_of(index).unwrap_or("").to_owned(),
+                }),
+            }
+        }
+        self.externals_by_index = externals_by_index;
+
+        let hooks = std::mem::take(&mut self.hooks_by_index);
+        self.hooks_by_index = hooks
+            .into_iter()
+            .filter_map(|(index, hook)| remap(index).map(|new_index| (new_index, hook)))
+            .collect();
+        self.program_counter = 0;
+        self.stack.clear();
+
+        Ok(conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::file::test_file::{op, TestFile};
+    use crate::vm::file::Operator;
+    use enumflags2::BitFlags;
+
+    fn old_file() -> Arc<File> {
+        let mut test_file = TestFile::new();
+        test_file.var("COUNTER", Kind::Int, 1);
+        test_file.var("CHANGED", Kind::Int, 1);
+        test_file.var("LIST", Kind::Int, 2);
+        test_file.var("GONE", Kind::Int, 1);
+        test_file.func("TARGET", BitFlags::empty(), &[], &[op(Operator::Ret)]);
+        test_file.func_var("HANDLER");
+        test_file.build()
+    }
+
+    /// Symbols moved by an inserted variable, `CHANGED` and `LIST` changed their type
+    fn new_file() -> Arc<File> {
+        let mut test_file = TestFile::new();
+        test_file.var("INSERTED", Kind::Int, 1);
+        test_file.var("COUNTER", Kind::Int, 1);
+        test_file.var("CHANGED", Kind::Float, 1);
+        test_file.var("LIST", Kind::Int, 3);
+        test_file.func("TARGET", BitFlags::empty(), &[], &[op(Operator::Ret)]);
+        test_file.func_var("HANDLER");
+        test_file.build()
+    }
+
+    #[test]
+    fn values_are_carried_over_by_name() {
+        let mut virtual_machine = VirtualMachine::from_file(old_file());
+        virtual_machine.set_int("COUNTER", 5).unwrap();
+        let target = virtual_machine.get_func("TARGET").unwrap();
+        let handler = virtual_machine
+            .file
+            .get_sym_table()
+            .get_symbol_index_by_name("HANDLER")
+            .unwrap();
+        virtual_machine.set_int_value(handler, 0, target as i32);
+
+        assert!(virtual_machine.reload_file(new_file()).unwrap().is_empty());
+        assert_eq!(virtual_machine.get_int("COUNTER"), Ok(5));
+        let target = virtual_machine.get_func("TARGET").unwrap();
+        assert_eq!(virtual_machine.get_func("HANDLER"), Ok(target));
+    }
+
+    #[test]
+    fn changed_symbols_are_reported() {
+        let mut virtual_machine = VirtualMachine::from_file(old_file());
+        virtual_machine.set_int("CHANGED", 1).unwrap();
+        virtual_machine.set_int_at("LIST", 1, 2).unwrap();
+        virtual_machine.set_int("GONE", 3).unwrap();
+
+        let mut conflicts = virtual_machine.reload_file(new_file()).unwrap();
+        conflicts.sort_by_key(|conflict| format!("{:?}", conflict));
+        assert_eq!(
+            conflicts,
+            vec![
+                ReloadConflict::ArraySizeChanged {
+                    name: "LIST".to_owned(),
+                    old: 2,
+                    new: 3,
+                },
+                ReloadConflict::KindChanged {
+                    name: "CHANGED".to_owned(),
+                    old: Kind::Int,
+                    new: Kind::Float,
+                },
+                ReloadConflict::SymbolRemoved {
+                    name: "GONE".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(virtual_machine.get_float("CHANGED"), Ok(0.0));
+        assert_eq!(virtual_machine.get_int_at("LIST", 1), Ok(0));
+    }
+
+    #[test]
+    fn running_scripts_block_the_reload() {
+        let mut virtual_machine = VirtualMachine::from_file(old_file());
+        virtual_machine.prepare_run_func();
+        assert!(virtual_machine.reload_file(new_file()).is_err());
+    }
+}
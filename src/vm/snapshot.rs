@@ -0,0 +1,186 @@
+use super::{StackValue, VirtualMachine, VirtualMachineState};
+use crate::game_state::{GameExternals, GameState, HandleSlots};
+use crate::snapshot::{SnapshotReader, SnapshotWriter, SNAPSHOT_MAGIC, SNAPSHOT_VERSION};
+use crate::stdlib::InstanceClass;
+use std::collections::HashMap;
+use zen_memory::Handle;
+
+fn write_stack(writer: &mut SnapshotWriter, stack: &[StackValue]) {
+    let values: Vec<u32> = stack.iter().map(|value| value.get()).collect();
+    writer.write(&values);
+}
+
+fn read_stack(reader: &mut SnapshotReader) -> Result<Vec<StackValue>, String> {
+    let values: Vec<u32> = reader.read()?;
+    Ok(values.into_iter().map(StackValue::from).collect())
+}
+
+fn write_current_instance(
+    writer: &mut SnapshotWriter,
+    slots: &HandleSlots,
+    index: usize,
+    handle: &Handle,
+    instance_class: Option<InstanceClass>,
+) {
+    writer.write(&index);
+    writer.write(&instance_class);
+    if let Some(instance_class) = instance_class {
+        slots.write_handle(writer, instance_class, handle);
+    }
+}
+
+fn read_current_instance(
+    reader: &mut SnapshotReader,
+    game_state: &GameState,
+) -> Result<(usize, Handle, Option<InstanceClass>), String> {
+    let index = reader.read()?;
+    let instance_class: Option<InstanceClass> = reader.read()?;
+    let handle = match instance_class {
+        Some(instance_class) => game_state.read_handle(reader, instance_class)?,
+        None => None,
+    };
+    let handle = handle.unwrap_or_else(|| {
+        let mut handle = Handle::new();
+        handle.invalidate();
+        handle
+    });
+    Ok((index, handle, instance_class))
+}
+
+impl VirtualMachine {
+    /// Writes every value changed at runtime, the game state and the suspended stacks.
+    /// A snapshot can only be loaded by a virtual machine using the same file.
+    pub fn save_snapshot(&self) -> Vec<u8> {
+        let mut writer = SnapshotWriter::new();
+        writer.write_bytes(&SNAPSHOT_MAGIC);
+        writer.write(&SNAPSHOT_VERSION);
+        writer.write(&self.file.get_sym_table().len());
+
+        let slots = self.game_state.get_handle_slots();
+        self.game_state.write_snapshot(&mut writer, &slots);
+
+        let data: Vec<_> = self.symbol_values.iter_data().collect();
+        writer.write(&(data.len() as u32));
+        for (index, data) in data {
+            writer.write(index);
+            writer.write(data);
+        }
+        let instances: Vec<_> = self.symbol_values.iter_instances().collect();
+        writer.write(&(instances.len() as u32));
+        for (index, instance) in instances {
+            writer.write(index);
+            writer.write(&instance.class);
+            slots.write_handle(&mut writer, instance.class, &instance.handle);
+        }
+        writer.write(&(self.registered_instances.len() as u32));
+        for (instance_class, instances) in self.registered_instances.iter() {
+            writer.write(instance_class);
+            writer.write(instances);
+        }
+
+        write_current_instance(
+            &mut writer,
+            &slots,
+            self.current_instance,
+            &self.current_instance_handle,
+            self.current_instance_class,
+        );
+        writer.write(&self.program_counter);
+        write_stack(&mut writer, &self.stack);
+        writer.write(&self.call_stack);
+        writer.write(&(self.state_stack.len() as u32));
+        for state in self.state_stack.iter() {
+            write_current_instance(
+                &mut writer,
+                &slots,
+                state.current_instance,
+                &state.current_instance_handle,
+                state.current_instance_class,
+            );
+            writer.write(&state.program_counter);
+            write_stack(&mut writer, &state.stack);
+        }
+        writer.into_bytes()
+    }
+    /// Replaces the runtime state with a snapshot, the vm is left untouched if the snapshot is invalid.
+    /// Registered externals and callbacks are kept.
+    pub fn load_snapshot(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let mut reader = SnapshotReader::new(bytes);
+        if reader.read_bytes(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+            return Err("Not a virtual machine snapshot".to_owned());
+        }
+        let version = reader.read::<u32>()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(format!("Unsupported snapshot version {}", version));
+        }
+        let sym_count = reader.read::<usize>()?;
+        if sym_count != self.file.get_sym_table().len() {
+            return Err(format!(
+                "Snapshot was taken with {} symbols, but file has {}",
+                sym_count,
+                self.file.get_sym_table().len()
+            ));
+        }
+
+        let mut game_state = GameState::new(GameExternals::new());
+        game_state.read_snapshot(&mut reader)?;
+
+        let mut data = vec![];
+        for _ in 0..reader.read::<u32>()? {
+            data.push((reader.read::<usize>()?, reader.read()?));
+        }
+        let mut instances = vec![];
+        for _ in 0..reader.read::<u32>()? {
+            let index = reader.read::<usize>()?;
+            let instance_class = reader.read::<InstanceClass>()?;
+            if let Some(handle) = game_state.read_handle(&mut reader, instance_class)? {
+                instances.push((index, handle, instance_class));
+            }
+        }
+        let mut registered_instances = HashMap::new();
+        for _ in 0..reader.read::<u32>()? {
+            let instance_class = reader.read::<InstanceClass>()?;
+            registered_instances.insert(instance_class, reader.read()?);
+        }
+
+        let current_instance = read_current_instance(&mut reader, &game_state)?;
+        let program_counter = reader.read()?;
+        let stack = read_stack(&mut reader)?;
+        let call_stack = reader.read()?;
+        let mut state_stack = vec![];
+        for _ in 0..reader.read::<u32>()? {
+            let (current_instance, current_instance_handle, current_instance_class) =
+                read_current_instance(&mut reader, &game_state)?;
+            state_stack.push(VirtualMachineState {
+                current_instance,
+                current_instance_handle,
+                current_instance_class,
+                program_counter: reader.read()?,
+                stack: read_stack(&mut reader)?,
+            });
+        }
+        if !reader.is_at_end() {
+            return Err("Snapshot contains trailing data".to_owned());
+        }
+
+        game_state.set_game_externals(self.game_state.take_game_externals());
+        self.game_state = game_state;
+        self.symbol_values.clear();
+        for (index, data) in data {
+            self.symbol_values.insert_data(index, data);
+        }
+        for (index, handle, instance_class) in instances {
+            self.symbol_values
+                .set_instance(index, handle, instance_class);
+        }
+        self.registered_instances = registered_instances;
+        self.current_instance = current_instance.0;
+        self.current_instance_handle = current_instance.1;
+        self.current_instance_class = current_instance.2;
+        self.program_counter = program_counter;
+        self.stack = stack;
+        self.call_stack = call_stack;
+        self.state_stack = state_stack;
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use super::file::sym_table::SymTable;
+use super::file::symbol::Data;
+use super::file::Kind;
+use crate::stdlib::InstanceClass;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use zen_memory::Handle;
+
+/// Object an instance symbol is bound to
+#[derive(Copy, Clone)]
+pub struct InstanceData {
+    pub handle: Handle,
+    pub class: InstanceClass,
+}
+
+impl InstanceData {
+    pub fn new(handle: Handle, class: InstanceClass) -> Self {
+        Self { handle, class }
+    }
+}
+
+/// Values of the symbols which are changed while running a virtual machine.
+/// The symbol table itself is shared, a value is copied out of it on the first write.
+#[derive(Default)]
+pub struct SymbolValues {
+    data: HashMap<usize, Data>,
+    instances: HashMap<usize, InstanceData>,
+}
+
+impl SymbolValues {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn get_data<'a>(&'a self, sym_table: &'a SymTable, index: usize) -> Option<&'a Data> {
+        match self.data.get(&index) {
+            Some(data) => Some(data),
+            None => sym_table.get_symbol_by_index(index).ok()?.get_data(),
+        }
+    }
+    pub fn get_mut_data(&mut self, sym_table: &SymTable, index: usize) -> Option<&mut Data> {
+        let entry = match self.data.entry(index) {
+            Entry::Occupied(entry) => return Some(entry.into_mut()),
+            Entry::Vacant(entry) => entry,
+        };
+        let symbol = sym_table.get_symbol_by_index(index).ok()?;
+        let data = match symbol.get_data() {
+            Some(data) => data.clone(),
+            // function variables hold the symbol index of the function they point to,
+            // zero until a function is assigned
+            None if symbol.properties.get_kind() == Kind::Func => {
+                let count = symbol.properties.get_count().max(1) as usize;
+                Data::IntSequence(vec![0; count])
+            }
+            None => return None,
+        };
+        Some(entry.insert(data))
+    }
+    /// Sets data for an index which may not exist in the symbol table
+    pub fn insert_data(&mut self, index: usize, data: Data) {
+        self.data.insert(index, data);
+    }
+    pub fn get_instance(&self, index: usize) -> Option<InstanceData> {
+        self.instances.get(&index).copied()
+    }
+    pub fn set_instance(&mut self, index: usize, handle: Handle, class: InstanceClass) {
+        self.instances.insert(index, InstanceData { handle, class });
+    }
+    pub fn remove_instance(&mut self, index: usize) -> Option<InstanceData> {
+        self.instances.remove(&index)
+    }
+    /// Values which differ from the symbol table
+    pub fn iter_data(&self) -> impl Iterator<Item = (&usize, &Data)> {
+        self.data.iter()
+    }
+    pub fn iter_instances(&self) -> impl Iterator<Item = (&usize, &InstanceData)> {
+        self.instances.iter()
+    }
+    pub fn take(&mut self) -> SymbolValues {
+        std::mem::take(self)
+    }
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.instances.clear();
+    }
+}